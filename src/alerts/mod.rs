@@ -0,0 +1,215 @@
+use crate::indicators::{self, IndicatorConfig};
+use crate::CandleData;
+use std::collections::VecDeque;
+
+const MAX_LOG_ENTRIES: usize = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+impl Comparison {
+    pub fn to_display_string(self) -> &'static str {
+        match self {
+            Comparison::Above => "above",
+            Comparison::Below => "below",
+        }
+    }
+
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::Below => value < threshold,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    PriceCross { level: f64 },                 // Either direction
+    PercentMove { percent: f64, minutes: u32 }, // Up or down within the last `minutes`
+    Indicator { config: IndicatorConfig, comparison: Comparison, threshold: f64 }, // First output, e.g. RSI above 70
+}
+
+impl Condition {
+    pub fn describe(&self) -> String {
+        match self {
+            Condition::PriceCross { level } => format!("Price crosses {:.2}", level),
+            Condition::PercentMove { percent, minutes } => format!("Moves {:.2}% within {} min", percent, minutes),
+            Condition::Indicator { config, comparison, threshold } => {
+                format!("{} {} {:.2}", config.name(), comparison.to_display_string(), threshold)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub id: u64,
+    pub symbol: String,
+    pub condition: Condition,
+    pub active: bool, // Cleared once it triggers
+    last_price: Option<f64>, // For detecting a cross, kept current while inactive too
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertEvent {
+    pub time: f64, // Seconds
+    pub symbol: String,
+    pub message: String,
+    pub price: f64,
+}
+
+// Alerts for every symbol, the log of those that triggered, and the recent
+// prices percent-move alerts look back over
+#[derive(Default)]
+pub struct AlertBook {
+    pub alerts: Vec<Alert>,
+    pub log: VecDeque<AlertEvent>, // Oldest first
+    next_id: u64,
+    prices: VecDeque<(f64, f64)>, // Time and price of the symbol last checked
+    prices_symbol: String,
+}
+
+impl AlertBook {
+    pub fn add(&mut self, symbol: &str, condition: Condition) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.alerts.push(Alert { id, symbol: symbol.to_string(), condition, active: true, last_price: None });
+        id
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        self.alerts.retain(|alert| alert.id != id);
+    }
+
+    // Check the active alerts of `symbol` against its candles as of `now`.
+    // Alerts that trigger are deactivated, logged and returned.
+    pub fn check(&mut self, symbol: &str, now: f64, candles: &VecDeque<CandleData>) -> Vec<AlertEvent> {
+        let price = match candles.back() {
+            Some(last) => last.close,
+            None => return Vec::new(),
+        };
+
+        if self.prices_symbol != symbol {
+            self.prices.clear();
+            self.prices_symbol = symbol.to_string();
+        }
+        self.prices.push_back((now, price));
+        let lookback = self
+            .alerts
+            .iter()
+            .filter_map(|alert| match alert.condition {
+                Condition::PercentMove { minutes, .. } if alert.active && alert.symbol == symbol => Some(minutes),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        while self.prices.front().is_some_and(|(time, _)| *time < now - lookback as f64 * 60.0) {
+            self.prices.pop_front();
+        }
+
+        let mut triggered = Vec::new();
+        for alert in self.alerts.iter_mut().filter(|alert| alert.symbol == symbol) {
+            let hit = alert.active && match &alert.condition {
+                Condition::PriceCross { level } => {
+                    alert.last_price.is_some_and(|last| (last < *level) != (price < *level))
+                }
+                Condition::PercentMove { percent, minutes } => self
+                    .prices
+                    .iter()
+                    .filter(|(time, _)| *time >= now - *minutes as f64 * 60.0)
+                    .any(|(_, from)| ((price - from) / from).abs() * 100.0 >= *percent),
+                Condition::Indicator { config, comparison, threshold } => indicators::last_values(config, candles)
+                    .first()
+                    .copied()
+                    .flatten()
+                    .is_some_and(|value| comparison.holds(value, *threshold)),
+            };
+            alert.last_price = Some(price);
+
+            if hit {
+                alert.active = false;
+                triggered.push(AlertEvent {
+                    time: now,
+                    symbol: symbol.to_string(),
+                    message: alert.condition.describe(),
+                    price,
+                });
+            }
+        }
+
+        self.log.extend(triggered.iter().cloned());
+        while self.log.len() > MAX_LOG_ENTRIES {
+            self.log.pop_front();
+        }
+        triggered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candles(closes: &[f64]) -> VecDeque<CandleData> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| CandleData {
+                timestamp: i as f64 * 60.0,
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn price_cross_triggers_once_in_either_direction() {
+        let mut book = AlertBook::default();
+        book.add("BTCUSDT", Condition::PriceCross { level: 100.0 });
+        book.add("BTCUSDT", Condition::PriceCross { level: 90.0 });
+        book.add("ETHUSDT", Condition::PriceCross { level: 100.0 });
+
+        // The first price only sets where it started
+        assert!(book.check("BTCUSDT", 0.0, &candles(&[95.0])).is_empty());
+        let events = book.check("BTCUSDT", 1.0, &candles(&[95.0, 100.0]));
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].message.as_str(), events[0].price), ("Price crosses 100.00", 100.0));
+        assert!(book.check("BTCUSDT", 2.0, &candles(&[95.0, 100.0, 99.0])).is_empty());
+
+        let events = book.check("BTCUSDT", 3.0, &candles(&[95.0, 100.0, 99.0, 89.0]));
+        assert_eq!(events[0].message, "Price crosses 90.00");
+        assert_eq!(book.log.len(), 2);
+        assert!(book.alerts.iter().all(|alert| !alert.active || alert.symbol == "ETHUSDT"));
+    }
+
+    #[test]
+    fn percent_move_only_looks_back_its_window() {
+        let mut book = AlertBook::default();
+        book.add("BTCUSDT", Condition::PercentMove { percent: 2.0, minutes: 5 });
+
+        assert!(book.check("BTCUSDT", 0.0, &candles(&[100.0])).is_empty());
+        assert!(book.check("BTCUSDT", 240.0, &candles(&[99.5])).is_empty());
+        // 2% below the first price, but that is now over five minutes ago
+        assert!(book.check("BTCUSDT", 360.0, &candles(&[98.0])).is_empty());
+        let events = book.check("BTCUSDT", 420.0, &candles(&[96.0]));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].price, 96.0);
+    }
+
+    #[test]
+    fn indicator_condition_uses_the_latest_value() {
+        let mut book = AlertBook::default();
+        let config = IndicatorConfig::Sma { period: 3 };
+        book.add("BTCUSDT", Condition::Indicator { config, comparison: Comparison::Above, threshold: 102.0 });
+
+        assert!(book.check("BTCUSDT", 0.0, &candles(&[100.0, 101.0, 102.0, 103.0])).is_empty()); // 102
+        let events = book.check("BTCUSDT", 60.0, &candles(&[100.0, 101.0, 102.0, 103.0, 104.0])); // 103
+        assert_eq!(events[0].message, "MA 3 above 102.00");
+    }
+}
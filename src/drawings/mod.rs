@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Retracement ratios, measured back from the second anchor toward the first
+pub const FIBONACCI_RATIOS: [f64; 7] = [0.0, 0.236, 0.382, 0.5, 0.618, 0.786, 1.0];
+
+// A point on the price chart
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Anchor {
+    pub time: f64, // Seconds
+    pub price: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    Trendline,
+    HorizontalRay,
+    Fibonacci,
+    Rectangle,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 4] = [Tool::Trendline, Tool::HorizontalRay, Tool::Fibonacci, Tool::Rectangle];
+
+    pub fn to_display_string(self) -> &'static str {
+        match self {
+            Tool::Trendline => "Trendline",
+            Tool::HorizontalRay => "Ray",
+            Tool::Fibonacci => "Fib",
+            Tool::Rectangle => "Rectangle",
+        }
+    }
+
+    // Clicks needed to place one
+    pub fn anchor_count(self) -> usize {
+        match self {
+            Tool::HorizontalRay => 1,
+            _ => 2,
+        }
+    }
+
+    // The shape once all its anchors are placed
+    pub fn shape(self, anchors: &[Anchor]) -> Option<Shape> {
+        match (self, anchors) {
+            (Tool::Trendline, &[start, end]) => Some(Shape::Trendline { start, end }),
+            (Tool::HorizontalRay, &[start]) => Some(Shape::HorizontalRay { start }),
+            (Tool::Fibonacci, &[start, end]) => Some(Shape::Fibonacci { start, end }),
+            (Tool::Rectangle, &[start, end]) => Some(Shape::Rectangle { start, end }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Shape {
+    Trendline { start: Anchor, end: Anchor },
+    HorizontalRay { start: Anchor }, // Extends right from its anchor
+    Fibonacci { start: Anchor, end: Anchor },
+    Rectangle { start: Anchor, end: Anchor }, // Opposite corners
+}
+
+impl Shape {
+    pub fn anchors(&self) -> Vec<Anchor> {
+        match self {
+            Shape::HorizontalRay { start } => vec![*start],
+            Shape::Trendline { start, end } | Shape::Fibonacci { start, end } | Shape::Rectangle { start, end } => {
+                vec![*start, *end]
+            }
+        }
+    }
+
+    pub fn anchors_mut(&mut self) -> Vec<&mut Anchor> {
+        match self {
+            Shape::HorizontalRay { start } => vec![start],
+            Shape::Trendline { start, end } | Shape::Fibonacci { start, end } | Shape::Rectangle { start, end } => {
+                vec![start, end]
+            }
+        }
+    }
+
+    // Shift every anchor, e.g. when the whole drawing is dragged
+    pub fn translate(&mut self, dt: f64, dprice: f64) {
+        for anchor in self.anchors_mut() {
+            anchor.time += dt;
+            anchor.price += dprice;
+        }
+    }
+
+    // Screen distance from `point` to the drawing's outline, given a mapping
+    // from chart to screen coordinates. Zero anywhere inside a rectangle.
+    pub fn distance(&self, point: (f64, f64), to_screen: impl Fn(Anchor) -> (f64, f64)) -> f64 {
+        match self {
+            Shape::Trendline { start, end } => segment_distance(point, to_screen(*start), to_screen(*end)),
+            Shape::HorizontalRay { start } => {
+                let (x, y) = to_screen(*start);
+                segment_distance(point, (x, y), (x.max(point.0), y))
+            }
+            Shape::Fibonacci { start, end } => fibonacci_levels(*start, *end)
+                .into_iter()
+                .map(|(_, price)| {
+                    let from = to_screen(Anchor { time: start.time, price });
+                    let to = to_screen(Anchor { time: end.time, price });
+                    segment_distance(point, from, to)
+                })
+                .fold(f64::INFINITY, f64::min),
+            Shape::Rectangle { start, end } => {
+                let (x0, y0) = to_screen(*start);
+                let (x1, y1) = to_screen(*end);
+                let dx = (x0.min(x1) - point.0).max(point.0 - x0.max(x1)).max(0.0);
+                let dy = (y0.min(y1) - point.1).max(point.1 - y0.max(y1)).max(0.0);
+                dx.hypot(dy)
+            }
+        }
+    }
+}
+
+// Ratio and price of each retracement level between two anchors
+pub fn fibonacci_levels(start: Anchor, end: Anchor) -> Vec<(f64, f64)> {
+    FIBONACCI_RATIOS
+        .iter()
+        .map(|&ratio| (ratio, end.price - (end.price - start.price) * ratio))
+        .collect()
+}
+
+fn segment_distance(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (point.0 - (a.0 + t * dx)).hypot(point.1 - (a.1 + t * dy))
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Drawing {
+    pub id: u64,
+    pub shape: Shape,
+}
+
+// Drawings of each symbol in their own JSON file, rewritten on every change
+pub struct DrawingStore {
+    dir: PathBuf,
+}
+
+impl DrawingStore {
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.to_path_buf() }
+    }
+
+    fn path(&self, symbol: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", symbol.to_uppercase()))
+    }
+
+    pub fn load(&self, symbol: &str) -> io::Result<Vec<Drawing>> {
+        match fs::read_to_string(self.path(symbol)) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, symbol: &str, drawings: &[Drawing]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(symbol);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(drawings)?)?;
+        fs::rename(&tmp_path, &path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor(time: f64, price: f64) -> Anchor {
+        Anchor { time, price }
+    }
+
+    // Chart coordinates are already screen coordinates in these tests
+    fn identity(anchor: Anchor) -> (f64, f64) {
+        (anchor.time, anchor.price)
+    }
+
+    #[test]
+    fn tools_need_all_their_anchors() {
+        assert_eq!(Tool::Trendline.shape(&[anchor(0.0, 1.0)]), None);
+        assert_eq!(
+            Tool::HorizontalRay.shape(&[anchor(0.0, 1.0)]),
+            Some(Shape::HorizontalRay { start: anchor(0.0, 1.0) })
+        );
+        let rectangle = Tool::Rectangle.shape(&[anchor(0.0, 1.0), anchor(10.0, 5.0)]).unwrap();
+        assert_eq!(rectangle.anchors(), vec![anchor(0.0, 1.0), anchor(10.0, 5.0)]);
+    }
+
+    #[test]
+    fn fibonacci_levels_retrace_from_the_second_anchor() {
+        let levels = fibonacci_levels(anchor(0.0, 100.0), anchor(10.0, 200.0));
+        assert_eq!(levels.first(), Some(&(0.0, 200.0)));
+        assert_eq!(levels[3], (0.5, 150.0));
+        assert_eq!(levels.last(), Some(&(1.0, 100.0)));
+    }
+
+    #[test]
+    fn distance_to_each_shape() {
+        let line = Shape::Trendline { start: anchor(0.0, 0.0), end: anchor(10.0, 10.0) };
+        assert!(line.distance((5.0, 5.0), identity) < 1e-9);
+        assert_eq!(line.distance((20.0, 10.0), identity), 10.0); // Past the end
+
+        // A ray only extends to the right
+        let ray = Shape::HorizontalRay { start: anchor(10.0, 5.0) };
+        assert_eq!(ray.distance((1000.0, 8.0), identity), 3.0);
+        assert_eq!(ray.distance((6.0, 5.0), identity), 4.0);
+
+        let fib = Shape::Fibonacci { start: anchor(0.0, 100.0), end: anchor(10.0, 200.0) };
+        assert!((fib.distance((5.0, 151.0), identity) - 1.0).abs() < 1e-9);
+
+        let rectangle = Shape::Rectangle { start: anchor(10.0, 10.0), end: anchor(0.0, 0.0) };
+        assert_eq!(rectangle.distance((5.0, 5.0), identity), 0.0);
+        assert_eq!(rectangle.distance((13.0, 14.0), identity), 5.0);
+    }
+
+    #[test]
+    fn drawings_round_trip_per_symbol() {
+        let dir = std::env::temp_dir().join(format!("asterism-drawings-test-{}", std::process::id()));
+        let store = DrawingStore::new(&dir);
+        assert!(store.load("BTCUSDT").unwrap().is_empty());
+
+        let mut shape = Shape::Fibonacci { start: anchor(0.0, 100.0), end: anchor(60.0, 120.0) };
+        shape.translate(60.0, -10.0);
+        let drawings = vec![
+            Drawing { id: 1, shape },
+            Drawing { id: 2, shape: Shape::HorizontalRay { start: anchor(0.0, 95.5) } },
+        ];
+        store.save("BTCUSDT", &drawings).unwrap();
+
+        assert_eq!(store.load("BTCUSDT").unwrap(), drawings);
+        assert!(store.load("ETHUSDT").unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
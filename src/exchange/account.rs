@@ -0,0 +1,168 @@
+use crate::exchange::binance::BINANCE_API_ADDRESS;
+use crate::exchange::SourceError;
+use crate::{OrderMode, OrderType};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+
+// Balances and order entry for one exchange account
+pub trait TradingAccount: Send + Sync {
+    fn name(&self) -> &str;
+
+    // Free (unlocked) balance per asset, zero balances left out
+    fn balances(&self) -> BoxFuture<'_, Result<HashMap<String, f64>, SourceError>>;
+
+    // Returns a short confirmation such as "order 123 FILLED (0.001 filled)"
+    fn place_order<'a>(
+        &'a self,
+        symbol: &'a str,
+        order_type: &'a OrderType,
+        order_mode: &'a OrderMode,
+        quantity: &'a str,
+        price: &'a str,
+    ) -> BoxFuture<'a, Result<String, SourceError>>;
+}
+
+#[derive(Debug, Deserialize)]
+struct SpotAccountInfo {
+    balances: Vec<SpotBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpotBalance {
+    asset: String,
+    free: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpotOrderResponse {
+    #[serde(rename = "orderId")]
+    order_id: u64,
+    status: String,
+    #[serde(rename = "executedQty")]
+    executed_qty: String,
+}
+
+// Signed Binance spot endpoints, keyed by BINANCE_API_KEY / BINANCE_API_SECRET
+pub struct BinanceAccount {
+    client: reqwest::Client,
+    api_key: String,
+    api_secret: String,
+}
+
+impl BinanceAccount {
+    pub fn from_env() -> Option<Self> {
+        let api_key = env::var("BINANCE_API_KEY").ok()?;
+        let api_secret = env::var("BINANCE_API_SECRET").ok()?;
+        Some(Self {
+            client: reqwest::Client::new(),
+            api_key,
+            api_secret,
+        })
+    }
+
+    fn signed_url(&self, path: &str, params: &str) -> String {
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let query = if params.is_empty() {
+            format!("timestamp={}", timestamp)
+        } else {
+            format!("{}&timestamp={}", params, timestamp)
+        };
+        let signature = hmac_sha256(&self.api_secret, &query);
+        format!(
+            "{}{}?{}&signature={}",
+            BINANCE_API_ADDRESS, path, query, signature
+        )
+    }
+}
+
+impl TradingAccount for BinanceAccount {
+    fn name(&self) -> &str {
+        "Binance Spot"
+    }
+
+    fn balances(&self) -> BoxFuture<'_, Result<HashMap<String, f64>, SourceError>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .get(self.signed_url("/account", ""))
+                .header("X-MBX-APIKEY", &self.api_key)
+                .send()
+                .await?;
+
+            let status = response.status();
+            let text = response.text().await?;
+            if !status.is_success() {
+                return Err(format!("API error: {} {}", status, text).into());
+            }
+
+            let info: SpotAccountInfo = serde_json::from_str(&text)?;
+            Ok(info
+                .balances
+                .into_iter()
+                .filter_map(|balance| {
+                    let free = balance.free.parse::<f64>().ok()?;
+                    (free > 0.0).then_some((balance.asset, free))
+                })
+                .collect())
+        })
+    }
+
+    fn place_order<'a>(
+        &'a self,
+        symbol: &'a str,
+        order_type: &'a OrderType,
+        order_mode: &'a OrderMode,
+        quantity: &'a str,
+        price: &'a str,
+    ) -> BoxFuture<'a, Result<String, SourceError>> {
+        Box::pin(async move {
+            let side = match order_type {
+                OrderType::Buy => "BUY",
+                OrderType::Sell => "SELL",
+            };
+
+            let params = match order_mode {
+                OrderMode::Market => format!(
+                    "symbol={}&side={}&type=MARKET&quantity={}",
+                    symbol, side, quantity
+                ),
+                OrderMode::Limit => format!(
+                    "symbol={}&side={}&type=LIMIT&timeInForce=GTC&quantity={}&price={}",
+                    symbol, side, quantity, price
+                ),
+            };
+
+            let response = self
+                .client
+                .post(self.signed_url("/order", &params))
+                .header("X-MBX-APIKEY", &self.api_key)
+                .send()
+                .await?;
+
+            let status = response.status();
+            let text = response.text().await?;
+            if !status.is_success() {
+                return Err(format!("order failed: {} {}", status, text).into());
+            }
+
+            let order: SpotOrderResponse = serde_json::from_str(&text)?;
+            Ok(format!(
+                "order {} {} ({} filled)",
+                order.order_id, order.status, order.executed_qty
+            ))
+        })
+    }
+}
+
+pub fn hmac_sha256(secret: &str, message: &str) -> String {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    type HmacSha256 = Hmac<Sha256>;
+
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(message.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
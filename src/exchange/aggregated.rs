@@ -0,0 +1,154 @@
+use crate::exchange::{
+    CandleStream, DepthStream, KlineRange, MarketDataSource, PartialPage, SourceError, SymbolInfo, TradeStream,
+};
+use crate::transform::{aggregate_complete, LiveAggregator};
+use crate::{CandleData, Timeframe};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+// Serves custom timeframes by requesting their native base interval from the
+// wrapped source and rolling it up. Native timeframes pass straight through.
+pub struct AggregatedSource {
+    inner: Arc<dyn MarketDataSource>,
+}
+
+impl AggregatedSource {
+    pub fn new(inner: Arc<dyn MarketDataSource>) -> Self {
+        Self { inner }
+    }
+}
+
+impl MarketDataSource for AggregatedSource {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn cache_namespace(&self) -> Option<&str> {
+        self.inner.cache_namespace()
+    }
+
+    fn historical_klines<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+        range: KlineRange,
+    ) -> BoxFuture<'a, Result<Vec<CandleData>, SourceError>> {
+        let Some(base) = timeframe.native_base() else {
+            return self.inner.historical_klines(symbol, timeframe, range);
+        };
+
+        Box::pin(async move {
+            let range = match range {
+                KlineRange::Since(since) => KlineRange::Since(timeframe.candle_open(since)),
+                range => range,
+            };
+            // A partial page is still rolled up, and stays partial
+            match self.inner.historical_klines(symbol, &base, range).await {
+                Ok(candles) => Ok(aggregate_complete(&candles.into(), timeframe).into()),
+                Err(e) => match e.downcast::<PartialPage>() {
+                    Ok(mut partial) => {
+                        partial.candles = aggregate_complete(&partial.candles.into(), timeframe).into();
+                        Err(partial as SourceError)
+                    }
+                    Err(e) => Err(e),
+                },
+            }
+        })
+    }
+
+    fn live_updates<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+    ) -> BoxFuture<'a, Result<CandleStream, SourceError>> {
+        let Some(base) = timeframe.native_base() else {
+            return self.inner.live_updates(symbol, timeframe);
+        };
+
+        Box::pin(async move {
+            let updates = self.inner.live_updates(symbol, &base).await?;
+            let inner = self.inner.clone();
+            let symbol = symbol.to_string();
+            let timeframe = timeframe.clone();
+            let state = (updates, LiveAggregator::new(timeframe.clone()));
+
+            let aggregated = futures_util::stream::unfold(state, move |(mut updates, mut aggregator)| {
+                let inner = inner.clone();
+                let symbol = symbol.clone();
+                let base = base.clone();
+                let timeframe = timeframe.clone();
+                async move {
+                    let candle = match updates.next().await? {
+                        Ok(candle) => candle,
+                        Err(e) => return Some((Err(e), (updates, aggregator))),
+                    };
+
+                    // The part of the first period before we joined comes from history
+                    if aggregator.needs_seed() {
+                        let since = KlineRange::Since(timeframe.candle_open(candle.timestamp));
+                        match inner.historical_klines(&symbol, &base, since).await {
+                            Ok(history) => aggregator.seed(&history, &candle),
+                            Err(e) => return Some((Err(e), (updates, aggregator))),
+                        }
+                    }
+
+                    let current = aggregator.update(candle);
+                    Some((Ok(current), (updates, aggregator)))
+                }
+            });
+
+            Ok(aggregated.boxed())
+        })
+    }
+
+    fn trade_updates<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<TradeStream, SourceError>> {
+        self.inner.trade_updates(symbol)
+    }
+
+    fn depth_updates<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<DepthStream, SourceError>> {
+        self.inner.depth_updates(symbol)
+    }
+
+    fn symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, SourceError>> {
+        self.inner.symbols()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::mock::MockSource;
+    use crate::transform::aggregate;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn custom_timeframes_are_rolled_up_from_the_base_interval() {
+        let now = 1_700_000_130.0;
+        let mock = Arc::new(MockSource::with_clock(7, now, Duration::from_millis(5)));
+        let source = AggregatedSource::new(mock.clone());
+        let timeframe = Timeframe::Custom(10);
+
+        let candles = source
+            .historical_klines("BTCUSDT", &timeframe, KlineRange::Latest)
+            .await
+            .unwrap();
+        let base = mock
+            .historical_klines("BTCUSDT", &Timeframe::M5, KlineRange::Latest)
+            .await
+            .unwrap();
+        let expected = aggregate(&base.into(), &timeframe);
+
+        // Only whole periods, ending with the one still forming
+        assert_eq!(candles[0].timestamp % 600.0, 0.0);
+        assert_eq!(candles.last(), expected.back());
+        assert_eq!(candles.last().unwrap().timestamp, timeframe.candle_open(now));
+
+        // The first live update continues that forming candle
+        let mut updates = source.live_updates("BTCUSDT", &timeframe).await.unwrap();
+        let live = updates.next().await.unwrap().unwrap();
+        let forming = candles.last().unwrap();
+        assert_eq!((live.timestamp, live.open), (forming.timestamp, forming.open));
+        assert!(live.volume >= forming.volume);
+    }
+}
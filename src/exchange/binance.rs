@@ -0,0 +1,744 @@
+use crate::exchange::{
+    ApiError, CandleStream, DepthStream, KlineRange, MalformedTrade, MarketDataSource, MarketType, PartialPage,
+    SourceError, SymbolInfo, Trade, TradeStream, QUOTE_ASSET,
+};
+use crate::orderbook::{DepthDiff, Level, MalformedDiff, OrderBook};
+use crate::{CandleData, Timeframe};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message as WsMessage};
+
+pub static BINANCE_FAPI_ADDRESS: &str = "https://fapi.binance.com/fapi/v1";
+pub static BINANCE_FWSS_ADDRESS: &str = "wss://fstream.binance.com/ws";
+pub static BINANCE_API_ADDRESS: &str = "https://api.binance.com/api/v3";
+pub static BINANCE_WSS_ADDRESS: &str = "wss://stream.binance.com:9443/ws";
+
+const LATEST_LIMIT: usize = 500;
+const HISTORY_LIMIT: usize = 1000;
+const DEPTH_SNAPSHOT_LIMIT: usize = 1000;
+const BOOK_DEPTH: usize = 100; // Levels per side passed on from the local book
+
+// REST klines and WebSocket kline stream for USDⓈ-M futures or spot
+pub struct BinanceSource {
+    client: reqwest::Client,
+    market: MarketType,
+}
+
+impl BinanceSource {
+    pub fn new(market: MarketType) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            market,
+        }
+    }
+
+    fn rest_address(&self) -> &'static str {
+        match self.market {
+            MarketType::Futures => BINANCE_FAPI_ADDRESS,
+            MarketType::Spot => BINANCE_API_ADDRESS,
+        }
+    }
+
+    fn ws_address(&self) -> &'static str {
+        match self.market {
+            MarketType::Futures => BINANCE_FWSS_ADDRESS,
+            MarketType::Spot => BINANCE_WSS_ADDRESS,
+        }
+    }
+
+    // Largest page the klines endpoint accepts
+    fn page_limit(&self) -> usize {
+        match self.market {
+            MarketType::Futures => 1500,
+            MarketType::Spot => 1000,
+        }
+    }
+
+    async fn fetch_klines(&self, url: &str) -> Result<Vec<CandleData>, SourceError> {
+        let response = self.client.get(url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_status(response.status()).into());
+        }
+
+        let text = response.text().await?;
+        let json: serde_json::Value = serde_json::from_str(&text)?;
+
+        parse_klines_json(&json)
+    }
+
+    fn klines_url(&self, symbol: &str, timeframe: &Timeframe, params: &str) -> String {
+        format!(
+            "{}/klines?symbol={}&interval={}&{}",
+            self.rest_address(),
+            symbol,
+            timeframe.to_api_string(),
+            params
+        )
+    }
+
+    // Page forward from `since` (seconds) until we reach the present
+    async fn fetch_klines_since(
+        &self,
+        symbol: &str,
+        timeframe: &Timeframe,
+        since: f64,
+    ) -> Result<Vec<CandleData>, SourceError> {
+        let page_limit = self.page_limit();
+        let mut candles: Vec<CandleData> = Vec::new();
+        let mut start_time = (since * 1000.0) as i64;
+
+        loop {
+            let url = self.klines_url(
+                symbol,
+                timeframe,
+                &format!("startTime={}&limit={}", start_time, page_limit),
+            );
+
+            let page = self.fetch_klines(&url).await?;
+            let page_len = page.len();
+
+            match page.last() {
+                Some(last) => start_time = (last.timestamp * 1000.0) as i64 + 1,
+                None => break,
+            }
+            candles.extend(page);
+
+            if page_len < page_limit {
+                break;
+            }
+        }
+
+        Ok(candles)
+    }
+
+    async fn fetch_depth_snapshot(
+        client: &reqwest::Client,
+        market: MarketType,
+        url: &str,
+    ) -> Result<OrderBook, SourceError> {
+        let response = client.get(url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_status(response.status()).into());
+        }
+
+        parse_depth_snapshot(market, &response.text().await?)
+    }
+
+    // Top 20 USDT pairs by 24h quote volume
+    pub async fn get_top_volume_pairs(&self) -> Result<Vec<(String, f64)>, SourceError> {
+        let url = format!("{}/ticker/24hr", self.rest_address());
+
+        let response = self.client.get(url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_status(response.status()).into());
+        }
+
+        let data: Vec<serde_json::Value> = response.json().await?;
+
+        let mut pairs: Vec<(String, f64)> = data
+            .into_iter()
+            .filter(|item| {
+                item["symbol"]
+                    .as_str()
+                    .map(|s| s.ends_with(QUOTE_ASSET))
+                    .unwrap_or(false)
+            })
+            .filter_map(|item| {
+                let symbol = item["symbol"].as_str()?.to_string();
+                let volume = item["quoteVolume"].as_str()?.parse::<f64>().ok()?;
+                Some((symbol, volume))
+            })
+            .collect();
+
+        pairs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(pairs.into_iter().take(20).collect())
+    }
+}
+
+impl MarketDataSource for BinanceSource {
+    fn name(&self) -> &str {
+        match self.market {
+            MarketType::Futures => "Binance Futures",
+            MarketType::Spot => "Binance Spot",
+        }
+    }
+
+    fn cache_namespace(&self) -> Option<&str> {
+        match self.market {
+            MarketType::Futures => Some("binance-futures"),
+            MarketType::Spot => Some("binance-spot"),
+        }
+    }
+
+    fn historical_klines<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+        range: KlineRange,
+    ) -> BoxFuture<'a, Result<Vec<CandleData>, SourceError>> {
+        Box::pin(async move {
+            match range {
+                KlineRange::Latest => {
+                    let url = self.klines_url(symbol, timeframe, &format!("limit={}", LATEST_LIMIT));
+                    self.fetch_klines(&url).await
+                }
+                KlineRange::Since(since) => self.fetch_klines_since(symbol, timeframe, since).await,
+                KlineRange::Before(before) => {
+                    let url = self.klines_url(
+                        symbol,
+                        timeframe,
+                        &format!("endTime={}&limit={}", (before * 1000.0) as i64 - 1, HISTORY_LIMIT),
+                    );
+                    self.fetch_klines(&url).await
+                }
+            }
+        })
+    }
+
+    fn live_updates<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+    ) -> BoxFuture<'a, Result<CandleStream, SourceError>> {
+        Box::pin(async move {
+            let url = kline_stream_url(self.ws_address(), symbol, &timeframe.to_api_string());
+            let (ws_stream, _) = connect_async(url).await?;
+
+            let updates = ws_stream
+                .take_while(|msg| {
+                    let open = !matches!(msg, Ok(WsMessage::Close(_)));
+                    async move { open }
+                })
+                .filter_map(|msg| async move {
+                    match msg {
+                        Ok(WsMessage::Text(text)) => Some(parse_kline_frame(&text).map_err(SourceError::from)),
+                        Ok(_) => None,
+                        Err(e) => Some(Err(SourceError::from(e))),
+                    }
+                });
+
+            Ok(updates.boxed())
+        })
+    }
+
+    fn trade_updates<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<TradeStream, SourceError>> {
+        Box::pin(async move {
+            let url = format!("{}/{}@aggTrade", self.ws_address(), symbol.to_lowercase());
+            let (ws_stream, _) = connect_async(url).await?;
+
+            let trades = ws_stream
+                .take_while(|msg| {
+                    let open = !matches!(msg, Ok(WsMessage::Close(_)));
+                    async move { open }
+                })
+                .filter_map(|msg| async move {
+                    match msg {
+                        Ok(WsMessage::Text(text)) => Some(parse_agg_trade(&text).map_err(SourceError::from)),
+                        Ok(_) => None,
+                        Err(e) => Some(Err(SourceError::from(e))),
+                    }
+                });
+
+            Ok(trades.boxed())
+        })
+    }
+
+    // Diffs are subscribed to before the snapshot is requested so none are
+    // missed in between. A gap in the sequence or an unreadable diff is
+    // reported as a `SequenceGap` or `MalformedDiff` error and the stream
+    // carries on from a new snapshot.
+    fn depth_updates<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<DepthStream, SourceError>> {
+        Box::pin(async move {
+            let url = format!("{}/{}@depth", self.ws_address(), symbol.to_lowercase());
+            let (ws_stream, _) = connect_async(url).await?;
+
+            let diffs = ws_stream
+                .take_while(|msg| {
+                    let open = !matches!(msg, Ok(WsMessage::Close(_)));
+                    async move { open }
+                })
+                .filter_map(|msg| async move {
+                    match msg {
+                        Ok(WsMessage::Text(text)) => Some(parse_depth_update(&text).map_err(SourceError::from)),
+                        Ok(_) => None,
+                        Err(e) => Some(Err(SourceError::from(e))),
+                    }
+                })
+                .boxed();
+
+            let (client, market) = (self.client.clone(), self.market);
+            let snapshot_url = format!(
+                "{}/depth?symbol={}&limit={}",
+                self.rest_address(),
+                symbol,
+                DEPTH_SNAPSHOT_LIMIT
+            );
+            let books = futures_util::stream::unfold((diffs, None), move |(mut diffs, mut book): (_, Option<OrderBook>)| {
+                let client = client.clone();
+                let snapshot_url = snapshot_url.clone();
+                async move {
+                    loop {
+                        let diff = match diffs.next().await? {
+                            Ok(diff) => diff,
+                            // Its changes are lost, so start over from a new snapshot
+                            Err(e) if e.is::<MalformedDiff>() => return Some((Err(e), (diffs, None))),
+                            Err(e) => return Some((Err(e), (diffs, book))),
+                        };
+                        if book.is_none() {
+                            match Self::fetch_depth_snapshot(&client, market, &snapshot_url).await {
+                                Ok(snapshot) => book = Some(snapshot),
+                                Err(e) => return Some((Err(e), (diffs, None))),
+                            }
+                        }
+
+                        let Some(current) = book.as_mut() else { continue };
+                        match current.apply(&diff) {
+                            Ok(()) if current.is_synced() => {
+                                let top = current.top(BOOK_DEPTH);
+                                return Some((Ok(top), (diffs, book)));
+                            }
+                            Ok(()) => {}
+                            Err(gap) => return Some((Err(gap.into()), (diffs, None))),
+                        }
+                    }
+                }
+            });
+
+            Ok(books.boxed())
+        })
+    }
+
+    fn symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, SourceError>> {
+        Box::pin(async move {
+            let pairs = self.get_top_volume_pairs().await?;
+            Ok(pairs
+                .into_iter()
+                .map(|(symbol, _)| SymbolInfo {
+                    base_asset: symbol.strip_suffix(QUOTE_ASSET).unwrap_or(&symbol).to_string(),
+                    quote_asset: QUOTE_ASSET.to_string(),
+                    symbol,
+                })
+                .collect())
+        })
+    }
+}
+
+pub fn kline_stream_url(ws_address: &str, symbol: &str, interval: &str) -> String {
+    format!(
+        "{}/{}@kline_{}",
+        ws_address,
+        symbol.to_lowercase(),
+        interval
+    )
+}
+
+// Payload of the `<symbol>@kline_<interval>` stream
+#[derive(Debug, Deserialize, Clone)]
+pub struct KlineEvent {
+    #[serde(rename = "k")]
+    pub kline: StreamKline,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StreamKline {
+    #[serde(rename = "t")]
+    pub open_time: i64,
+    #[serde(rename = "o")]
+    pub open: String,
+    #[serde(rename = "h")]
+    pub high: String,
+    #[serde(rename = "l")]
+    pub low: String,
+    #[serde(rename = "c")]
+    pub close: String,
+    #[serde(rename = "v")]
+    pub volume: String,
+}
+
+pub fn parse_stream_kline(kline: &StreamKline) -> Result<CandleData, KlineError> {
+    let field = |name: &str, text: &str| {
+        text.parse::<f64>()
+            .map_err(|_| KlineError::MalformedFrame(format!("invalid {} {:?}", name, text)))
+    };
+    let candle = CandleData {
+        timestamp: kline.open_time as f64 / 1000.0,
+        open: field("open", &kline.open)?,
+        high: field("high", &kline.high)?,
+        low: field("low", &kline.low)?,
+        close: field("close", &kline.close)?,
+        volume: field("volume", &kline.volume)?,
+    };
+    check_ohlcv(&candle).map_err(KlineError::MalformedFrame)?;
+    Ok(candle)
+}
+
+// One text frame of the kline stream
+pub fn parse_kline_frame(text: &str) -> Result<CandleData, KlineError> {
+    let event = serde_json::from_str::<KlineEvent>(text).map_err(|e| KlineError::MalformedFrame(e.to_string()))?;
+    parse_stream_kline(&event.kline)
+}
+
+// Prices and volume that cannot belong to a real candle
+fn check_ohlcv(candle: &CandleData) -> Result<(), String> {
+    let prices = [candle.open, candle.high, candle.low, candle.close];
+    if prices.iter().any(|price| !price.is_finite() || *price <= 0.0) {
+        return Err("non-positive price".to_string());
+    }
+    if candle.high < candle.low {
+        return Err(format!("high {} below low {}", candle.high, candle.low));
+    }
+    if !candle.volume.is_finite() || candle.volume < 0.0 {
+        return Err(format!("invalid volume {}", candle.volume));
+    }
+    Ok(())
+}
+
+// Payload of the `<symbol>@aggTrade` stream
+#[derive(Debug, Deserialize, Clone)]
+pub struct AggTradeEvent {
+    #[serde(rename = "p")]
+    pub price: String,
+    #[serde(rename = "q")]
+    pub quantity: String,
+    #[serde(rename = "T")]
+    pub trade_time: i64,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+pub fn parse_agg_trade(text: &str) -> Result<Trade, MalformedTrade> {
+    let event = serde_json::from_str::<AggTradeEvent>(text).map_err(|e| MalformedTrade(e.to_string()))?;
+    let number = |name: &str, text: &str| {
+        text.parse::<f64>()
+            .map_err(|_| MalformedTrade(format!("invalid {} {:?}", name, text)))
+    };
+
+    Ok(Trade {
+        timestamp: event.trade_time as f64 / 1000.0,
+        price: number("price", &event.price)?,
+        quantity: number("quantity", &event.quantity)?,
+        is_buyer_maker: event.is_buyer_maker,
+    })
+}
+
+// Response of the depth endpoint; levels are [price, quantity] strings
+#[derive(Debug, Deserialize, Clone)]
+pub struct DepthSnapshot {
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: u64,
+    pub bids: Vec<[String; 2]>,
+    pub asks: Vec<[String; 2]>,
+}
+
+// Payload of the `<symbol>@depth` diff stream
+#[derive(Debug, Deserialize, Clone)]
+pub struct DepthUpdateEvent {
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    #[serde(rename = "u")]
+    pub final_update_id: u64,
+    #[serde(rename = "pu")]
+    pub previous_update_id: Option<u64>, // Futures only
+    #[serde(rename = "b")]
+    pub bids: Vec<[String; 2]>,
+    #[serde(rename = "a")]
+    pub asks: Vec<[String; 2]>,
+}
+
+fn parse_levels(levels: &[[String; 2]]) -> Option<Vec<Level>> {
+    levels
+        .iter()
+        .map(|[price, quantity]| {
+            Some(Level {
+                price: price.parse::<f64>().ok()?,
+                quantity: quantity.parse::<f64>().ok()?,
+            })
+        })
+        .collect()
+}
+
+pub fn parse_depth_snapshot(market: MarketType, text: &str) -> Result<OrderBook, SourceError> {
+    let snapshot = serde_json::from_str::<DepthSnapshot>(text)?;
+    let bids = parse_levels(&snapshot.bids).ok_or("invalid bid level in depth snapshot")?;
+    let asks = parse_levels(&snapshot.asks).ok_or("invalid ask level in depth snapshot")?;
+
+    Ok(OrderBook::from_snapshot(market, snapshot.last_update_id, bids, asks))
+}
+
+pub fn parse_depth_update(text: &str) -> Result<DepthDiff, MalformedDiff> {
+    let event = serde_json::from_str::<DepthUpdateEvent>(text).map_err(|e| MalformedDiff(e.to_string()))?;
+
+    Ok(DepthDiff {
+        first_update_id: event.first_update_id,
+        final_update_id: event.final_update_id,
+        previous_update_id: event.previous_update_id,
+        bids: parse_levels(&event.bids).ok_or_else(|| MalformedDiff("invalid bid level".to_string()))?,
+        asks: parse_levels(&event.asks).ok_or_else(|| MalformedDiff("invalid ask level".to_string()))?,
+    })
+}
+
+// One row of a Binance klines response:
+// [openTime, "open", "high", "low", "close", "volume", closeTime,
+//  "quoteVolume", trades, "takerBuyBase", "takerBuyQuote", "ignore"]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Kline {
+    pub open_time: i64,
+    #[serde(deserialize_with = "decimal")]
+    pub open: f64,
+    #[serde(deserialize_with = "decimal")]
+    pub high: f64,
+    #[serde(deserialize_with = "decimal")]
+    pub low: f64,
+    #[serde(deserialize_with = "decimal")]
+    pub close: f64,
+    #[serde(deserialize_with = "decimal")]
+    pub volume: f64,
+    pub close_time: i64,
+    #[serde(deserialize_with = "decimal")]
+    pub quote_volume: f64,
+    pub trades: u64,
+    #[serde(deserialize_with = "decimal")]
+    pub taker_buy_volume: f64,
+    #[serde(deserialize_with = "decimal")]
+    pub taker_buy_quote_volume: f64,
+    #[serde(default)]
+    _ignore: serde::de::IgnoredAny,
+}
+
+impl Kline {
+    pub fn to_candle(&self) -> CandleData {
+        CandleData {
+            timestamp: self.open_time as f64 / 1000.0,
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+        }
+    }
+
+    // Rows that deserialize but cannot be a real candle
+    fn validate(&self) -> Result<(), String> {
+        check_ohlcv(&self.to_candle())?;
+        if self.close_time < self.open_time {
+            return Err("closes before it opens".to_string());
+        }
+        if self.quote_volume < 0.0 {
+            return Err(format!("negative quote volume {}", self.quote_volume));
+        }
+        if self.volume > 0.0 && self.trades == 0 {
+            return Err("volume without trades".to_string());
+        }
+        if self.taker_buy_volume > self.volume || self.taker_buy_quote_volume > self.quote_volume {
+            return Err("taker buy volume above the total".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Binance sends prices and volumes as strings to keep their precision
+fn decimal<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse::<f64>()
+        .map_err(|_| serde::de::Error::custom(format!("invalid decimal {:?}", text)))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KlineError {
+    NotAnArray,
+    MalformedRow { index: usize, reason: String },
+    MalformedFrame(String), // A kline stream message
+}
+
+impl std::fmt::Display for KlineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KlineError::NotAnArray => write!(f, "klines response is not an array"),
+            KlineError::MalformedRow { index, reason } => {
+                write!(f, "malformed kline at row {}: {}", index, reason)
+            }
+            KlineError::MalformedFrame(reason) => write!(f, "malformed kline stream frame: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for KlineError {}
+
+// The rows that parse, and an error for each one that does not
+pub fn parse_klines(json: &serde_json::Value) -> Result<(Vec<Kline>, Vec<KlineError>), KlineError> {
+    let rows = json.as_array().ok_or(KlineError::NotAnArray)?;
+
+    let mut klines = Vec::with_capacity(rows.len());
+    let mut errors = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let kline = Kline::deserialize(row)
+            .map_err(|e| e.to_string())
+            .and_then(|kline| kline.validate().map(|()| kline));
+        match kline {
+            Ok(kline) => klines.push(kline),
+            Err(reason) => errors.push(KlineError::MalformedRow { index, reason }),
+        }
+    }
+    Ok((klines, errors))
+}
+
+// Candles of a klines response. Malformed rows make it a `PartialPage`
+// error that still carries the other candles.
+pub fn parse_klines_json(json: &serde_json::Value) -> Result<Vec<CandleData>, SourceError> {
+    let (klines, errors) = parse_klines(json)?;
+    let candles: Vec<CandleData> = klines.iter().map(Kline::to_candle).collect();
+
+    match errors.first() {
+        None => Ok(candles),
+        Some(first) => Err(PartialPage {
+            skipped: errors.len(),
+            total: candles.len() + errors.len(),
+            reason: first.to_string(),
+            candles,
+        }
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(text: &str) -> serde_json::Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn klines_keep_every_field() {
+        let (klines, errors) = parse_klines(&fixture(include_str!("fixtures/binance_klines.json"))).unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(klines.len(), 3);
+        let kline = &klines[1];
+        assert_eq!(kline.open_time, 1_700_000_100_000);
+        assert_eq!(kline.close_time, 1_700_000_159_999);
+        assert_eq!(kline.high, 37_125.7);
+        assert_eq!(kline.quote_volume, 746530.1203);
+        assert_eq!(kline.trades, 905);
+        assert_eq!(kline.taker_buy_volume, 11.482);
+        assert_eq!(kline.taker_buy_quote_volume, 426133.56712);
+
+        let candle = kline.to_candle();
+        assert_eq!(candle.timestamp, 1_700_000_100.0);
+        assert_eq!(candle.close, 37_120.5);
+        assert_eq!(candle.volume, 20.117);
+    }
+
+    #[test]
+    fn malformed_row_is_reported_not_dropped() {
+        let json = fixture(include_str!("fixtures/binance_klines_malformed.json"));
+        let (klines, errors) = parse_klines(&json).unwrap();
+        assert_eq!(klines.len(), 2);
+        match &errors[..] {
+            [KlineError::MalformedRow { index, reason }] => {
+                assert_eq!(*index, 1);
+                assert!(reason.contains("n/a"), "{}", reason);
+            }
+            other => panic!("unexpected errors {:?}", other),
+        }
+
+        // The rest of the page is kept alongside the error
+        let err = parse_klines_json(&json).unwrap_err();
+        let partial = err.downcast_ref::<PartialPage>().unwrap();
+        assert_eq!(partial.candles.len(), 2);
+        assert_eq!(partial.candles[1].timestamp, 1_700_000_160.0);
+        assert!(err.to_string().contains("malformed kline at row 1"), "{}", err);
+    }
+
+    #[test]
+    fn short_and_impossible_rows_are_rejected() {
+        let rejected = |row: &str| {
+            let (klines, errors) = parse_klines(&fixture(row)).unwrap();
+            klines.is_empty() && matches!(&errors[..], [KlineError::MalformedRow { index: 0, .. }])
+        };
+        assert!(rejected(r#"[[1700000040000, "1.0", "2.0", "0.5"]]"#));
+        assert!(rejected(
+            r#"[[1700000040000, "0", "2.0", "0.5", "1.5", "3", 1700000099999, "4.5", 2, "1", "1.5", "0"]]"#
+        ));
+        // More taker buying than was traded
+        assert!(rejected(
+            r#"[[1700000040000, "1.0", "2.0", "0.5", "1.5", "3", 1700000099999, "4.5", 2, "4", "1.5", "0"]]"#
+        ));
+
+        assert_eq!(
+            parse_klines(&fixture(r#"{"code": -1121, "msg": "Invalid symbol."}"#)),
+            Err(KlineError::NotAnArray)
+        );
+    }
+
+    #[test]
+    fn stream_frames_parse_or_report_why_not() {
+        let candle = parse_kline_frame(include_str!("fixtures/binance_ws_kline.json")).unwrap();
+        assert_eq!(candle.timestamp, 1_700_000_160.0);
+        assert_eq!((candle.high, candle.close, candle.volume), (37_135.0, 37_131.0, 13.01));
+
+        let err = parse_kline_frame(include_str!("fixtures/binance_ws_kline_malformed.json")).unwrap_err();
+        assert_eq!(err, KlineError::MalformedFrame("non-positive price".to_string()));
+        assert!(matches!(parse_kline_frame("{\"result\": null}"), Err(KlineError::MalformedFrame(_))));
+    }
+
+    #[test]
+    fn agg_trade_maps_to_trade() {
+        let trade = parse_agg_trade(include_str!("fixtures/binance_agg_trade.json")).unwrap();
+
+        assert_eq!(trade.timestamp, 1_700_000_170.123);
+        assert_eq!(trade.price, 37_130.4);
+        assert_eq!(trade.quantity, 0.25);
+        assert!(trade.is_buyer_maker);
+
+        let malformed = include_str!("fixtures/binance_agg_trade.json").replace("0.250", "-");
+        assert_eq!(parse_agg_trade(&malformed), Err(MalformedTrade("invalid quantity \"-\"".to_string())));
+    }
+
+    #[test]
+    fn depth_update_applies_on_top_of_the_snapshot() {
+        let mut book = parse_depth_snapshot(MarketType::Futures, include_str!("fixtures/binance_depth_snapshot.json")).unwrap();
+        assert_eq!(book.last_update_id, 1_027_024);
+        assert_eq!(book.bids[0], Level { price: 37_130.3, quantity: 4.12 });
+        assert_eq!(book.asks[0], Level { price: 37_130.4, quantity: 1.375 });
+
+        let diff = parse_depth_update(include_str!("fixtures/binance_depth_update.json")).unwrap();
+        assert_eq!(diff.previous_update_id, Some(1_027_019));
+        book.apply(&diff).unwrap();
+
+        assert!(book.is_synced());
+        assert_eq!(book.last_update_id, 1_027_030);
+        assert_eq!(
+            book.bids,
+            vec![Level { price: 37_130.3, quantity: 3.9 }, Level { price: 37_129.9, quantity: 12.004 }]
+        );
+        assert_eq!(book.asks[1], Level { price: 37_130.5, quantity: 0.8 });
+    }
+
+    #[test]
+    fn malformed_depth_update_is_reported() {
+        // Spot diffs carry no previous id, so a skipped one would go unnoticed
+        let err = parse_depth_update(include_str!("fixtures/binance_depth_update_malformed.json")).unwrap_err();
+        assert_eq!(err, MalformedDiff("invalid bid level".to_string()));
+        assert!(parse_depth_update("{\"result\": null}").is_err());
+    }
+
+    #[test]
+    fn rate_limit_statuses_are_flagged() {
+        // 429 asks to slow down, 418 is the ban for ignoring it
+        for status in [reqwest::StatusCode::TOO_MANY_REQUESTS, reqwest::StatusCode::IM_A_TEAPOT] {
+            let err = SourceError::from(ApiError::from_status(status));
+            assert!(crate::exchange::is_rate_limited(&err), "{}", err);
+        }
+        let err = SourceError::from(ApiError::from_status(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!crate::exchange::is_rate_limited(&err));
+    }
+}
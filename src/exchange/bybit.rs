@@ -0,0 +1,764 @@
+use crate::exchange::account::{hmac_sha256, TradingAccount};
+use crate::exchange::{
+    ApiError, CandleStream, KlineRange, MalformedTrade, MarketDataSource, MarketType, PartialPage, SourceError,
+    SymbolInfo, Trade, TradeStream, QUOTE_ASSET,
+};
+use crate::{CandleData, OrderMode, OrderType, Timeframe};
+use futures_util::future::BoxFuture;
+use futures_util::stream::BoxStream;
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message as WsMessage};
+
+pub static BYBIT_API_ADDRESS: &str = "https://api.bybit.com/v5";
+pub static BYBIT_LINEAR_WSS_ADDRESS: &str = "wss://stream.bybit.com/v5/public/linear";
+pub static BYBIT_SPOT_WSS_ADDRESS: &str = "wss://stream.bybit.com/v5/public/spot";
+
+const LATEST_LIMIT: usize = 500;
+const PAGE_LIMIT: usize = 1000;
+const RECV_WINDOW: &str = "5000";
+const RATE_LIMIT_CODE: i64 = 10006;
+
+// Bybit drops public connections that stay silent for more than a few minutes
+const PING_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(20);
+
+// Every v5 REST response is wrapped in this envelope; `result` is `{}` on errors
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(rename = "retCode")]
+    ret_code: i64,
+    #[serde(rename = "retMsg")]
+    ret_msg: String,
+    #[serde(default)]
+    result: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct KlineResult {
+    // [startTime, open, high, low, close, volume, turnover], newest first
+    list: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TickerResult {
+    list: Vec<Ticker>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ticker {
+    symbol: String,
+    #[serde(rename = "turnover24h")]
+    turnover_24h: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletResult {
+    list: Vec<WalletAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletAccount {
+    coin: Vec<WalletCoin>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletCoin {
+    coin: String,
+    #[serde(rename = "walletBalance")]
+    wallet_balance: String,
+    #[serde(default)]
+    locked: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrderResult {
+    #[serde(rename = "orderId")]
+    order_id: String,
+}
+
+// Public stream message: `{"topic": "...", "data": [...]}`, or an op reply
+#[derive(Debug, Deserialize)]
+struct WsEnvelope<T> {
+    topic: Option<String>,
+    data: Option<Vec<T>>,
+    op: Option<String>,
+    success: Option<bool>,
+    ret_msg: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WsKline {
+    start: i64,
+    open: String,
+    high: String,
+    low: String,
+    close: String,
+    volume: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WsTrade {
+    #[serde(rename = "T")]
+    trade_time: i64,
+    #[serde(rename = "S")]
+    side: String, // Taker side
+    #[serde(rename = "v")]
+    quantity: String,
+    #[serde(rename = "p")]
+    price: String,
+}
+
+// REST klines and public WebSocket streams for USDT perpetuals or spot
+pub struct BybitSource {
+    client: reqwest::Client,
+    market: MarketType,
+}
+
+impl BybitSource {
+    pub fn new(market: MarketType) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            market,
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        category(self.market)
+    }
+
+    fn ws_address(&self) -> &'static str {
+        match self.market {
+            MarketType::Futures => BYBIT_LINEAR_WSS_ADDRESS,
+            MarketType::Spot => BYBIT_SPOT_WSS_ADDRESS,
+        }
+    }
+
+    async fn get(&self, url: &str) -> Result<String, SourceError> {
+        let response = self.client.get(url).send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error(status).into());
+        }
+
+        Ok(response.text().await?)
+    }
+
+    async fn fetch_klines(
+        &self,
+        symbol: &str,
+        timeframe: &Timeframe,
+        params: &str,
+    ) -> Result<Vec<CandleData>, SourceError> {
+        let url = format!(
+            "{}/market/kline?category={}&symbol={}&interval={}&{}",
+            BYBIT_API_ADDRESS,
+            self.category(),
+            symbol,
+            bybit_interval(timeframe),
+            params
+        );
+        parse_klines(&self.get(&url).await?)
+    }
+
+    // Bybit pages from the newest candle in the window, so walk forward in
+    // windows that each fit in one page. Rows skipped on any page make the
+    // whole walk a `PartialPage`.
+    async fn fetch_klines_since(
+        &self,
+        symbol: &str,
+        timeframe: &Timeframe,
+        since: f64,
+    ) -> Result<Vec<CandleData>, SourceError> {
+        let window = (timeframe.get_candle_interval() * PAGE_LIMIT as f64 * 1000.0) as i64;
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut candles: Vec<CandleData> = Vec::new();
+        let mut partial: Option<PartialPage> = None;
+        let mut start = (since * 1000.0) as i64;
+
+        while start <= now {
+            let end = start + window - 1;
+            let page = self
+                .fetch_klines(
+                    symbol,
+                    timeframe,
+                    &format!("start={}&end={}&limit={}", start, end, PAGE_LIMIT),
+                )
+                .await;
+            match page {
+                Ok(page) => {
+                    if let Some(partial) = &mut partial {
+                        partial.total += page.len();
+                    }
+                    candles.extend(page);
+                }
+                Err(e) => {
+                    let page = e.downcast::<PartialPage>()?;
+                    let found = partial.get_or_insert(PartialPage {
+                        candles: Vec::new(),
+                        skipped: 0,
+                        total: candles.len(),
+                        reason: page.reason,
+                    });
+                    found.skipped += page.skipped;
+                    found.total += page.total;
+                    candles.extend(page.candles);
+                }
+            }
+            start = end + 1;
+        }
+
+        match partial {
+            None => Ok(candles),
+            Some(partial) => Err(PartialPage { candles, ..partial }.into()),
+        }
+    }
+
+    // Top 20 USDT pairs by 24h turnover
+    pub async fn get_top_volume_pairs(&self) -> Result<Vec<(String, f64)>, SourceError> {
+        let url = format!(
+            "{}/market/tickers?category={}",
+            BYBIT_API_ADDRESS,
+            self.category()
+        );
+        let mut pairs = parse_tickers(&self.get(&url).await?)?;
+
+        pairs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(pairs.into_iter().take(20).collect())
+    }
+
+    async fn subscribe(&self, topic: String) -> Result<BoxStream<'static, Result<String, SourceError>>, SourceError> {
+        let (mut ws_stream, _) = connect_async(self.ws_address()).await?;
+        let request = serde_json::json!({ "op": "subscribe", "args": [topic] });
+        ws_stream.send(WsMessage::Text(request.to_string().into())).await?;
+
+        let ping = tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+
+        // Yield text frames until the socket closes, pinging in between
+        let messages = futures_util::stream::unfold((ws_stream, ping), |(mut ws_stream, mut ping)| async move {
+            loop {
+                tokio::select! {
+                    _ = ping.tick() => {
+                        if let Err(e) = ws_stream.send(WsMessage::Text(r#"{"op":"ping"}"#.into())).await {
+                            return Some((Err(SourceError::from(e)), (ws_stream, ping)));
+                        }
+                    }
+                    msg = ws_stream.next() => match msg {
+                        Some(Ok(WsMessage::Text(text))) => return Some((Ok(text.as_str().to_string()), (ws_stream, ping))),
+                        Some(Ok(WsMessage::Close(_))) | None => return None,
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Some((Err(SourceError::from(e)), (ws_stream, ping))),
+                    }
+                }
+            }
+        });
+
+        Ok(messages.boxed())
+    }
+}
+
+impl MarketDataSource for BybitSource {
+    fn name(&self) -> &str {
+        match self.market {
+            MarketType::Futures => "Bybit Perpetuals",
+            MarketType::Spot => "Bybit Spot",
+        }
+    }
+
+    fn cache_namespace(&self) -> Option<&str> {
+        match self.market {
+            MarketType::Futures => Some("bybit-linear"),
+            MarketType::Spot => Some("bybit-spot"),
+        }
+    }
+
+    fn historical_klines<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+        range: KlineRange,
+    ) -> BoxFuture<'a, Result<Vec<CandleData>, SourceError>> {
+        Box::pin(async move {
+            match range {
+                KlineRange::Latest => {
+                    self.fetch_klines(symbol, timeframe, &format!("limit={}", LATEST_LIMIT))
+                        .await
+                }
+                KlineRange::Since(since) => self.fetch_klines_since(symbol, timeframe, since).await,
+                KlineRange::Before(before) => {
+                    self.fetch_klines(
+                        symbol,
+                        timeframe,
+                        &format!("end={}&limit={}", (before * 1000.0) as i64 - 1, PAGE_LIMIT),
+                    )
+                    .await
+                }
+            }
+        })
+    }
+
+    fn live_updates<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+    ) -> BoxFuture<'a, Result<CandleStream, SourceError>> {
+        Box::pin(async move {
+            let topic = format!("kline.{}.{}", bybit_interval(timeframe), symbol);
+            let messages = self.subscribe(topic).await?;
+
+            let updates = messages
+                .filter_map(|msg| async move {
+                    match msg.and_then(|text| parse_ws_klines(&text)) {
+                        Ok(candles) if candles.is_empty() => None,
+                        Ok(candles) => Some(futures_util::stream::iter(candles.into_iter().map(Ok)).left_stream()),
+                        Err(e) => Some(futures_util::stream::once(async move { Err(e) }).right_stream()),
+                    }
+                })
+                .flatten();
+
+            Ok(updates.boxed())
+        })
+    }
+
+    fn trade_updates<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, Result<TradeStream, SourceError>> {
+        Box::pin(async move {
+            let messages = self.subscribe(format!("publicTrade.{}", symbol)).await?;
+
+            let trades = messages
+                .filter_map(|msg| async move {
+                    match msg.and_then(|text| parse_ws_trades(&text)) {
+                        Ok(trades) if trades.is_empty() => None,
+                        Ok(trades) => Some(futures_util::stream::iter(trades.into_iter().map(Ok)).left_stream()),
+                        Err(e) => Some(futures_util::stream::once(async move { Err(e) }).right_stream()),
+                    }
+                })
+                .flatten();
+
+            Ok(trades.boxed())
+        })
+    }
+
+    fn symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, SourceError>> {
+        Box::pin(async move {
+            let pairs = self.get_top_volume_pairs().await?;
+            Ok(pairs
+                .into_iter()
+                .map(|(symbol, _)| SymbolInfo {
+                    base_asset: symbol.strip_suffix(QUOTE_ASSET).unwrap_or(&symbol).to_string(),
+                    quote_asset: QUOTE_ASSET.to_string(),
+                    symbol,
+                })
+                .collect())
+        })
+    }
+}
+
+// Signed v5 endpoints on a unified trading account, keyed by
+// BYBIT_API_KEY / BYBIT_API_SECRET
+pub struct BybitAccount {
+    client: reqwest::Client,
+    api_key: String,
+    api_secret: String,
+}
+
+impl BybitAccount {
+    pub fn from_env() -> Option<Self> {
+        let api_key = env::var("BYBIT_API_KEY").ok()?;
+        let api_secret = env::var("BYBIT_API_SECRET").ok()?;
+        Some(Self {
+            client: reqwest::Client::new(),
+            api_key,
+            api_secret,
+        })
+    }
+
+    // Signature covers timestamp + key + recv window + query string or JSON body
+    fn signed(&self, request: reqwest::RequestBuilder, payload: &str) -> reqwest::RequestBuilder {
+        let timestamp = chrono::Utc::now().timestamp_millis().to_string();
+        let signature = hmac_sha256(
+            &self.api_secret,
+            &format!("{}{}{}{}", timestamp, self.api_key, RECV_WINDOW, payload),
+        );
+        request
+            .header("X-BAPI-API-KEY", &self.api_key)
+            .header("X-BAPI-TIMESTAMP", timestamp)
+            .header("X-BAPI-RECV-WINDOW", RECV_WINDOW)
+            .header("X-BAPI-SIGN", signature)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<String, SourceError> {
+        let response = request.send().await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(format!("API error: {} {}", status, text).into());
+        }
+
+        Ok(text)
+    }
+}
+
+impl TradingAccount for BybitAccount {
+    fn name(&self) -> &str {
+        "Bybit Spot"
+    }
+
+    fn balances(&self) -> BoxFuture<'_, Result<HashMap<String, f64>, SourceError>> {
+        Box::pin(async move {
+            let query = "accountType=UNIFIED";
+            let request = self
+                .client
+                .get(format!("{}/account/wallet-balance?{}", BYBIT_API_ADDRESS, query));
+
+            parse_wallet_balances(&self.send(self.signed(request, query)).await?)
+        })
+    }
+
+    fn place_order<'a>(
+        &'a self,
+        symbol: &'a str,
+        order_type: &'a OrderType,
+        order_mode: &'a OrderMode,
+        quantity: &'a str,
+        price: &'a str,
+    ) -> BoxFuture<'a, Result<String, SourceError>> {
+        Box::pin(async move {
+            let side = match order_type {
+                OrderType::Buy => "Buy",
+                OrderType::Sell => "Sell",
+            };
+
+            // Spot market orders are sized in the quote coin unless told otherwise
+            let body = match order_mode {
+                OrderMode::Market => serde_json::json!({
+                    "category": category(MarketType::Spot),
+                    "symbol": symbol,
+                    "side": side,
+                    "orderType": "Market",
+                    "qty": quantity,
+                    "marketUnit": "baseCoin",
+                }),
+                OrderMode::Limit => serde_json::json!({
+                    "category": category(MarketType::Spot),
+                    "symbol": symbol,
+                    "side": side,
+                    "orderType": "Limit",
+                    "qty": quantity,
+                    "price": price,
+                    "timeInForce": "GTC",
+                }),
+            }
+            .to_string();
+
+            let request = self
+                .client
+                .post(format!("{}/order/create", BYBIT_API_ADDRESS))
+                .header("Content-Type", "application/json")
+                .body(body.clone());
+
+            let text = self.send(self.signed(request, &body)).await?;
+            parse_order_response(&text)
+        })
+    }
+}
+
+fn category(market: MarketType) -> &'static str {
+    match market {
+        MarketType::Futures => "linear",
+        MarketType::Spot => "spot",
+    }
+}
+
+pub fn bybit_interval(timeframe: &Timeframe) -> &'static str {
+    match timeframe {
+        Timeframe::M1 => "1",
+        Timeframe::M3 => "3",
+        Timeframe::M5 => "5",
+        Timeframe::M15 => "15",
+        Timeframe::M30 => "30",
+        Timeframe::H1 => "60",
+        Timeframe::H4 => "240",
+        Timeframe::H12 => "720",
+        Timeframe::D1 => "D",
+        Timeframe::W1 => "W",
+        Timeframe::MN1 => "M",
+        // Requested as their base interval by AggregatedSource
+        Timeframe::Custom(_) => bybit_interval(&timeframe.native_base().unwrap_or(Timeframe::M1)),
+    }
+}
+
+// Unwrap the v5 envelope, turning a non-zero retCode into an error
+// Bybit answers 403 when an IP exceeds the public rate limit
+pub fn status_error(status: reqwest::StatusCode) -> ApiError {
+    let mut error = ApiError::from_status(status);
+    error.rate_limited |= status.as_u16() == 403;
+    error
+}
+
+fn parse_result<T: DeserializeOwned>(text: &str) -> Result<T, SourceError> {
+    let envelope: Envelope = serde_json::from_str(text)?;
+    if envelope.ret_code != 0 {
+        return Err(ApiError {
+            message: format!("Bybit error {}: {}", envelope.ret_code, envelope.ret_msg),
+            rate_limited: envelope.ret_code == RATE_LIMIT_CODE,
+        }
+        .into());
+    }
+    Ok(serde_json::from_value(envelope.result)?)
+}
+
+fn parse_number(value: &str) -> Result<f64, SourceError> {
+    value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number {:?}", value).into())
+}
+
+fn parse_kline_row(row: &[String]) -> Result<CandleData, SourceError> {
+    match row {
+        [start, open, high, low, close, volume, ..] => Ok(CandleData {
+            timestamp: parse_number(start)? / 1000.0,
+            open: parse_number(open)?,
+            high: parse_number(high)?,
+            low: parse_number(low)?,
+            close: parse_number(close)?,
+            volume: parse_number(volume)?,
+        }),
+        _ => Err(format!("kline row has {} fields", row.len()).into()),
+    }
+}
+
+// Oldest first, like every other source. Malformed rows make it a
+// `PartialPage` error that still carries the other candles.
+pub fn parse_klines(text: &str) -> Result<Vec<CandleData>, SourceError> {
+    let result: KlineResult = parse_result(text)?;
+
+    let mut candles = Vec::new();
+    let mut errors = Vec::new();
+    for (index, row) in result.list.iter().enumerate() {
+        match parse_kline_row(row) {
+            Ok(candle) => candles.push(candle),
+            Err(e) => errors.push(format!("malformed kline at row {}: {}", index, e)),
+        }
+    }
+    candles.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+    match errors.first() {
+        None => Ok(candles),
+        Some(first) => Err(PartialPage {
+            skipped: errors.len(),
+            total: candles.len() + errors.len(),
+            reason: first.clone(),
+            candles,
+        }
+        .into()),
+    }
+}
+
+fn parse_tickers(text: &str) -> Result<Vec<(String, f64)>, SourceError> {
+    let result: TickerResult = parse_result(text)?;
+
+    Ok(result
+        .list
+        .into_iter()
+        .filter(|ticker| ticker.symbol.ends_with(QUOTE_ASSET))
+        .filter_map(|ticker| {
+            let turnover = ticker.turnover_24h.parse::<f64>().ok()?;
+            Some((ticker.symbol, turnover))
+        })
+        .collect())
+}
+
+// Free balance per coin, zero balances left out
+fn parse_wallet_balances(text: &str) -> Result<HashMap<String, f64>, SourceError> {
+    let result: WalletResult = parse_result(text)?;
+
+    Ok(result
+        .list
+        .into_iter()
+        .flat_map(|account| account.coin)
+        .filter_map(|coin| {
+            let balance = coin.wallet_balance.parse::<f64>().ok()?;
+            let free = balance - coin.locked.parse::<f64>().unwrap_or(0.0);
+            (free > 0.0).then_some((coin.coin, free))
+        })
+        .collect())
+}
+
+fn parse_order_response(text: &str) -> Result<String, SourceError> {
+    let order: OrderResult = parse_result(text)?;
+    Ok(format!("order {} submitted", order.order_id))
+}
+
+// Topic data from one stream message; empty for pongs and acks
+fn parse_ws_data<T: DeserializeOwned>(text: &str) -> Result<Vec<T>, SourceError> {
+    let message: WsEnvelope<T> = serde_json::from_str(text)?;
+
+    if message.success == Some(false) {
+        return Err(format!(
+            "Bybit {} failed: {}",
+            message.op.unwrap_or_default(),
+            message.ret_msg.unwrap_or_default()
+        )
+        .into());
+    }
+
+    match (message.topic, message.data) {
+        (Some(_), Some(data)) => Ok(data),
+        _ => Ok(Vec::new()),
+    }
+}
+
+pub fn parse_ws_klines(text: &str) -> Result<Vec<CandleData>, SourceError> {
+    parse_ws_data::<WsKline>(text)?
+        .into_iter()
+        .map(|kline| {
+            Ok(CandleData {
+                timestamp: kline.start as f64 / 1000.0,
+                open: parse_number(&kline.open)?,
+                high: parse_number(&kline.high)?,
+                low: parse_number(&kline.low)?,
+                close: parse_number(&kline.close)?,
+                volume: parse_number(&kline.volume)?,
+            })
+        })
+        .collect()
+}
+
+// Unreadable frames come back as MalformedTrade so the feed can report them
+// and keep going; a failed subscription stays a plain error
+pub fn parse_ws_trades(text: &str) -> Result<Vec<Trade>, SourceError> {
+    let malformed = |e: SourceError| -> SourceError { MalformedTrade(e.to_string()).into() };
+    let trades = parse_ws_data::<WsTrade>(text).map_err(|e| {
+        if e.is::<serde_json::Error>() {
+            malformed(e)
+        } else {
+            e
+        }
+    })?;
+
+    trades
+        .into_iter()
+        .map(|trade| {
+            Ok(Trade {
+                timestamp: trade.trade_time as f64 / 1000.0,
+                price: parse_number(&trade.price).map_err(malformed)?,
+                quantity: parse_number(&trade.quantity).map_err(malformed)?,
+                is_buyer_maker: trade.side == "Sell",
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn klines_are_returned_oldest_first() {
+        let candles = parse_klines(include_str!("fixtures/bybit_kline.json")).unwrap();
+
+        assert_eq!(candles.len(), 3);
+        assert_eq!(candles[0].timestamp, 1_700_000_040.0);
+        assert_eq!(candles[2].timestamp, 1_700_000_160.0);
+        assert_eq!(candles[2].open, 37_120.5);
+        assert_eq!(candles[2].high, 37_135.0);
+        assert_eq!(candles[2].low, 37_101.1);
+        assert_eq!(candles[2].close, 37_130.2);
+        assert_eq!(candles[2].volume, 12.482);
+    }
+
+    #[test]
+    fn malformed_row_keeps_the_rest_of_the_page() {
+        let err = parse_klines(include_str!("fixtures/bybit_kline_malformed.json")).unwrap_err();
+        let partial = err.downcast_ref::<PartialPage>().unwrap();
+
+        assert_eq!((partial.skipped, partial.total), (1, 3));
+        assert_eq!(partial.candles.len(), 2);
+        assert_eq!(partial.candles[0].timestamp, 1_700_000_040.0);
+        assert_eq!(partial.candles[1].timestamp, 1_700_000_160.0);
+        assert_eq!(err.to_string(), "skipped 1 of 3 rows: malformed kline at row 1: invalid number \"n/a\"");
+    }
+
+    #[test]
+    fn api_errors_are_surfaced() {
+        let err = parse_klines(include_str!("fixtures/bybit_error.json")).unwrap_err();
+        assert!(err.to_string().contains("10001"), "{}", err);
+        assert!(!crate::exchange::is_rate_limited(&err));
+    }
+
+    #[test]
+    fn rate_limits_are_flagged() {
+        let err = parse_klines(include_str!("fixtures/bybit_rate_limit.json")).unwrap_err();
+        assert!(crate::exchange::is_rate_limited(&err), "{}", err);
+
+        assert!(status_error(reqwest::StatusCode::FORBIDDEN).rate_limited);
+        assert!(status_error(reqwest::StatusCode::TOO_MANY_REQUESTS).rate_limited);
+        assert!(!status_error(reqwest::StatusCode::SERVICE_UNAVAILABLE).rate_limited);
+    }
+
+    #[test]
+    fn ws_kline_message_parses_forming_candle() {
+        let candles = parse_ws_klines(include_str!("fixtures/bybit_ws_kline.json")).unwrap();
+
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].timestamp, 1_700_000_160.0);
+        assert_eq!(candles[0].close, 37_131.0);
+        assert_eq!(candles[0].volume, 13.01);
+
+        // Subscription acks and pongs carry no candles
+        let ack = r#"{"success":true,"ret_msg":"","conn_id":"abc","op":"subscribe"}"#;
+        assert!(parse_ws_klines(ack).unwrap().is_empty());
+    }
+
+    #[test]
+    fn ws_trades_map_taker_side() {
+        let trades = parse_ws_trades(include_str!("fixtures/bybit_ws_trade.json")).unwrap();
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].price, 37_130.5);
+        assert_eq!(trades[0].quantity, 0.004);
+        assert!(!trades[0].is_buyer_maker);
+        assert!(trades[1].is_buyer_maker);
+        assert_eq!(trades[1].timestamp, 1_700_000_170.123);
+    }
+
+    #[test]
+    fn failed_subscription_is_an_error() {
+        let reply = r#"{"success":false,"ret_msg":"error:handler not found","conn_id":"abc","op":"subscribe"}"#;
+        let error = parse_ws_trades(reply).unwrap_err();
+        assert!(!error.is::<MalformedTrade>());
+    }
+
+    #[test]
+    fn malformed_trade_frames_are_flagged() {
+        let bad_price = include_str!("fixtures/bybit_ws_trade.json").replace("37130.50", "n/a");
+        let error = parse_ws_trades(&bad_price).unwrap_err();
+        assert_eq!(error.to_string(), "malformed trade: invalid number \"n/a\"");
+
+        assert!(parse_ws_trades(r#"{"topic":"publicTrade.BTCUSDT","data":[{"T":"x"}]}"#)
+            .unwrap_err()
+            .is::<MalformedTrade>());
+    }
+
+    #[test]
+    fn tickers_wallet_and_order_responses() {
+        let pairs = parse_tickers(include_str!("fixtures/bybit_tickers.json")).unwrap();
+        assert_eq!(
+            pairs,
+            vec![("BTCUSDT".to_string(), 2_512_345_678.5), ("ETHUSDT".to_string(), 1_034_567_890.0)]
+        );
+
+        let balances = parse_wallet_balances(include_str!("fixtures/bybit_wallet.json")).unwrap();
+        assert_eq!(balances.get("USDT"), Some(&900.0));
+        assert_eq!(balances.get("BTC"), Some(&0.015));
+        assert!(!balances.contains_key("ETH"));
+
+        let confirmation = parse_order_response(include_str!("fixtures/bybit_order.json")).unwrap();
+        assert_eq!(confirmation, "order 1321003749386327552 submitted");
+    }
+}
@@ -0,0 +1,187 @@
+use crate::exchange::{
+    default_symbols, CandleStream, KlineRange, MarketDataSource, SourceError, SymbolInfo,
+    QUOTE_ASSET,
+};
+use crate::{CandleData, Timeframe};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use std::time::Duration;
+
+const LATEST_LIMIT: usize = 500;
+const HISTORY_LIMIT: usize = 1000;
+
+// Deterministic in-process market for demos and tests. Every candle is a
+// pure function of (seed, symbol, open time), so overlapping requests always
+// agree and the same seed reproduces the same chart.
+pub struct MockSource {
+    seed: u64,
+    tick: Duration,
+    now: Option<f64>, // Fixed clock in seconds; wall clock when None
+}
+
+impl MockSource {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            tick: Duration::from_millis(500),
+            now: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_clock(seed: u64, now: f64, tick: Duration) -> Self {
+        Self {
+            seed,
+            tick,
+            now: Some(now),
+        }
+    }
+
+    fn now(&self) -> f64 {
+        self.now
+            .unwrap_or_else(|| chrono::Utc::now().timestamp_millis() as f64 / 1000.0)
+    }
+
+    fn symbol_seed(&self, symbol: &str) -> u64 {
+        symbol
+            .bytes()
+            .fold(self.seed, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64))
+    }
+
+    fn price_at(&self, symbol_seed: u64, timestamp: f64, interval: f64) -> f64 {
+        let base = 100.0 + (symbol_seed % 1000) as f64;
+        let phase = timestamp / (interval * 50.0);
+        let mut rng = fastrand::Rng::with_seed(symbol_seed ^ (timestamp as u64));
+        base * (1.0 + 0.05 * phase.sin() + 0.02 * (phase * 3.7).cos() + 0.004 * (rng.f64() - 0.5))
+    }
+
+    // Candle opening at `open_time`. `progress` in 0..=1 is how much of the
+    // interval has elapsed; anything below 1 is a forming candle.
+    fn candle_at(&self, symbol: &str, interval: f64, open_time: f64, progress: f64) -> CandleData {
+        let symbol_seed = self.symbol_seed(symbol);
+        let open = self.price_at(symbol_seed, open_time - interval, interval);
+        let target = self.price_at(symbol_seed, open_time, interval);
+        let close = open + (target - open) * progress.clamp(0.0, 1.0);
+
+        let mut rng = fastrand::Rng::with_seed(symbol_seed.rotate_left(17) ^ (open_time as u64));
+        let wick = open.abs() * 0.002;
+        CandleData {
+            timestamp: open_time,
+            open,
+            high: open.max(close) + wick * rng.f64() * progress,
+            low: open.min(close) - wick * rng.f64() * progress,
+            close,
+            volume: (50.0 + 200.0 * rng.f64()) * progress,
+        }
+    }
+
+    // Calendar-aligned, so monthly candles open on the 1st
+    fn candles_between(&self, symbol: &str, timeframe: &Timeframe, from: f64, to: f64) -> Vec<CandleData> {
+        let now = self.now();
+        let interval = timeframe.get_candle_interval();
+        let mut open_time = timeframe.candle_open(from);
+        if open_time < from {
+            open_time = timeframe.candle_close(open_time);
+        }
+        let mut candles = Vec::new();
+
+        while open_time <= to && open_time <= now {
+            let close_time = timeframe.candle_close(open_time);
+            let progress = ((now - open_time) / (close_time - open_time)).min(1.0);
+            candles.push(self.candle_at(symbol, interval, open_time, progress));
+            open_time = close_time;
+        }
+
+        candles
+    }
+}
+
+impl MarketDataSource for MockSource {
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    fn historical_klines<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+        range: KlineRange,
+    ) -> BoxFuture<'a, Result<Vec<CandleData>, SourceError>> {
+        Box::pin(async move {
+            let interval = timeframe.get_candle_interval();
+            let now = self.now();
+            let current_open = timeframe.candle_open(now);
+
+            let candles = match range {
+                KlineRange::Latest => self.candles_between(
+                    symbol,
+                    timeframe,
+                    current_open - (LATEST_LIMIT - 1) as f64 * interval,
+                    now,
+                ),
+                KlineRange::Since(since) => self.candles_between(symbol, timeframe, since, now),
+                KlineRange::Before(before) => {
+                    let last_open = timeframe.candle_open(before - 0.5);
+                    self.candles_between(
+                        symbol,
+                        timeframe,
+                        last_open - (HISTORY_LIMIT - 1) as f64 * interval,
+                        last_open,
+                    )
+                }
+            };
+
+            Ok(candles)
+        })
+    }
+
+    fn live_updates<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+    ) -> BoxFuture<'a, Result<CandleStream, SourceError>> {
+        Box::pin(async move {
+            let interval = timeframe.get_candle_interval();
+            let timeframe = timeframe.clone();
+            let source = MockSource {
+                seed: self.seed,
+                tick: self.tick,
+                now: self.now,
+            };
+            let symbol = symbol.to_string();
+            let tick = self.tick;
+
+            // With a fixed clock each tick advances simulated time by one tick
+            let updates = futures_util::stream::unfold(0u32, move |n| {
+                let candle = {
+                    let now = match source.now {
+                        Some(start) => start + tick.as_secs_f64() * (n + 1) as f64,
+                        None => source.now(),
+                    };
+                    let open_time = timeframe.candle_open(now);
+                    let progress = (now - open_time) / (timeframe.candle_close(open_time) - open_time);
+                    source.candle_at(&symbol, interval, open_time, progress)
+                };
+                async move {
+                    tokio::time::sleep(tick).await;
+                    Some((Ok(candle), n + 1))
+                }
+            });
+
+            Ok(updates.boxed())
+        })
+    }
+
+    fn symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, SourceError>> {
+        Box::pin(async move {
+            Ok(default_symbols()
+                .into_iter()
+                .map(|symbol| SymbolInfo {
+                    base_asset: symbol.strip_suffix(QUOTE_ASSET).unwrap_or(&symbol).to_string(),
+                    quote_asset: QUOTE_ASSET.to_string(),
+                    symbol,
+                })
+                .collect())
+        })
+    }
+}
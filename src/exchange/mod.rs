@@ -0,0 +1,254 @@
+use crate::orderbook::OrderBook;
+use crate::{CandleData, Timeframe};
+use account::{BinanceAccount, TradingAccount};
+use aggregated::AggregatedSource;
+use binance::BinanceSource;
+use bybit::{BybitAccount, BybitSource};
+use futures_util::future::BoxFuture;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+pub mod account;
+pub mod aggregated;
+pub mod binance;
+pub mod bybit;
+pub mod mock;
+
+// Symbols that are always offered in the selector, even before the
+// 24h volume ranking has been fetched.
+pub static DEFAULT_ARR: [&str; 14] = [
+    "BTC", "ETH", "XRP", "SOL", "DOT", "TRX", "TON", "SHIB", "DOGE", "PEPE", "BNB", "SUI", "XLM",
+    "ADA",
+];
+
+pub static QUOTE_ASSET: &str = "USDT";
+
+pub type SourceError = Box<dyn std::error::Error + Send + Sync>;
+
+// Non-success reply from an exchange REST endpoint
+#[derive(Debug)]
+pub struct ApiError {
+    pub message: String,
+    pub rate_limited: bool,
+}
+
+impl ApiError {
+    pub fn from_status(status: reqwest::StatusCode) -> Self {
+        Self {
+            message: format!("API error: {}", status),
+            // 418 is Binance's auto-ban after ignoring 429s
+            rate_limited: status.as_u16() == 429 || status.as_u16() == 418,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// A feed the source does not offer at all, so there is nothing to retry
+#[derive(Debug)]
+pub struct Unsupported {
+    pub source: String,
+    pub feed: &'static str,
+}
+
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} has no {} feed", self.source, self.feed)
+    }
+}
+
+impl std::error::Error for Unsupported {}
+
+// A page of candles with rows that could not be parsed. The readable ones
+// are kept so one bad row does not cost the whole page.
+#[derive(Debug)]
+pub struct PartialPage {
+    pub candles: Vec<CandleData>,
+    pub skipped: usize,
+    pub total: usize,
+    pub reason: String, // Why the first skipped row was rejected
+}
+
+impl std::fmt::Display for PartialPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "skipped {} of {} rows: {}", self.skipped, self.total, self.reason)
+    }
+}
+
+impl std::error::Error for PartialPage {}
+
+// The usable candles of a page that failed only partially, and the error to
+// report for the rest
+pub fn salvage_page(result: Result<Vec<CandleData>, SourceError>) -> Result<(Vec<CandleData>, Option<String>), SourceError> {
+    match result {
+        Ok(candles) => Ok((candles, None)),
+        Err(e) => match e.downcast::<PartialPage>() {
+            Ok(partial) => {
+                let message = partial.to_string();
+                Ok((partial.candles, Some(message)))
+            }
+            Err(e) => Err(e),
+        },
+    }
+}
+
+pub fn is_rate_limited(error: &SourceError) -> bool {
+    error
+        .downcast_ref::<ApiError>()
+        .map(|e| e.rate_limited)
+        .unwrap_or(false)
+}
+
+// Stream of candle updates; the same open time may be sent repeatedly while
+// that candle is still forming. The stream ends when the connection drops.
+pub type CandleStream = BoxStream<'static, Result<CandleData, SourceError>>;
+
+// Executed trades in the order the exchange reports them
+pub type TradeStream = BoxStream<'static, Result<Trade, SourceError>>;
+
+// The order book's best levels after each applied update
+pub type DepthStream = BoxStream<'static, Result<OrderBook, SourceError>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ExchangeKind {
+    Binance,
+    Bybit,
+}
+
+impl ExchangeKind {
+    pub fn to_display_string(self) -> &'static str {
+        match self {
+            ExchangeKind::Binance => "Binance",
+            ExchangeKind::Bybit => "Bybit",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarketType {
+    Futures, // USDⓈ-M perpetuals
+    Spot,
+}
+
+impl MarketType {
+    pub fn to_display_string(self) -> &'static str {
+        match self {
+            MarketType::Futures => "USDⓈ-M Futures",
+            MarketType::Spot => "Spot",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KlineRange {
+    Latest,       // The most recent page
+    Since(f64),   // Everything from this open time (seconds) up to now
+    Before(f64),  // One page of candles that opened before this time
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trade {
+    pub timestamp: f64, // Seconds
+    pub price: f64,
+    pub quantity: f64,
+    pub is_buyer_maker: bool, // True when the taker sold
+}
+
+// A trade message that could not be read; the trades in it are missing from
+// the tape
+#[derive(Debug, PartialEq)]
+pub struct MalformedTrade(pub String);
+
+impl std::fmt::Display for MalformedTrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "malformed trade: {}", self.0)
+    }
+}
+
+impl std::error::Error for MalformedTrade {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub base_asset: String,
+    pub quote_asset: String,
+}
+
+// Where candles come from: an exchange, a recording or an in-process mock
+pub trait MarketDataSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    // Subdirectory of the candle cache, or None if candles should not be cached
+    fn cache_namespace(&self) -> Option<&str> {
+        None
+    }
+
+    fn historical_klines<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+        range: KlineRange,
+    ) -> BoxFuture<'a, Result<Vec<CandleData>, SourceError>>;
+
+    fn live_updates<'a>(
+        &'a self,
+        symbol: &'a str,
+        timeframe: &'a Timeframe,
+    ) -> BoxFuture<'a, Result<CandleStream, SourceError>>;
+
+    // Sources without a trade tape return a stream that never yields
+    fn trade_updates<'a>(&'a self, _symbol: &'a str) -> BoxFuture<'a, Result<TradeStream, SourceError>> {
+        Box::pin(async { Ok(futures_util::stream::pending().boxed()) })
+    }
+
+    // Sources without an order book say so with an `Unsupported` error
+    fn depth_updates<'a>(&'a self, _symbol: &'a str) -> BoxFuture<'a, Result<DepthStream, SourceError>> {
+        let error = Unsupported { source: self.name().to_string(), feed: "order book" };
+        Box::pin(async move { Err(error.into()) })
+    }
+
+    fn symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, SourceError>>;
+}
+
+pub fn create_source(exchange: ExchangeKind, market: MarketType) -> Arc<dyn MarketDataSource> {
+    match exchange {
+        ExchangeKind::Binance => Arc::new(AggregatedSource::new(Arc::new(BinanceSource::new(market)))),
+        ExchangeKind::Bybit => Arc::new(AggregatedSource::new(Arc::new(BybitSource::new(market)))),
+    }
+}
+
+// Spot trading account for the exchange, if its API keys are set
+pub fn create_account(exchange: ExchangeKind) -> Option<Arc<dyn TradingAccount>> {
+    match exchange {
+        ExchangeKind::Binance => BinanceAccount::from_env().map(|account| Arc::new(account) as Arc<dyn TradingAccount>),
+        ExchangeKind::Bybit => BybitAccount::from_env().map(|account| Arc::new(account) as Arc<dyn TradingAccount>),
+    }
+}
+
+pub fn default_symbols() -> Vec<String> {
+    DEFAULT_ARR
+        .iter()
+        .map(|base| format!("{}{}", base, QUOTE_ASSET))
+        .collect()
+}
+
+// "ETHUSDT" -> "ETH"
+pub fn base_asset(symbol: &str) -> &str {
+    symbol.strip_suffix(QUOTE_ASSET).unwrap_or(symbol)
+}
+
+// Keep the default list first and append any top-volume pairs not already in it
+pub fn merge_symbols(symbols: &mut Vec<String>, extra: Vec<String>) {
+    for symbol in extra {
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+}
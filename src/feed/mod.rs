@@ -0,0 +1,1116 @@
+use crate::exchange::{self, KlineRange, MalformedTrade, MarketDataSource, SourceError, Trade};
+use crate::orderbook::{MalformedDiff, OrderBook, SequenceGap};
+use crate::store::CandleStore;
+use crate::{CandleData, Timeframe};
+use futures_util::StreamExt;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+// Reconnect delays double from the base up to the cap; rate limits always
+// wait at least RATE_LIMIT_DELAY
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+const RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+const OFFLINE_AFTER: u32 = 5; // Consecutive failures
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Retrying { attempt: u32, delay: Duration },
+    RateLimited { delay: Duration },
+    Offline { delay: Duration }, // Still retrying, but nothing has worked for a while
+}
+
+impl ConnectionState {
+    fn retry_delay(&self) -> Duration {
+        match self {
+            ConnectionState::Retrying { delay, .. }
+            | ConnectionState::RateLimited { delay }
+            | ConnectionState::Offline { delay } => *delay,
+            ConnectionState::Connecting | ConnectionState::Connected => Duration::ZERO,
+        }
+    }
+}
+
+// What to do after `failures` consecutive failed attempts
+fn retry_state(failures: u32, rate_limited: bool) -> ConnectionState {
+    let delay = BACKOFF_BASE
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(BACKOFF_MAX);
+
+    if rate_limited {
+        ConnectionState::RateLimited { delay: delay.max(RATE_LIMIT_DELAY) }
+    } else if failures >= OFFLINE_AFTER {
+        ConnectionState::Offline { delay }
+    } else {
+        ConnectionState::Retrying { attempt: failures, delay }
+    }
+}
+
+// Error message plus whether the exchange asked us to slow down
+fn failure(error: SourceError) -> (String, bool) {
+    (error.to_string(), exchange::is_rate_limited(&error))
+}
+
+// UI-side summary of the feed connection, built from FeedEvents
+pub struct ConnectionHealth {
+    pub state: ConnectionState,
+    state_since: Instant,
+    last_update: Option<Instant>,
+    pub last_error: Option<String>,
+}
+
+impl Default for ConnectionHealth {
+    fn default() -> Self {
+        Self {
+            state: ConnectionState::Connecting,
+            state_since: Instant::now(),
+            last_update: None,
+            last_error: None,
+        }
+    }
+}
+
+impl ConnectionHealth {
+    pub fn apply(&mut self, event: &FeedEvent) {
+        match event {
+            FeedEvent::Candles(candles) if !candles.is_empty() => {
+                self.last_update = Some(Instant::now())
+            }
+            FeedEvent::Candles(_) => {}
+            FeedEvent::State(state) => {
+                self.state = state.clone();
+                self.state_since = Instant::now();
+            }
+            FeedEvent::Error(e) => self.last_error = Some(e.clone()),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state == ConnectionState::Connected
+    }
+
+    // Time left before the next attempt while waiting to reconnect
+    pub fn retry_in(&self) -> Duration {
+        self.state
+            .retry_delay()
+            .saturating_sub(self.state_since.elapsed())
+    }
+
+    pub fn last_update_age(&self) -> Option<Duration> {
+        self.last_update.map(|instant| instant.elapsed())
+    }
+}
+
+// On-disk cache for a source, if that source wants one
+pub fn open_store(
+    source: &dyn MarketDataSource,
+    cache_dir: &Path,
+    symbol: &str,
+    timeframe: &Timeframe,
+) -> Option<CandleStore> {
+    let namespace = source.cache_namespace()?;
+    match CandleStore::open(&cache_dir.join(namespace), symbol, &timeframe.to_api_string()) {
+        Ok(store) => Some(store),
+        Err(e) => {
+            eprintln!("Error opening candle cache: {}", e);
+            None
+        }
+    }
+}
+
+// What the data feed reports to the UI
+pub enum FeedEvent {
+    Candles(Vec<CandleData>), // New or updated candles, oldest first
+    State(ConnectionState),
+    Error(String),            // Most recent failure, shown in the status area
+}
+
+// Keep `candle_data` up to date for one symbol/timeframe and forward every
+// batch of new or updated candles to the UI
+pub async fn run_data_feed(
+    source: Arc<dyn MarketDataSource>,
+    tx: mpsc::UnboundedSender<FeedEvent>,
+    candle_data: Arc<Mutex<VecDeque<CandleData>>>,
+    symbol: String,
+    timeframe: Timeframe,
+    max_candles: usize,
+    store: Option<CandleStore>,
+) {
+    let mut persisted_until = f64::MIN;
+
+    // Start from the cached candles so only the missing tail is downloaded.
+    // However stale the cache is, the backfill continues from its last
+    // candle so the file never holds a hole.
+    if let Some(store) = &store {
+        match store.load() {
+            Ok(cached) => {
+                let cached = &cached[cached.len().saturating_sub(max_candles)..];
+                if let Some(last) = cached.last() {
+                    persisted_until = last.timestamp;
+                    merge_candles(&candle_data, cached, max_candles);
+                    if tx.send(FeedEvent::Candles(cached.to_vec())).is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Error reading candle cache: {}", e);
+            }
+        }
+    }
+
+    let mut failures = 0;
+
+    loop {
+        if tx.send(FeedEvent::State(ConnectionState::Connecting)).is_err() {
+            return;
+        }
+
+        // Backfill: the latest page on first connect, otherwise everything
+        // since the last candle we hold (covers reconnect gaps)
+        let last_timestamp = candle_data
+            .lock()
+            .ok()
+            .and_then(|data| data.back().map(|candle| candle.timestamp));
+
+        let range = match last_timestamp {
+            Some(timestamp) => KlineRange::Since(timestamp),
+            None => KlineRange::Latest,
+        };
+
+        let backfill = source.historical_klines(&symbol, &timeframe, range).await;
+        let backfill = exchange::salvage_page(backfill).map_err(failure);
+
+        // Only go live once the gap is filled, so a failed backfill is retried
+        let (message, rate_limited) = match backfill {
+            Ok((candles, skipped)) => {
+                if let Some(message) = skipped {
+                    eprintln!("Malformed klines from {}: {}", source.name(), message);
+                    if tx.send(FeedEvent::Error(message)).is_err() {
+                        return;
+                    }
+                }
+
+                // Written straight away, since a long backfill may not all fit in memory
+                persist_closed(store.as_ref(), &candles, &mut persisted_until);
+                merge_candles(&candle_data, &candles, max_candles);
+                if tx.send(FeedEvent::Candles(candles)).is_err() {
+                    return;
+                }
+
+                // Live updates of the forming candle
+                let connection = source
+                    .live_updates(&symbol, &timeframe)
+                    .await
+                    .map_err(failure);
+
+                match connection {
+                    Ok(mut updates) => {
+                        if tx.send(FeedEvent::State(ConnectionState::Connected)).is_err() {
+                            return;
+                        }
+
+                        let mut error = None;
+                        while let Some(update) = updates.next().await {
+                            match update {
+                                Ok(candle) => {
+                                    failures = 0;
+                                    merge_candles(&candle_data, std::slice::from_ref(&candle), max_candles);
+                                    persist_closed_candles(store.as_ref(), &candle_data, &mut persisted_until);
+                                    if tx.send(FeedEvent::Candles(vec![candle])).is_err() {
+                                        return;
+                                    }
+                                }
+                                Err(e) => {
+                                    error = Some(failure(e));
+                                    break;
+                                }
+                            }
+                        }
+                        error.unwrap_or_else(|| ("connection closed by server".to_string(), false))
+                    }
+                    Err(e) => e,
+                }
+            }
+            Err(e) => e,
+        };
+
+        if tx.is_closed() {
+            return;
+        }
+
+        failures += 1;
+        eprintln!("Error in {} feed: {}", source.name(), message);
+        let state = retry_state(failures, rate_limited);
+        let delay = state.retry_delay();
+        if tx.send(FeedEvent::Error(message)).is_err() || tx.send(FeedEvent::State(state)).is_err() {
+            return;
+        }
+
+        tokio::time::sleep(delay).await;
+    }
+}
+
+// What the trade feed reports to the UI
+pub enum TradeEvent {
+    Trade(Trade),
+    Error(String), // Shown above the tape until the feed is restarted
+}
+
+// Forward executed trades for one symbol, reconnecting when the stream drops.
+// An unreadable trade message is reported and skipped without reconnecting.
+pub async fn run_trade_feed(
+    source: Arc<dyn MarketDataSource>,
+    tx: mpsc::UnboundedSender<TradeEvent>,
+    symbol: String,
+) {
+    let mut failures = 0;
+
+    loop {
+        let connection = source.trade_updates(&symbol).await.map_err(failure);
+
+        let (message, rate_limited) = match connection {
+            Ok(mut trades) => {
+                let mut error = None;
+                while let Some(trade) = trades.next().await {
+                    match trade {
+                        Ok(trade) => {
+                            failures = 0;
+                            if tx.send(TradeEvent::Trade(trade)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) if e.is::<MalformedTrade>() => {
+                            if tx.send(TradeEvent::Error(e.to_string())).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            error = Some(failure(e));
+                            break;
+                        }
+                    }
+                }
+                error.unwrap_or_else(|| ("connection closed by server".to_string(), false))
+            }
+            Err(e) => e,
+        };
+
+        if tx.is_closed() {
+            return;
+        }
+
+        failures += 1;
+        eprintln!("Error in {} trade stream: {}", source.name(), message);
+        let _ = tx.send(TradeEvent::Error(message));
+        tokio::time::sleep(retry_state(failures, rate_limited).retry_delay()).await;
+    }
+}
+
+// What the depth feed reports to the UI
+pub enum DepthEvent {
+    Book(OrderBook),
+    Error(String), // Shown with the book until the next one arrives
+}
+
+// Forward the order book for one symbol. Each reconnect starts from a new
+// snapshot; a gap in the diffs or an unreadable one resyncs without
+// reconnecting.
+pub async fn run_depth_feed(
+    source: Arc<dyn MarketDataSource>,
+    tx: mpsc::UnboundedSender<DepthEvent>,
+    symbol: String,
+) {
+    let mut failures = 0;
+
+    loop {
+        let connection = match source.depth_updates(&symbol).await {
+            Err(e) if e.is::<exchange::Unsupported>() => {
+                let _ = tx.send(DepthEvent::Error(e.to_string()));
+                return;
+            }
+            connection => connection.map_err(failure),
+        };
+
+        let (message, rate_limited) = match connection {
+            Ok(mut books) => {
+                let mut error = None;
+                while let Some(book) = books.next().await {
+                    match book {
+                        Ok(book) => {
+                            failures = 0;
+                            if tx.send(DepthEvent::Book(book)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) if e.is::<SequenceGap>() || e.is::<MalformedDiff>() => {
+                            if tx.send(DepthEvent::Error(format!("Resyncing: {}", e))).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            error = Some(failure(e));
+                            break;
+                        }
+                    }
+                }
+                error.unwrap_or_else(|| ("connection closed by server".to_string(), false))
+            }
+            Err(e) => e,
+        };
+
+        if tx.is_closed() {
+            return;
+        }
+
+        failures += 1;
+        eprintln!("Error in {} depth stream: {}", source.name(), message);
+        let _ = tx.send(DepthEvent::Error(message));
+        tokio::time::sleep(retry_state(failures, rate_limited).retry_delay()).await;
+    }
+}
+
+// How many candles a history page prepended, and why any of its rows had to
+// be skipped; or why it failed and whether the exchange asked us to slow down
+pub type HistoryPage = Result<(usize, Option<String>), (String, bool)>;
+
+// Spaces out retries of older history pages after failures, with the same
+// delays as reconnects
+#[derive(Default)]
+pub struct HistoryBackoff {
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl HistoryBackoff {
+    pub fn failed(&mut self, rate_limited: bool, now: Instant) {
+        self.failures += 1;
+        self.retry_at = Some(now + retry_state(self.failures, rate_limited).retry_delay());
+    }
+
+    pub fn succeeded(&mut self) {
+        *self = Self::default();
+    }
+
+    // Whether another page may be requested at `now`
+    pub fn ready(&self, now: Instant) -> bool {
+        self.retry_at.is_none_or(|retry_at| now >= retry_at)
+    }
+}
+
+// One page of history before `before`, served from the cache when possible
+pub async fn load_older_history(
+    source: Arc<dyn MarketDataSource>,
+    candle_data: Arc<Mutex<VecDeque<CandleData>>>,
+    symbol: String,
+    timeframe: Timeframe,
+    before: f64,
+    max_candles: usize,
+    store: Option<CandleStore>,
+) -> HistoryPage {
+    let cached = store
+        .as_ref()
+        .and_then(|store| store.load_before(before, 1000).ok())
+        .unwrap_or_default();
+
+    let cached = contiguous_until(&cached, &timeframe, before);
+    if !cached.is_empty() {
+        return Ok((prepend_candles(&candle_data, cached, max_candles), None));
+    }
+
+    let page = source.historical_klines(&symbol, &timeframe, KlineRange::Before(before)).await;
+    let (candles, skipped) = exchange::salvage_page(page).map_err(failure)?;
+
+    if let Some(store) = &store {
+        if let Err(e) = store.append(&candles) {
+            eprintln!("Error writing candle cache: {}", e);
+        }
+    }
+
+    Ok((prepend_candles(&candle_data, &candles, max_candles), skipped))
+}
+
+// The newest run of `cached` that leads up to `before` without a hole; empty
+// if the cache does not reach `before` at all
+fn contiguous_until<'a>(cached: &'a [CandleData], timeframe: &Timeframe, before: f64) -> &'a [CandleData] {
+    let mut next_open = before;
+    let mut start = cached.len();
+    for (index, candle) in cached.iter().enumerate().rev() {
+        if (timeframe.candle_close(candle.timestamp) - next_open).abs() >= 1.0 {
+            break;
+        }
+        next_open = candle.timestamp;
+        start = index;
+    }
+    &cached[start..]
+}
+
+// Insert new candles or replace ones with the same open time
+pub fn merge_candles(candle_data: &Arc<Mutex<VecDeque<CandleData>>>, candles: &[CandleData], max_candles: usize) {
+    if let Ok(mut data) = candle_data.lock() {
+        for new_candle in candles {
+            let latest_existing_time = data.back().map(|d| d.timestamp).unwrap_or(f64::MIN);
+
+            if new_candle.timestamp > latest_existing_time + 0.5 {
+                data.push_back(new_candle.clone());
+            } else if let Some(existing_pos) = data
+                .iter()
+                .rposition(|existing| (existing.timestamp - new_candle.timestamp).abs() < 1.0)
+            {
+                data[existing_pos] = new_candle.clone();
+            }
+        }
+
+        while data.len() > max_candles {
+            data.pop_front();
+        }
+    }
+}
+
+// Append candles of `candles` (oldest first) that have closed since the last
+// write. The newest one is still forming and is left out until the next one opens.
+fn persist_closed(store: Option<&CandleStore>, candles: &[CandleData], persisted_until: &mut f64) {
+    let Some(store) = store else {
+        return;
+    };
+
+    let closed = &candles[..candles.len().saturating_sub(1)];
+    let start = closed.partition_point(|candle| candle.timestamp <= *persisted_until);
+    let closed = &closed[start..];
+
+    if let Some(last) = closed.last() {
+        match store.append(closed) {
+            Ok(()) => *persisted_until = last.timestamp,
+            Err(e) => eprintln!("Error writing candle cache: {}", e),
+        }
+    }
+}
+
+// The same for the candles held in memory
+fn persist_closed_candles(
+    store: Option<&CandleStore>,
+    candle_data: &Arc<Mutex<VecDeque<CandleData>>>,
+    persisted_until: &mut f64,
+) {
+    if store.is_none() {
+        return;
+    }
+
+    let mut recent: Vec<CandleData> = match candle_data.lock() {
+        Ok(data) => data
+            .iter()
+            .rev()
+            .take_while(|candle| candle.timestamp > *persisted_until)
+            .cloned()
+            .collect(),
+        Err(_) => return,
+    };
+    recent.reverse();
+    persist_closed(store, &recent, persisted_until);
+}
+
+// Add older candles in front of the loaded range, stopping at the cap.
+// Returns how many were added.
+pub fn prepend_candles(candle_data: &Arc<Mutex<VecDeque<CandleData>>>, candles: &[CandleData], max_candles: usize) -> usize {
+    let mut added = 0;
+
+    if let Ok(mut data) = candle_data.lock() {
+        for candle in candles.iter().rev() {
+            if data.len() >= max_candles {
+                break;
+            }
+
+            let oldest_existing_time = data.front().map(|d| d.timestamp).unwrap_or(f64::MAX);
+            if candle.timestamp < oldest_existing_time - 0.5 {
+                data.push_front(candle.clone());
+                added += 1;
+            }
+        }
+    }
+
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::mock::MockSource;
+    use std::time::Duration;
+
+    fn candle(timestamp: f64, close: f64) -> CandleData {
+        CandleData {
+            timestamp,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    fn shared(candles: Vec<CandleData>) -> Arc<Mutex<VecDeque<CandleData>>> {
+        Arc::new(Mutex::new(candles.into_iter().collect()))
+    }
+
+    fn timestamps(candle_data: &Arc<Mutex<VecDeque<CandleData>>>) -> Vec<f64> {
+        candle_data.lock().unwrap().iter().map(|c| c.timestamp).collect()
+    }
+
+    fn wall_clock() -> f64 {
+        chrono::Utc::now().timestamp() as f64
+    }
+
+    async fn next_event(rx: &mut mpsc::UnboundedReceiver<FeedEvent>) -> FeedEvent {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("feed did not send in time")
+            .expect("feed stopped")
+    }
+
+    // Next batch of candles, skipping connection state changes
+    async fn recv(rx: &mut mpsc::UnboundedReceiver<FeedEvent>) -> Vec<CandleData> {
+        loop {
+            match next_event(rx).await {
+                FeedEvent::Candles(candles) => return candles,
+                FeedEvent::State(_) => {}
+                FeedEvent::Error(e) => panic!("feed reported an error: {}", e),
+            }
+        }
+    }
+
+    // Mock market whose first `failures` backfills fail, and whose order book
+    // falls out of sequence once
+    struct FlakySource {
+        inner: MockSource,
+        failures: std::sync::atomic::AtomicU32,
+        error: fn() -> SourceError, // What the failing backfills return
+    }
+
+    fn unavailable() -> SourceError {
+        exchange::ApiError::from_status(reqwest::StatusCode::SERVICE_UNAVAILABLE).into()
+    }
+
+    impl MarketDataSource for FlakySource {
+        fn name(&self) -> &str {
+            "Flaky"
+        }
+
+        fn historical_klines<'a>(
+            &'a self,
+            symbol: &'a str,
+            timeframe: &'a Timeframe,
+            range: KlineRange,
+        ) -> futures_util::future::BoxFuture<'a, Result<Vec<CandleData>, SourceError>> {
+            use std::sync::atomic::Ordering;
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                let error = (self.error)();
+                return Box::pin(async move { Err(error) });
+            }
+            self.inner.historical_klines(symbol, timeframe, range)
+        }
+
+        fn live_updates<'a>(
+            &'a self,
+            symbol: &'a str,
+            timeframe: &'a Timeframe,
+        ) -> futures_util::future::BoxFuture<'a, Result<exchange::CandleStream, SourceError>> {
+            self.inner.live_updates(symbol, timeframe)
+        }
+
+        fn symbols(
+            &self,
+        ) -> futures_util::future::BoxFuture<'_, Result<Vec<exchange::SymbolInfo>, SourceError>> {
+            self.inner.symbols()
+        }
+
+        // A book, a gap in the diffs, the resynced book, then an unreadable diff
+        fn depth_updates<'a>(
+            &'a self,
+            _symbol: &'a str,
+        ) -> futures_util::future::BoxFuture<'a, Result<exchange::DepthStream, SourceError>> {
+            let book = |id| OrderBook::from_snapshot(exchange::MarketType::Spot, id, Vec::new(), Vec::new());
+            let gap = SequenceGap { last_update_id: 10, first_update_id: 15 };
+            let malformed = MalformedDiff("invalid bid level".to_string());
+            let events: Vec<Result<OrderBook, SourceError>> =
+                vec![Ok(book(10)), Err(gap.into()), Ok(book(20)), Err(malformed.into())];
+            Box::pin(async move {
+                Ok(futures_util::stream::iter(events).chain(futures_util::stream::pending()).boxed())
+            })
+        }
+
+        fn trade_updates<'a>(
+            &'a self,
+            _symbol: &'a str,
+        ) -> futures_util::future::BoxFuture<'a, Result<exchange::TradeStream, SourceError>> {
+            let trade = |price| Trade { timestamp: 0.0, price, quantity: 1.0, is_buyer_maker: false };
+            let malformed = MalformedTrade("invalid price \"n/a\"".to_string());
+            let events: Vec<Result<Trade, SourceError>> = vec![Ok(trade(100.0)), Err(malformed.into()), Ok(trade(101.0))];
+            Box::pin(async move {
+                Ok(futures_util::stream::iter(events).chain(futures_util::stream::pending()).boxed())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn depth_gaps_are_reported_without_reconnecting() {
+        let source: Arc<dyn MarketDataSource> = Arc::new(FlakySource {
+            inner: MockSource::new(7),
+            failures: std::sync::atomic::AtomicU32::new(0),
+            error: unavailable,
+        });
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_depth_feed(source, tx, "BTCUSDT".to_string()));
+
+        let mut events = Vec::new();
+        for _ in 0..4 {
+            let event = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+            events.push(match event {
+                DepthEvent::Book(book) => format!("book {}", book.last_update_id),
+                DepthEvent::Error(e) => e,
+            });
+        }
+        assert_eq!(
+            events,
+            vec![
+                "book 10".to_string(),
+                "Resyncing: order book out of sync: at update 10, next diff starts at 15".to_string(),
+                "book 20".to_string(),
+                "Resyncing: malformed depth update: invalid bid level".to_string(),
+            ]
+        );
+
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn malformed_trades_are_reported_without_reconnecting() {
+        let source: Arc<dyn MarketDataSource> = Arc::new(FlakySource {
+            inner: MockSource::new(7),
+            failures: std::sync::atomic::AtomicU32::new(0),
+            error: unavailable,
+        });
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_trade_feed(source, tx, "BTCUSDT".to_string()));
+
+        let mut events = Vec::new();
+        for _ in 0..3 {
+            let event = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+            events.push(match event {
+                TradeEvent::Trade(trade) => format!("trade {}", trade.price),
+                TradeEvent::Error(e) => e,
+            });
+        }
+        assert_eq!(
+            events,
+            vec![
+                "trade 100".to_string(),
+                "malformed trade: invalid price \"n/a\"".to_string(),
+                "trade 101".to_string(),
+            ]
+        );
+        assert!(rx.try_recv().is_err());
+
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn sources_without_an_order_book_say_so_once() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_depth_feed(Arc::new(MockSource::new(7)), tx, "BTCUSDT".to_string()));
+
+        tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+        match rx.recv().await {
+            Some(DepthEvent::Error(e)) => assert_eq!(e, "Mock has no order book feed"),
+            _ => panic!("expected an error"),
+        }
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[test]
+    fn merge_replaces_forming_candle_and_appends_new_ones() {
+        let data = shared(vec![candle(0.0, 1.0), candle(60.0, 2.0)]);
+
+        merge_candles(&data, &[candle(60.0, 3.0), candle(120.0, 4.0)], 10);
+
+        let data = data.lock().unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[1].close, 3.0);
+        assert_eq!(data[2].timestamp, 120.0);
+    }
+
+    #[test]
+    fn merge_drops_oldest_candles_over_the_cap() {
+        let data = shared(vec![candle(0.0, 1.0), candle(60.0, 2.0)]);
+
+        merge_candles(&data, &[candle(120.0, 3.0), candle(180.0, 4.0)], 3);
+
+        assert_eq!(timestamps(&data), vec![60.0, 120.0, 180.0]);
+    }
+
+    #[test]
+    fn prepend_skips_overlap_and_stops_at_the_cap() {
+        let data = shared(vec![candle(180.0, 1.0), candle(240.0, 2.0)]);
+
+        let added = prepend_candles(
+            &data,
+            &[candle(0.0, 1.0), candle(60.0, 1.0), candle(120.0, 1.0), candle(180.0, 9.0)],
+            4,
+        );
+
+        assert_eq!(added, 2);
+        assert_eq!(timestamps(&data), vec![60.0, 120.0, 180.0, 240.0]);
+        assert_eq!(data.lock().unwrap()[2].close, 1.0);
+    }
+
+    #[tokio::test]
+    async fn feed_backfills_latest_page_then_streams_updates() {
+        let now = wall_clock();
+        let source: Arc<dyn MarketDataSource> =
+            Arc::new(MockSource::with_clock(7, now, Duration::from_millis(5)));
+        let data = shared(Vec::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let task = tokio::spawn(run_data_feed(
+            source,
+            tx,
+            data.clone(),
+            "BTCUSDT".to_string(),
+            Timeframe::M1,
+            10000,
+            None,
+        ));
+
+        let backfill = recv(&mut rx).await;
+        assert_eq!(backfill.len(), 500);
+        assert!(backfill.windows(2).all(|pair| pair[1].timestamp - pair[0].timestamp == 60.0));
+
+        let update = recv(&mut rx).await;
+        assert_eq!(update.len(), 1);
+        assert!(update[0].timestamp >= backfill[499].timestamp);
+
+        let data = data.lock().unwrap();
+        assert!(data.len() == 500 || data.len() == 501);
+        assert_eq!(data.back().unwrap().timestamp, update[0].timestamp);
+        drop(data);
+
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn feed_resumes_from_cache_and_fetches_only_the_tail() {
+        let now = wall_clock();
+        let source = MockSource::with_clock(7, now, Duration::from_secs(60));
+        let current_open = (now / 60.0).floor() * 60.0;
+
+        let dir = std::env::temp_dir().join(format!("asterism-feed-test-{}", std::process::id()));
+        let store = CandleStore::open(&dir, "BTCUSDT", "1m").unwrap();
+        let cached = source
+            .historical_klines("BTCUSDT", &Timeframe::M1, KlineRange::Before(current_open - 600.0))
+            .await
+            .unwrap();
+        store.append(&cached).unwrap();
+
+        let data = shared(Vec::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_data_feed(
+            Arc::new(source),
+            tx,
+            data.clone(),
+            "BTCUSDT".to_string(),
+            Timeframe::M1,
+            10000,
+            Some(CandleStore::open(&dir, "BTCUSDT", "1m").unwrap()),
+        ));
+
+        let from_cache = recv(&mut rx).await;
+        assert_eq!(from_cache.len(), cached.len());
+
+        // Only the 10 missing candles plus the forming one are downloaded
+        let tail = recv(&mut rx).await;
+        assert_eq!(tail.first().unwrap().timestamp, current_open - 660.0);
+        assert_eq!(tail.last().unwrap().timestamp, current_open);
+        assert_eq!(tail.len(), 12);
+        assert_eq!(data.lock().unwrap().len(), cached.len() + 11);
+
+        task.abort();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn older_history_is_prepended_before_the_oldest_candle() {
+        let now = wall_clock();
+        let source: Arc<dyn MarketDataSource> =
+            Arc::new(MockSource::with_clock(7, now, Duration::from_secs(60)));
+        let latest = source
+            .historical_klines("BTCUSDT", &Timeframe::M1, KlineRange::Latest)
+            .await
+            .unwrap();
+        let oldest = latest[0].timestamp;
+        let data = shared(latest);
+
+        let (added, skipped) = load_older_history(
+            source,
+            data.clone(),
+            "BTCUSDT".to_string(),
+            Timeframe::M1,
+            oldest,
+            1200,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!((added, skipped), (700, None));
+        let timestamps = timestamps(&data);
+        assert_eq!(timestamps.len(), 1200);
+        assert_eq!(timestamps[699], oldest - 60.0);
+        assert!(timestamps.windows(2).all(|pair| pair[1] > pair[0]));
+    }
+
+    #[tokio::test]
+    async fn failed_history_page_is_retried_after_a_backoff() {
+        let now = wall_clock();
+        let source = Arc::new(FlakySource {
+            inner: MockSource::with_clock(7, now, Duration::from_secs(60)),
+            failures: std::sync::atomic::AtomicU32::new(1),
+            error: unavailable,
+        });
+        let oldest = (now / 60.0).floor() * 60.0;
+        let data = shared(vec![candle(oldest, 1.0)]);
+        let load = || {
+            load_older_history(source.clone(), data.clone(), "BTCUSDT".to_string(), Timeframe::M1, oldest, 10000, None)
+        };
+
+        let mut backoff = HistoryBackoff::default();
+        let failed_at = Instant::now();
+        let (message, rate_limited) = load().await.unwrap_err();
+        assert_eq!((message.as_str(), rate_limited), ("API error: 503 Service Unavailable", false));
+        backoff.failed(rate_limited, failed_at);
+        assert!(!backoff.ready(failed_at));
+        assert!(backoff.ready(failed_at + BACKOFF_BASE));
+
+        // The retry gets the page, and history carries on from there
+        assert_eq!(load().await.unwrap(), (1000, None));
+        backoff.succeeded();
+        assert!(backoff.ready(failed_at));
+        assert_eq!(data.lock().unwrap()[999].timestamp, oldest - 60.0);
+    }
+
+    #[tokio::test]
+    async fn cached_history_with_a_hole_is_fetched_instead() {
+        let now = wall_clock();
+        let source: Arc<dyn MarketDataSource> =
+            Arc::new(MockSource::with_clock(7, now, Duration::from_secs(60)));
+        let latest = source
+            .historical_klines("BTCUSDT", &Timeframe::M1, KlineRange::Latest)
+            .await
+            .unwrap();
+        let oldest = latest[0].timestamp;
+
+        // The cache ends an hour before the loaded range, then three
+        // candles lead right up to it
+        let dir = std::env::temp_dir().join(format!("asterism-feed-gap-test-{}", std::process::id()));
+        let store = CandleStore::open(&dir, "BTCUSDT", "1m").unwrap();
+        let before_hole = source
+            .historical_klines("BTCUSDT", &Timeframe::M1, KlineRange::Before(oldest - 3600.0))
+            .await
+            .unwrap();
+        store.append(&before_hole).unwrap();
+        store.append(&[candle(oldest - 180.0, 1.0), candle(oldest - 120.0, 1.0), candle(oldest - 60.0, 1.0)]).unwrap();
+
+        let data = shared(latest.clone());
+        let load = |data, before| {
+            load_older_history(
+                source.clone(),
+                data,
+                "BTCUSDT".to_string(),
+                Timeframe::M1,
+                before,
+                10000,
+                Some(CandleStore::open(&dir, "BTCUSDT", "1m").unwrap()),
+            )
+        };
+
+        // Only the run next to the loaded range comes from the cache
+        assert_eq!(load(data.clone(), oldest).await.unwrap(), (3, None));
+        assert_eq!(data.lock().unwrap()[0].close, 1.0);
+
+        // The next page would cross the hole, so it is downloaded
+        assert_eq!(load(data.clone(), oldest - 180.0).await.unwrap(), (1000, None));
+        let timestamps = timestamps(&data);
+        assert!(timestamps.windows(2).all(|pair| pair[1] - pair[0] == 60.0));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn stale_cache_is_backfilled_from_its_tail() {
+        let now = wall_clock();
+        let source = MockSource::with_clock(7, now, Duration::from_secs(60));
+        let current_open = (now / 60.0).floor() * 60.0;
+
+        // Older than the 20 candles kept in memory
+        let dir = std::env::temp_dir().join(format!("asterism-feed-stale-test-{}", std::process::id()));
+        let store = CandleStore::open(&dir, "BTCUSDT", "1m").unwrap();
+        let cached = source
+            .historical_klines("BTCUSDT", &Timeframe::M1, KlineRange::Before(current_open - 3000.0))
+            .await
+            .unwrap();
+        store.append(&cached).unwrap();
+
+        let data = shared(Vec::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_data_feed(
+            Arc::new(source),
+            tx,
+            data.clone(),
+            "BTCUSDT".to_string(),
+            Timeframe::M1,
+            20,
+            Some(CandleStore::open(&dir, "BTCUSDT", "1m").unwrap()),
+        ));
+
+        assert_eq!(recv(&mut rx).await.len(), 20);
+        let tail = recv(&mut rx).await;
+        assert_eq!(tail.first().unwrap().timestamp, current_open - 3060.0);
+        assert_eq!(tail.last().unwrap().timestamp, current_open);
+
+        // Everything but the forming candle is on disk, without a hole
+        let stored = store.load().unwrap();
+        assert_eq!(stored.last().unwrap().timestamp, current_open - 60.0);
+        assert!(stored.windows(2).all(|pair| pair[1].timestamp - pair[0].timestamp == 60.0));
+        assert_eq!(data.lock().unwrap().len(), 20);
+
+        task.abort();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        let delays: Vec<u64> = (1..=8)
+            .map(|failures| retry_state(failures, false).retry_delay().as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+
+        assert_eq!(
+            retry_state(2, false),
+            ConnectionState::Retrying { attempt: 2, delay: Duration::from_secs(2) }
+        );
+        assert_eq!(
+            retry_state(OFFLINE_AFTER, false),
+            ConnectionState::Offline { delay: Duration::from_secs(16) }
+        );
+        assert_eq!(
+            retry_state(1, true),
+            ConnectionState::RateLimited { delay: RATE_LIMIT_DELAY }
+        );
+        assert_eq!(retry_state(u32::MAX, false).retry_delay(), BACKOFF_MAX);
+    }
+
+    #[test]
+    fn health_tracks_state_error_and_last_update() {
+        let mut health = ConnectionHealth::default();
+        assert_eq!(health.state, ConnectionState::Connecting);
+        assert!(health.last_update_age().is_none());
+
+        health.apply(&FeedEvent::Error("API error: 503".to_string()));
+        health.apply(&FeedEvent::State(retry_state(1, false)));
+        assert!(!health.is_connected());
+        assert!(health.retry_in() <= Duration::from_secs(1));
+        assert_eq!(health.last_error.as_deref(), Some("API error: 503"));
+
+        health.apply(&FeedEvent::State(ConnectionState::Connected));
+        health.apply(&FeedEvent::Candles(vec![candle(0.0, 1.0)]));
+        assert!(health.is_connected());
+        assert_eq!(health.retry_in(), Duration::ZERO);
+        assert!(health.last_update_age().unwrap() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn failed_backfill_is_reported_and_retried() {
+        let source: Arc<dyn MarketDataSource> = Arc::new(FlakySource {
+            inner: MockSource::with_clock(7, wall_clock(), Duration::from_millis(5)),
+            failures: std::sync::atomic::AtomicU32::new(1),
+            error: unavailable,
+        });
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_data_feed(
+            source,
+            tx,
+            shared(Vec::new()),
+            "BTCUSDT".to_string(),
+            Timeframe::M1,
+            10000,
+            None,
+        ));
+
+        assert!(matches!(next_event(&mut rx).await, FeedEvent::State(ConnectionState::Connecting)));
+        match next_event(&mut rx).await {
+            FeedEvent::Error(e) => assert!(e.contains("503"), "{}", e),
+            _ => panic!("expected the backfill error"),
+        }
+        assert!(matches!(
+            next_event(&mut rx).await,
+            FeedEvent::State(ConnectionState::Retrying { attempt: 1, .. })
+        ));
+
+        // The retry succeeds and the feed goes live
+        assert!(matches!(next_event(&mut rx).await, FeedEvent::State(ConnectionState::Connecting)));
+        match next_event(&mut rx).await {
+            FeedEvent::Candles(candles) => assert_eq!(candles.len(), 500),
+            _ => panic!("expected the backfill"),
+        }
+        assert!(matches!(next_event(&mut rx).await, FeedEvent::State(ConnectionState::Connected)));
+
+        task.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited_backfills_wait_out_the_limit() {
+        // As the exchanges report them: Binance's 429 and 418, Bybit's 10006
+        let responses: [fn() -> SourceError; 3] = [
+            || exchange::ApiError::from_status(reqwest::StatusCode::TOO_MANY_REQUESTS).into(),
+            || exchange::ApiError::from_status(reqwest::StatusCode::IM_A_TEAPOT).into(),
+            || exchange::bybit::parse_klines(include_str!("../exchange/fixtures/bybit_rate_limit.json")).unwrap_err(),
+        ];
+
+        for error in responses {
+            let source: Arc<dyn MarketDataSource> = Arc::new(FlakySource {
+                inner: MockSource::new(7),
+                failures: std::sync::atomic::AtomicU32::new(1),
+                error,
+            });
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            let task = tokio::spawn(run_data_feed(
+                source,
+                tx,
+                shared(Vec::new()),
+                "BTCUSDT".to_string(),
+                Timeframe::M1,
+                10000,
+                None,
+            ));
+
+            assert!(matches!(next_event(&mut rx).await, FeedEvent::State(ConnectionState::Connecting)));
+            assert!(matches!(next_event(&mut rx).await, FeedEvent::Error(_)));
+            match next_event(&mut rx).await {
+                FeedEvent::State(ConnectionState::RateLimited { delay }) => assert_eq!(delay, RATE_LIMIT_DELAY),
+                _ => panic!("expected the feed to back off"),
+            }
+
+            // Nothing is retried before the delay is up
+            let limited_at = tokio::time::Instant::now();
+            assert!(tokio::time::timeout(RATE_LIMIT_DELAY - BACKOFF_BASE, rx.recv()).await.is_err());
+            assert!(matches!(next_event(&mut rx).await, FeedEvent::State(ConnectionState::Connecting)));
+            assert!(limited_at.elapsed() >= RATE_LIMIT_DELAY);
+
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,1182 @@
+use crate::{CandleData, Timeframe};
+use eframe::egui::Color32;
+use std::collections::VecDeque;
+use streaming::Calculator;
+
+mod streaming;
+
+// Colors handed out to new indicator instances in turn
+const PALETTE: [Color32; 8] = [
+    Color32::from_rgb(255, 215, 0),
+    Color32::from_rgb(0, 150, 255),
+    Color32::from_rgb(255, 100, 255),
+    Color32::from_rgb(0, 220, 180),
+    Color32::from_rgb(255, 140, 60),
+    Color32::from_rgb(170, 130, 255),
+    Color32::from_rgb(140, 220, 80),
+    Color32::from_rgb(240, 240, 240),
+];
+
+const BAND_COLOR: Color32 = Color32::from_rgb(128, 128, 128);
+const SIGNAL_COLOR: Color32 = Color32::from_rgb(255, 150, 0);
+const PLUS_COLOR: Color32 = Color32::from_rgb(0, 200, 100);
+const MINUS_COLOR: Color32 = Color32::from_rgb(255, 100, 100);
+const KIJUN_COLOR: Color32 = Color32::from_rgb(200, 60, 90);
+const CLOUD_UP_COLOR: Color32 = Color32::from_rgba_premultiplied(0, 60, 30, 60);
+const CLOUD_DOWN_COLOR: Color32 = Color32::from_rgba_premultiplied(70, 20, 20, 60);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Placement {
+    Overlay, // Drawn on the price chart
+    Subpane, // Own chart below the price chart
+}
+
+// An indicator type together with its parameters
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndicatorConfig {
+    Sma { period: usize },
+    Ema { period: usize },
+    Bollinger { period: usize, std_dev: f64 },
+    Macd { fast: usize, slow: usize, signal: usize },
+    Rsi { period: usize },
+    SessionVwap,                  // Resets at 00:00 UTC
+    AnchoredVwap { anchor: f64 }, // Accumulates from `anchor` (Unix seconds) on
+    Obv,
+    Stochastic { period: usize, smooth_k: usize, smooth_d: usize },
+    Atr { period: usize },
+    Adx { period: usize, smoothing: usize },
+    Cci { period: usize },
+    Ichimoku { tenkan: usize, kijun: usize, senkou_b: usize, displacement: usize },
+    Supertrend { period: usize, multiplier: f64 },
+}
+
+// Every indicator that can be added, with its default parameters
+pub fn registry() -> Vec<IndicatorConfig> {
+    vec![
+        IndicatorConfig::Sma { period: 20 },
+        IndicatorConfig::Ema { period: 20 },
+        IndicatorConfig::Bollinger { period: 20, std_dev: 2.0 },
+        IndicatorConfig::Macd { fast: 12, slow: 26, signal: 9 },
+        IndicatorConfig::Rsi { period: 14 },
+        IndicatorConfig::SessionVwap,
+        IndicatorConfig::AnchoredVwap { anchor: 0.0 },
+        IndicatorConfig::Obv,
+        IndicatorConfig::Stochastic { period: 14, smooth_k: 3, smooth_d: 3 },
+        IndicatorConfig::Atr { period: 14 },
+        IndicatorConfig::Adx { period: 14, smoothing: 14 },
+        IndicatorConfig::Cci { period: 20 },
+        IndicatorConfig::Ichimoku { tenkan: 9, kijun: 26, senkou_b: 52, displacement: 26 },
+        IndicatorConfig::Supertrend { period: 10, multiplier: 3.0 },
+    ]
+}
+
+// A parameter as the add/remove dialog edits it
+pub enum Param<'a> {
+    Period(&'static str, &'a mut usize),
+    Factor(&'static str, &'a mut f64),
+    Time(&'static str, &'a mut f64), // Unix seconds
+}
+
+impl IndicatorConfig {
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            IndicatorConfig::Sma { .. } => "MA",
+            IndicatorConfig::Ema { .. } => "EMA",
+            IndicatorConfig::Bollinger { .. } => "Bollinger",
+            IndicatorConfig::Macd { .. } => "MACD",
+            IndicatorConfig::Rsi { .. } => "RSI",
+            IndicatorConfig::SessionVwap => "VWAP",
+            IndicatorConfig::AnchoredVwap { .. } => "Anchored VWAP",
+            IndicatorConfig::Obv => "OBV",
+            IndicatorConfig::Stochastic { .. } => "Stochastic",
+            IndicatorConfig::Atr { .. } => "ATR",
+            IndicatorConfig::Adx { .. } => "ADX/DMI",
+            IndicatorConfig::Cci { .. } => "CCI",
+            IndicatorConfig::Ichimoku { .. } => "Ichimoku",
+            IndicatorConfig::Supertrend { .. } => "Supertrend",
+        }
+    }
+
+    // Kind plus parameters, e.g. "MA 25" or "MACD 12/26/9"
+    pub fn name(&self) -> String {
+        match self {
+            IndicatorConfig::Sma { period } => format!("MA {}", period),
+            IndicatorConfig::Ema { period } => format!("EMA {}", period),
+            IndicatorConfig::Bollinger { period, std_dev } => format!("BB {} {}σ", period, std_dev),
+            IndicatorConfig::Macd { fast, slow, signal } => {
+                format!("MACD {}/{}/{}", fast, slow, signal)
+            }
+            IndicatorConfig::Rsi { period } => format!("RSI {}", period),
+            IndicatorConfig::SessionVwap => "VWAP".to_string(),
+            IndicatorConfig::AnchoredVwap { .. } => "AVWAP".to_string(),
+            IndicatorConfig::Obv => "OBV".to_string(),
+            IndicatorConfig::Stochastic { period, smooth_k, smooth_d } => {
+                format!("Stoch {}/{}/{}", period, smooth_k, smooth_d)
+            }
+            IndicatorConfig::Atr { period } => format!("ATR {}", period),
+            IndicatorConfig::Adx { period, smoothing } => format!("ADX {}/{}", period, smoothing),
+            IndicatorConfig::Cci { period } => format!("CCI {}", period),
+            IndicatorConfig::Ichimoku { tenkan, kijun, senkou_b, displacement } => {
+                format!("Ichimoku {}/{}/{}/{}", tenkan, kijun, senkou_b, displacement)
+            }
+            IndicatorConfig::Supertrend { period, multiplier } => {
+                format!("Supertrend {} {}", period, multiplier)
+            }
+        }
+    }
+
+    pub fn placement(&self) -> Placement {
+        match self {
+            IndicatorConfig::Sma { .. }
+            | IndicatorConfig::Ema { .. }
+            | IndicatorConfig::Bollinger { .. }
+            | IndicatorConfig::SessionVwap
+            | IndicatorConfig::AnchoredVwap { .. }
+            | IndicatorConfig::Ichimoku { .. }
+            | IndicatorConfig::Supertrend { .. } => Placement::Overlay,
+            IndicatorConfig::Macd { .. }
+            | IndicatorConfig::Rsi { .. }
+            | IndicatorConfig::Obv
+            | IndicatorConfig::Stochastic { .. }
+            | IndicatorConfig::Atr { .. }
+            | IndicatorConfig::Adx { .. }
+            | IndicatorConfig::Cci { .. } => Placement::Subpane,
+        }
+    }
+
+    pub fn params_mut(&mut self) -> Vec<Param<'_>> {
+        match self {
+            IndicatorConfig::Sma { period }
+            | IndicatorConfig::Ema { period }
+            | IndicatorConfig::Rsi { period }
+            | IndicatorConfig::Atr { period }
+            | IndicatorConfig::Cci { period } => vec![Param::Period("Period", period)],
+            IndicatorConfig::Bollinger { period, std_dev } => vec![
+                Param::Period("Period", period),
+                Param::Factor("Std dev", std_dev),
+            ],
+            IndicatorConfig::Macd { fast, slow, signal } => vec![
+                Param::Period("Fast", fast),
+                Param::Period("Slow", slow),
+                Param::Period("Signal", signal),
+            ],
+            IndicatorConfig::Stochastic { period, smooth_k, smooth_d } => vec![
+                Param::Period("Period", period),
+                Param::Period("%K", smooth_k),
+                Param::Period("%D", smooth_d),
+            ],
+            IndicatorConfig::Adx { period, smoothing } => vec![
+                Param::Period("DI", period),
+                Param::Period("Smoothing", smoothing),
+            ],
+            IndicatorConfig::Ichimoku { tenkan, kijun, senkou_b, displacement } => vec![
+                Param::Period("Tenkan", tenkan),
+                Param::Period("Kijun", kijun),
+                Param::Period("Senkou B", senkou_b),
+                Param::Period("Shift", displacement),
+            ],
+            IndicatorConfig::Supertrend { period, multiplier } => vec![
+                Param::Period("ATR", period),
+                Param::Factor("Multiplier", multiplier),
+            ],
+            IndicatorConfig::AnchoredVwap { anchor } => vec![Param::Time("Anchor", anchor)],
+            IndicatorConfig::SessionVwap | IndicatorConfig::Obv => Vec::new(),
+        }
+    }
+
+    // Fixed y range for bounded oscillators
+    pub fn y_bounds(&self) -> Option<(f64, f64)> {
+        match self {
+            IndicatorConfig::Rsi { .. } | IndicatorConfig::Stochastic { .. } => Some((0.0, 100.0)),
+            _ => None,
+        }
+    }
+
+    // Candles the given output is drawn ahead of the candle it comes from
+    pub fn forward_shift(&self, output: usize) -> usize {
+        match self {
+            IndicatorConfig::Ichimoku { displacement, .. } if output >= 2 => *displacement,
+            _ => 0,
+        }
+    }
+
+    // Horizontal guide lines drawn in the indicator's pane
+    pub fn levels(&self) -> Vec<f64> {
+        match self {
+            IndicatorConfig::Rsi { .. } => vec![30.0, 50.0, 70.0],
+            IndicatorConfig::Stochastic { .. } => vec![20.0, 80.0],
+            IndicatorConfig::Adx { .. } => vec![25.0],
+            IndicatorConfig::Cci { .. } => vec![-100.0, 0.0, 100.0],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeriesStyle {
+    Line { width: f32 },
+    Histogram, // Bars from zero, colored by sign
+}
+
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+    pub color: Color32,
+    pub style: SeriesStyle,
+}
+
+// Area between two outputs, colored by which one is on top
+pub struct Fill {
+    pub name: String,
+    pub points: Vec<(f64, f64, f64)>, // Time, first and second value
+    pub above_color: Color32,         // First above second
+    pub below_color: Color32,
+}
+
+// One indicator on the chart, with its values over the whole loaded series
+pub struct IndicatorInstance {
+    pub id: u64,
+    pub config: IndicatorConfig,
+    pub color: Color32,
+    timeframe: Option<Timeframe>, // Own candle series instead of the chart's
+    cache: IndicatorCache,
+}
+
+impl IndicatorInstance {
+    pub fn new(id: u64, config: IndicatorConfig) -> Self {
+        let cache = IndicatorCache::new(&config);
+        Self {
+            id,
+            config,
+            color: PALETTE[id as usize % PALETTE.len()],
+            timeframe: None,
+            cache,
+        }
+    }
+
+    pub fn timeframe(&self) -> Option<&Timeframe> {
+        self.timeframe.as_ref()
+    }
+
+    // Compute from candles of `timeframe`. Values then only show once their
+    // candle has closed and hold until the next one closes.
+    pub fn set_timeframe(&mut self, timeframe: Option<Timeframe>) {
+        if timeframe != self.timeframe {
+            self.timeframe = timeframe;
+            self.reset();
+        }
+    }
+
+    // Name plus timeframe, e.g. "MA 20 (4h)"
+    pub fn label(&self) -> String {
+        match &self.timeframe {
+            Some(timeframe) => format!("{} ({})", self.config.name(), timeframe.to_display_string()),
+            None => self.config.name(),
+        }
+    }
+
+    // Drop cached values, e.g. when computing from different prices
+    pub fn reset(&mut self) {
+        self.cache = IndicatorCache::new(&self.config);
+    }
+
+    // Bring the cached values up to date with the loaded candles
+    pub fn sync(&mut self, data: &VecDeque<CandleData>) {
+        self.cache.sync(&self.config, data);
+    }
+
+    // Name, color and style of each output, in calculator order
+    fn series_styles(&self) -> Vec<(String, Color32, SeriesStyle)> {
+        let name = self.label();
+        let line = |suffix: &str, color, width| {
+            (format!("{}{}", name, suffix), color, SeriesStyle::Line { width })
+        };
+
+        match &self.config {
+            IndicatorConfig::Sma { .. } | IndicatorConfig::Ema { .. } => {
+                vec![line("", self.color, 2.5)]
+            }
+            IndicatorConfig::SessionVwap | IndicatorConfig::AnchoredVwap { .. } => {
+                vec![line("", self.color, 2.0)]
+            }
+            IndicatorConfig::Bollinger { .. } => vec![
+                line(" Upper", BAND_COLOR, 1.5),
+                line(" Middle", self.color, 1.5),
+                line(" Lower", BAND_COLOR, 1.5),
+            ],
+            IndicatorConfig::Macd { .. } => vec![
+                line("", self.color, 2.0),
+                line(" Signal", SIGNAL_COLOR, 2.0),
+                (format!("{} Histogram", name), self.color, SeriesStyle::Histogram),
+            ],
+            IndicatorConfig::Rsi { .. }
+            | IndicatorConfig::Obv
+            | IndicatorConfig::Atr { .. }
+            | IndicatorConfig::Cci { .. } => vec![line("", self.color, 2.0)],
+            IndicatorConfig::Stochastic { .. } => vec![
+                line(" %K", self.color, 2.0),
+                line(" %D", SIGNAL_COLOR, 1.5),
+            ],
+            IndicatorConfig::Adx { .. } => vec![
+                line("", self.color, 2.0),
+                line(" +DI", PLUS_COLOR, 1.5),
+                line(" -DI", MINUS_COLOR, 1.5),
+            ],
+            IndicatorConfig::Ichimoku { .. } => vec![
+                line(" Tenkan", self.color, 1.5),
+                line(" Kijun", KIJUN_COLOR, 1.5),
+                line(" Senkou A", PLUS_COLOR, 1.0),
+                line(" Senkou B", MINUS_COLOR, 1.0),
+            ],
+            IndicatorConfig::Supertrend { .. } => vec![
+                line(" Up", PLUS_COLOR, 2.0),
+                line(" Down", MINUS_COLOR, 2.0),
+            ],
+        }
+    }
+
+    // Where a value computed at the candle opening at `t` is drawn: shifted
+    // forward for projected outputs, and at the candle's close for values from
+    // another timeframe
+    fn drawn_at(&self, output: usize, t: f64) -> f64 {
+        let t = t + self.config.forward_shift(output) as f64 * self.cache.interval.unwrap_or(0.0);
+        match &self.timeframe {
+            Some(timeframe) => timeframe.candle_close(t),
+            None => t,
+        }
+    }
+
+    // Points of one output between `start` and `end`, at the time they are drawn.
+    // With a timeframe of its own the forming candle is left out.
+    fn visible_points(&self, output: usize, start: f64, end: f64) -> Vec<(f64, f64)> {
+        let points = match self.cache.outputs.get(output) {
+            Some(points) => points,
+            None => return Vec::new(),
+        };
+        let forming = self.timeframe.as_ref().and(self.cache.last.as_ref()).map(|last| last.timestamp);
+        let from = points.partition_point(|(t, _)| self.drawn_at(output, *t) < start);
+        let to = points.partition_point(|(t, _)| self.drawn_at(output, *t) <= end);
+        points
+            .range(from..to)
+            .filter(|(t, _)| Some(*t) != forming)
+            .map(|(t, value)| (self.drawn_at(output, *t), *value))
+            .collect()
+    }
+
+    // Cached points between `start` and `end`. Lines are split where an
+    // output skips candles, e.g. Supertrend switching sides.
+    pub fn visible_series(&self, start: f64, end: f64) -> Vec<Series> {
+        let max_step = self.cache.interval.map_or(f64::INFINITY, |interval| interval * 1.5);
+        let mut visible = Vec::new();
+
+        for (output, (name, color, style)) in self.series_styles().into_iter().enumerate() {
+            let points = self.visible_points(output, start, end);
+            let mut segments: Vec<Vec<(f64, f64)>> = Vec::new();
+            for point in points {
+                match segments.last_mut() {
+                    Some(segment)
+                        if matches!(style, SeriesStyle::Histogram)
+                            || segment.last().is_some_and(|(t, _)| point.0 - t <= max_step) =>
+                    {
+                        segment.push(point)
+                    }
+                    _ => segments.push(vec![point]),
+                }
+            }
+
+            // Values from another timeframe hold until the next one is known
+            if self.timeframe.is_some() && matches!(style, SeriesStyle::Line { .. }) {
+                let step = self.cache.interval.unwrap_or(0.0);
+                for segment in &mut segments {
+                    let ends: Vec<f64> = segment.iter().skip(1).map(|(t, _)| *t).collect();
+                    let last_end = segment.last().map_or(0.0, |(t, _)| t + step);
+                    *segment = segment
+                        .iter()
+                        .zip(ends.into_iter().chain([last_end]))
+                        .flat_map(|(&(t, value), end)| [(t, value), (end, value)])
+                        .collect();
+                }
+            }
+
+            visible.extend(segments.into_iter().map(|points| Series {
+                name: name.clone(),
+                points,
+                color,
+                style,
+            }));
+        }
+        visible
+    }
+
+    // Value of each output shown at `t`, for the crosshair readout. Values
+    // from another timeframe hold until their next candle closes.
+    pub fn values_at(&self, t: f64) -> Vec<(String, f64)> {
+        let lookback = self.cache.interval.map_or(0.0, |interval| interval * 2.0);
+        self.series_styles()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(output, (name, _, _))| {
+                let (drawn, value) = *self.visible_points(output, t - lookback, t).last()?;
+                let shown = match &self.timeframe {
+                    Some(timeframe) => t < timeframe.candle_close(drawn),
+                    None => drawn == t,
+                };
+                shown.then_some((name, value))
+            })
+            .collect()
+    }
+
+    // Shaded areas between outputs, such as the Ichimoku cloud
+    pub fn visible_fills(&self, start: f64, end: f64) -> Vec<Fill> {
+        match self.config {
+            IndicatorConfig::Ichimoku { .. } => {
+                let senkou_b = self.visible_points(3, start, end);
+                let points = self
+                    .visible_points(2, start, end)
+                    .into_iter()
+                    .filter_map(|(t, a)| {
+                        let index = senkou_b.binary_search_by(|(time, _)| time.total_cmp(&t)).ok()?;
+                        Some((t, a, senkou_b[index].1))
+                    })
+                    .collect();
+                vec![Fill {
+                    name: format!("{} Cloud", self.label()),
+                    points,
+                    above_color: CLOUD_UP_COLOR,
+                    below_color: CLOUD_DOWN_COLOR,
+                }]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Outputs of `config` at the last candle of `data`, computed without a cache
+pub fn last_values(config: &IndicatorConfig, data: &VecDeque<CandleData>) -> Vec<Option<f64>> {
+    let last = match data.back() {
+        Some(last) => last,
+        None => return Vec::new(),
+    };
+    let mut calculator = streaming::calculator(config);
+    for candle in data.range(..data.len() - 1) {
+        calculator.commit(candle);
+    }
+    calculator.peek(last)
+}
+
+// Volume traded within one price band
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceLevel {
+    pub low: f64,
+    pub high: f64,
+    pub volume: f64,
+}
+
+// Volume by price over `candles` in `bins` equal bands between the lowest low
+// and the highest high. Each candle's volume is spread evenly over its range.
+pub fn volume_profile(candles: &[CandleData], bins: usize) -> Vec<PriceLevel> {
+    let low = candles.iter().map(|candle| candle.low).fold(f64::INFINITY, f64::min);
+    let high = candles.iter().map(|candle| candle.high).fold(f64::NEG_INFINITY, f64::max);
+    if bins == 0 || !low.is_finite() || !high.is_finite() {
+        return Vec::new();
+    }
+
+    let step = (high - low).max(f64::EPSILON) / bins as f64;
+    let mut levels: Vec<PriceLevel> = (0..bins)
+        .map(|i| PriceLevel {
+            low: low + step * i as f64,
+            high: low + step * (i + 1) as f64,
+            volume: 0.0,
+        })
+        .collect();
+    let bin_of = |price: f64| (((price - low) / step) as usize).min(bins - 1);
+
+    for candle in candles {
+        let range = candle.high - candle.low;
+        if range <= 0.0 {
+            levels[bin_of(candle.close)].volume += candle.volume;
+            continue;
+        }
+        for level in &mut levels[bin_of(candle.low)..=bin_of(candle.high)] {
+            let overlap = level.high.min(candle.high) - level.low.max(candle.low);
+            level.volume += candle.volume * overlap.max(0.0) / range;
+        }
+    }
+    levels
+}
+
+// Indicator outputs for every loaded candle. Appending candles or updating
+// the forming one costs O(1) per candle; trimming old candles drops their
+// points. Anything else (older history prepended, a different series,
+// changed parameters) rebuilds from scratch.
+struct IndicatorCache {
+    config: IndicatorConfig,
+    calculator: Box<dyn Calculator>,
+    first: Option<CandleData>,
+    last: Option<CandleData>, // Peeked but not committed yet
+    interval: Option<f64>,    // Spacing of the last two candles
+    outputs: Vec<VecDeque<(f64, f64)>>,
+}
+
+impl IndicatorCache {
+    fn new(config: &IndicatorConfig) -> Self {
+        Self {
+            config: config.clone(),
+            calculator: streaming::calculator(config),
+            first: None,
+            last: None,
+            interval: None,
+            outputs: Vec::new(),
+        }
+    }
+
+    fn sync(&mut self, config: &IndicatorConfig, data: &VecDeque<CandleData>) {
+        let (front, back) = match (data.front(), data.back()) {
+            (Some(front), Some(back)) => (front, back),
+            _ => {
+                *self = Self::new(config);
+                return;
+            }
+        };
+
+        let stale = self.config != *config
+            || self.first.as_ref().is_some_and(|first| {
+                front.timestamp < first.timestamp
+                    || (front.timestamp == first.timestamp && front != first)
+            })
+            || self.last.as_ref().is_some_and(|last| back.timestamp < last.timestamp);
+        if stale {
+            *self = Self::new(config);
+        }
+
+        // Candles dropped from the front of the series
+        if self.first.as_ref().is_some_and(|first| front.timestamp > first.timestamp) {
+            for points in &mut self.outputs {
+                let keep_from = points.partition_point(|(t, _)| *t < front.timestamp);
+                points.drain(..keep_from);
+            }
+        }
+        self.first = Some(front.clone());
+
+        // Resume from the cached last candle, which is near the back
+        let resume_from = match &self.last {
+            Some(last) => data.iter().rposition(|candle| candle.timestamp == last.timestamp),
+            None => Some(0),
+        };
+        let resume_from = match resume_from {
+            Some(index) => index,
+            None => {
+                *self = Self::new(config);
+                self.first = Some(front.clone());
+                0
+            }
+        };
+
+        for candle in data.range(resume_from..) {
+            self.push(candle);
+        }
+        if data.len() >= 2 {
+            self.interval = Some(back.timestamp - data[data.len() - 2].timestamp);
+        }
+    }
+
+    fn push(&mut self, candle: &CandleData) {
+        match self.last.take() {
+            Some(last) if last.timestamp == candle.timestamp => {
+                if last == *candle {
+                    self.last = Some(last);
+                    return;
+                }
+                // The forming candle changed: replace its points
+                for points in &mut self.outputs {
+                    if points.back().is_some_and(|(t, _)| *t == candle.timestamp) {
+                        points.pop_back();
+                    }
+                }
+            }
+            Some(last) => self.calculator.commit(&last),
+            None => {}
+        }
+
+        let values = self.calculator.peek(candle);
+        if self.outputs.len() < values.len() {
+            self.outputs.resize_with(values.len(), VecDeque::new);
+        }
+        for (points, value) in self.outputs.iter_mut().zip(values) {
+            if let Some(value) = value {
+                points.push_back((candle.timestamp, value));
+            }
+        }
+        self.last = Some(candle.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(i: usize, close: f64) -> CandleData {
+        CandleData {
+            timestamp: i as f64 * 60.0,
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: 10.0,
+        }
+    }
+
+    fn series(len: usize) -> VecDeque<CandleData> {
+        (0..len)
+            .map(|i| {
+                let mut candle = candle(i, 100.0 + 10.0 * (i as f64 * 0.3).sin() + i as f64 * 0.1);
+                candle.volume = 10.0 + (i % 7) as f64;
+                candle
+            })
+            .collect()
+    }
+
+    fn closes(data: &[CandleData]) -> Vec<f64> {
+        data.iter().map(|candle| candle.close).collect()
+    }
+
+    // Straightforward full-series versions to check the streaming ones against
+    fn reference(config: &IndicatorConfig, data: &[CandleData]) -> Vec<Vec<(f64, f64)>> {
+        let closes = closes(data);
+        let t = |i: usize| data[i].timestamp;
+        // Seeded with the simple average of the first `period` values; the
+        // result lines up with values[period - 1..]
+        let smooth = |values: &[f64], period: usize, alpha: f64| -> Vec<f64> {
+            let mut out = vec![values[..period].iter().sum::<f64>() / period as f64];
+            for value in &values[period..] {
+                let previous = *out.last().unwrap();
+                out.push(alpha * value + (1.0 - alpha) * previous);
+            }
+            out
+        };
+        let ema = |values: &[f64], period: usize| smooth(values, period, 2.0 / (period as f64 + 1.0));
+        let wilder = |values: &[f64], period: usize| smooth(values, period, 1.0 / period as f64);
+        let sma = |values: &[f64], period: usize| -> Vec<f64> {
+            values.windows(period).map(|window| window.iter().sum::<f64>() / period as f64).collect()
+        };
+        let true_range = |i: usize| {
+            let candle = &data[i];
+            match i {
+                0 => candle.high - candle.low,
+                _ => (candle.high - candle.low)
+                    .max((candle.high - closes[i - 1]).abs())
+                    .max((candle.low - closes[i - 1]).abs()),
+            }
+        };
+        let aligned = |values: &[f64], first: usize| -> Vec<(f64, f64)> {
+            values.iter().enumerate().map(|(k, v)| (t(k + first), *v)).collect()
+        };
+
+        match *config {
+            IndicatorConfig::Sma { period } => vec![(period - 1..data.len())
+                .map(|i| (t(i), closes[i + 1 - period..=i].iter().sum::<f64>() / period as f64))
+                .collect()],
+            IndicatorConfig::Ema { period } => {
+                let values = ema(&closes, period);
+                vec![values.iter().enumerate().map(|(k, v)| (t(k + period - 1), *v)).collect()]
+            }
+            IndicatorConfig::Bollinger { period, std_dev } => {
+                let mut bands = vec![Vec::new(), Vec::new(), Vec::new()];
+                for i in period - 1..data.len() {
+                    let window = &closes[i + 1 - period..=i];
+                    let mean = window.iter().sum::<f64>() / period as f64;
+                    let variance =
+                        window.iter().map(|c| (c - mean) * (c - mean)).sum::<f64>() / period as f64;
+                    bands[0].push((t(i), mean + std_dev * variance.sqrt()));
+                    bands[1].push((t(i), mean));
+                    bands[2].push((t(i), mean - std_dev * variance.sqrt()));
+                }
+                bands
+            }
+            IndicatorConfig::Macd { fast, slow, signal } => {
+                let fast_values = ema(&closes, fast);
+                let slow_values = ema(&closes, slow);
+                let macd: Vec<f64> = (slow - 1..data.len())
+                    .map(|i| fast_values[i + 1 - fast] - slow_values[i + 1 - slow])
+                    .collect();
+                let signal_values = ema(&macd, signal);
+                let offset = slow + signal - 2;
+                vec![
+                    macd.iter().enumerate().map(|(k, v)| (t(k + slow - 1), *v)).collect(),
+                    signal_values.iter().enumerate().map(|(k, v)| (t(k + offset), *v)).collect(),
+                    signal_values
+                        .iter()
+                        .enumerate()
+                        .map(|(k, v)| (t(k + offset), macd[k + signal - 1] - v))
+                        .collect(),
+                ]
+            }
+            IndicatorConfig::Rsi { period } => {
+                let changes: Vec<f64> = closes.windows(2).map(|pair| pair[1] - pair[0]).collect();
+                let gains: Vec<f64> = changes.iter().map(|change| change.max(0.0)).collect();
+                let losses: Vec<f64> = changes.iter().map(|change| (-change).max(0.0)).collect();
+                let alpha = 1.0 / period as f64;
+                let avg_gains = smooth(&gains, period, alpha);
+                let avg_losses = smooth(&losses, period, alpha);
+                vec![avg_gains
+                    .iter()
+                    .zip(&avg_losses)
+                    .enumerate()
+                    .map(|(k, (gain, loss))| (t(k + period), 100.0 - 100.0 / (1.0 + gain / loss)))
+                    .collect()]
+            }
+            IndicatorConfig::SessionVwap | IndicatorConfig::AnchoredVwap { .. } => {
+                let anchor = match *config {
+                    IndicatorConfig::AnchoredVwap { anchor } => Some(anchor),
+                    _ => None,
+                };
+                vec![(0..data.len())
+                    .filter(|&i| anchor.is_none_or(|anchor| t(i) >= anchor))
+                    .map(|i| {
+                        let day = (t(i) / 86_400.0).floor();
+                        let period = data[..=i].iter().filter(|candle| match anchor {
+                            Some(anchor) => candle.timestamp >= anchor,
+                            None => (candle.timestamp / 86_400.0).floor() == day,
+                        });
+                        let (price_volume, volume) = period.fold((0.0, 0.0), |(pv, v), candle| {
+                            let typical = (candle.high + candle.low + candle.close) / 3.0;
+                            (pv + typical * candle.volume, v + candle.volume)
+                        });
+                        (t(i), price_volume / volume)
+                    })
+                    .collect()]
+            }
+            IndicatorConfig::Stochastic { period, smooth_k, smooth_d } => {
+                let raw: Vec<f64> = (period - 1..data.len())
+                    .map(|i| {
+                        let window = &data[i + 1 - period..=i];
+                        let high = window.iter().map(|c| c.high).fold(f64::MIN, f64::max);
+                        let low = window.iter().map(|c| c.low).fold(f64::MAX, f64::min);
+                        if high > low { 100.0 * (closes[i] - low) / (high - low) } else { 50.0 }
+                    })
+                    .collect();
+                let k = sma(&raw, smooth_k);
+                let d = sma(&k, smooth_d);
+                vec![
+                    aligned(&k, period + smooth_k - 2),
+                    aligned(&d, period + smooth_k + smooth_d - 3),
+                ]
+            }
+            IndicatorConfig::Atr { period } => {
+                let ranges: Vec<f64> = (0..data.len()).map(true_range).collect();
+                vec![aligned(&wilder(&ranges, period), period - 1)]
+            }
+            IndicatorConfig::Adx { period, smoothing } => {
+                let ranges: Vec<f64> = (1..data.len()).map(true_range).collect();
+                let (plus, minus): (Vec<f64>, Vec<f64>) = (1..data.len())
+                    .map(|i| {
+                        let up = data[i].high - data[i - 1].high;
+                        let down = data[i - 1].low - data[i].low;
+                        (
+                            if up > down && up > 0.0 { up } else { 0.0 },
+                            if down > up && down > 0.0 { down } else { 0.0 },
+                        )
+                    })
+                    .unzip();
+                let (ranges, plus, minus) = (wilder(&ranges, period), wilder(&plus, period), wilder(&minus, period));
+                let plus_di: Vec<f64> = plus.iter().zip(&ranges).map(|(dm, tr)| 100.0 * dm / tr).collect();
+                let minus_di: Vec<f64> = minus.iter().zip(&ranges).map(|(dm, tr)| 100.0 * dm / tr).collect();
+                let dx: Vec<f64> = plus_di
+                    .iter()
+                    .zip(&minus_di)
+                    .map(|(plus, minus)| 100.0 * (plus - minus).abs() / (plus + minus))
+                    .collect();
+                vec![
+                    aligned(&wilder(&dx, smoothing), period + smoothing - 1),
+                    aligned(&plus_di, period),
+                    aligned(&minus_di, period),
+                ]
+            }
+            IndicatorConfig::Cci { period } => {
+                let typical: Vec<f64> = data.iter().map(|c| (c.high + c.low + c.close) / 3.0).collect();
+                let cci: Vec<f64> = typical
+                    .windows(period)
+                    .map(|window| {
+                        let mean = window.iter().sum::<f64>() / period as f64;
+                        let deviation = window.iter().map(|x| (x - mean).abs()).sum::<f64>() / period as f64;
+                        (window[period - 1] - mean) / (0.015 * deviation)
+                    })
+                    .collect();
+                vec![aligned(&cci, period - 1)]
+            }
+            IndicatorConfig::Ichimoku { tenkan, kijun, senkou_b, displacement } => {
+                let midpoint = |period: usize| -> Vec<f64> {
+                    data.windows(period)
+                        .map(|window| {
+                            let high = window.iter().map(|c| c.high).fold(f64::MIN, f64::max);
+                            let low = window.iter().map(|c| c.low).fold(f64::MAX, f64::min);
+                            (high + low) / 2.0
+                        })
+                        .collect()
+                };
+                let (tenkan_values, kijun_values) = (midpoint(tenkan), midpoint(kijun));
+                let senkou_a: Vec<f64> = kijun_values
+                    .iter()
+                    .enumerate()
+                    .map(|(k, kijun_value)| (tenkan_values[k + kijun - tenkan] + kijun_value) / 2.0)
+                    .collect();
+                let shift = displacement as f64 * (t(1) - t(0));
+                let shifted = |points: Vec<(f64, f64)>| points.into_iter().map(|(t, v)| (t + shift, v)).collect();
+                vec![
+                    aligned(&tenkan_values, tenkan - 1),
+                    aligned(&kijun_values, kijun - 1),
+                    shifted(aligned(&senkou_a, kijun - 1)),
+                    shifted(aligned(&midpoint(senkou_b), senkou_b - 1)),
+                ]
+            }
+            IndicatorConfig::Supertrend { period, multiplier } => {
+                let ranges: Vec<f64> = (0..data.len()).map(true_range).collect();
+                let atr = wilder(&ranges, period);
+                let (mut up, mut down) = (Vec::new(), Vec::new());
+                let mut state: Option<(f64, f64, bool)> = None;
+                for (k, atr) in atr.iter().enumerate() {
+                    let i = k + period - 1;
+                    let middle = (data[i].high + data[i].low) / 2.0;
+                    let (mut upper, mut lower) = (middle + multiplier * atr, middle - multiplier * atr);
+                    let mut uptrend = false;
+                    if let Some((previous_upper, previous_lower, was_up)) = state {
+                        if upper > previous_upper && closes[i - 1] <= previous_upper {
+                            upper = previous_upper;
+                        }
+                        if lower < previous_lower && closes[i - 1] >= previous_lower {
+                            lower = previous_lower;
+                        }
+                        uptrend = if was_up { closes[i] >= lower } else { closes[i] > upper };
+                    }
+                    state = Some((upper, lower, uptrend));
+                    if uptrend {
+                        up.push((t(i), lower));
+                    } else {
+                        down.push((t(i), upper));
+                    }
+                }
+                vec![up, down]
+            }
+            IndicatorConfig::Obv => {
+                let mut total = 0.0;
+                vec![(0..data.len())
+                    .map(|i| {
+                        if i > 0 && closes[i] > closes[i - 1] {
+                            total += data[i].volume;
+                        } else if i > 0 && closes[i] < closes[i - 1] {
+                            total -= data[i].volume;
+                        }
+                        (t(i), total)
+                    })
+                    .collect()]
+            }
+        }
+    }
+
+    fn assert_matches_reference(instance: &IndicatorInstance, data: &VecDeque<CandleData>) {
+        let data: Vec<CandleData> = data.iter().cloned().collect();
+        let expected = reference(&instance.config, &data);
+
+        // Join lines that were split at gaps back into one output each
+        let mut actual: Vec<(String, Vec<(f64, f64)>)> = Vec::new();
+        for series in instance.visible_series(f64::MIN, f64::MAX) {
+            match actual.last_mut() {
+                Some((name, points)) if *name == series.name => points.extend(series.points),
+                _ => actual.push((series.name, series.points)),
+            }
+        }
+
+        assert_eq!(actual.len(), expected.len(), "{}", instance.config.name());
+        for ((name, points), expected) in actual.iter().zip(&expected) {
+            assert_eq!(points.len(), expected.len(), "{}", name);
+            for ((t, v), (expected_t, expected_v)) in points.iter().zip(expected) {
+                assert_eq!(t, expected_t, "{}", name);
+                assert!((v - expected_v).abs() < 1e-9, "{} at {}: {} vs {}", name, t, v, expected_v);
+            }
+        }
+    }
+
+    fn configs() -> Vec<IndicatorConfig> {
+        vec![
+            IndicatorConfig::Sma { period: 7 },
+            IndicatorConfig::Ema { period: 9 },
+            IndicatorConfig::Bollinger { period: 20, std_dev: 2.0 },
+            IndicatorConfig::Macd { fast: 12, slow: 26, signal: 9 },
+            IndicatorConfig::Rsi { period: 14 },
+            IndicatorConfig::Sma { period: 1 },
+            IndicatorConfig::SessionVwap,
+            IndicatorConfig::AnchoredVwap { anchor: 50.0 * 60.0 },
+            IndicatorConfig::Obv,
+            IndicatorConfig::Stochastic { period: 14, smooth_k: 3, smooth_d: 3 },
+            IndicatorConfig::Stochastic { period: 5, smooth_k: 1, smooth_d: 1 },
+            IndicatorConfig::Atr { period: 14 },
+            IndicatorConfig::Adx { period: 14, smoothing: 14 },
+            IndicatorConfig::Adx { period: 7, smoothing: 3 },
+            IndicatorConfig::Cci { period: 20 },
+            IndicatorConfig::Ichimoku { tenkan: 9, kijun: 26, senkou_b: 52, displacement: 26 },
+            IndicatorConfig::Supertrend { period: 10, multiplier: 3.0 },
+            IndicatorConfig::Supertrend { period: 5, multiplier: 1.0 },
+        ]
+    }
+
+    #[test]
+    fn full_series_matches_batch_computation() {
+        let data = series(200);
+        for config in configs() {
+            let mut instance = IndicatorInstance::new(0, config);
+            instance.sync(&data);
+            assert_matches_reference(&instance, &data);
+        }
+    }
+
+    #[test]
+    fn forming_candle_updates_replace_only_the_last_point() {
+        let full = series(120);
+        for config in configs() {
+            let mut instance = IndicatorInstance::new(0, config);
+            let mut data = VecDeque::new();
+
+            // Each candle first arrives with a provisional close, then its final one
+            for candle in &full {
+                let mut forming = candle.clone();
+                forming.close += 3.0;
+                data.push_back(forming);
+                instance.sync(&data);
+
+                *data.back_mut().unwrap() = candle.clone();
+                instance.sync(&data);
+            }
+
+            assert_matches_reference(&instance, &data);
+        }
+    }
+
+    #[test]
+    fn values_do_not_depend_on_what_is_visible() {
+        let data = series(200);
+        let mut instance = IndicatorInstance::new(0, IndicatorConfig::Macd { fast: 12, slow: 26, signal: 9 });
+        instance.sync(&data);
+
+        let full = instance.visible_series(f64::MIN, f64::MAX);
+        let window = instance.visible_series(150.0 * 60.0, 170.0 * 60.0);
+        let at = |points: &[(f64, f64)], t: f64| points.iter().find(|(time, _)| *time == t).unwrap().1;
+
+        assert_eq!(window[0].points.len(), 21);
+        assert_eq!(at(&window[0].points, 160.0 * 60.0), at(&full[0].points, 160.0 * 60.0));
+    }
+
+    #[test]
+    fn trimmed_and_prepended_history() {
+        let mut data = series(200);
+        let mut instance = IndicatorInstance::new(0, IndicatorConfig::Sma { period: 7 });
+        instance.sync(&data);
+
+        // Trimming the front keeps the remaining values
+        let before = instance.visible_series(f64::MIN, f64::MAX)[0].points.clone();
+        data.drain(..50);
+        instance.sync(&data);
+        let after = instance.visible_series(f64::MIN, f64::MAX)[0].points.clone();
+        assert_eq!(after.first().unwrap().0, 50.0 * 60.0);
+        assert_eq!(after, before[before.len() - after.len()..].to_vec());
+
+        // Older candles in front rebuild the whole series
+        let older = series(200);
+        for candle in older.iter().take(50).rev() {
+            data.push_front(candle.clone());
+        }
+        instance.sync(&data);
+        assert_matches_reference(&instance, &data);
+    }
+
+    #[test]
+    fn registry_kinds_can_be_added_more_than_once() {
+        let registry = registry();
+        let mut kinds: Vec<&str> = registry.iter().map(IndicatorConfig::kind_name).collect();
+        kinds.sort();
+        kinds.dedup();
+        assert_eq!(kinds.len(), registry.len());
+
+        // A second MA with its period edited the way the add dialog does
+        let mut slow = registry[0].clone();
+        for param in slow.params_mut() {
+            if let Param::Period(_, period) = param {
+                *period = 50;
+            }
+        }
+        let mut instances = [IndicatorInstance::new(0, registry[0].clone()), IndicatorInstance::new(1, slow)];
+
+        let data = series(100);
+        for instance in &mut instances {
+            instance.sync(&data);
+            assert_matches_reference(instance, &data);
+        }
+        let labels: Vec<String> = instances.iter().map(IndicatorInstance::label).collect();
+        assert_eq!(labels, vec!["MA 20", "MA 50"]);
+        assert_ne!(instances[0].color, instances[1].color);
+        let starts: Vec<f64> =
+            instances.iter().map(|instance| instance.visible_series(f64::MIN, f64::MAX)[0].points[0].0).collect();
+        assert_eq!(starts, vec![19.0 * 60.0, 49.0 * 60.0]);
+
+        // Oscillators get their own pane with fixed bounds
+        let rsi = registry.iter().find(|config| config.kind_name() == "RSI").unwrap();
+        assert_eq!((rsi.placement(), rsi.y_bounds()), (Placement::Subpane, Some((0.0, 100.0))));
+        assert_eq!(instances[0].config.placement(), Placement::Overlay);
+    }
+
+    #[test]
+    fn changed_parameters_rebuild() {
+        let data = series(100);
+        let mut instance = IndicatorInstance::new(0, IndicatorConfig::Sma { period: 7 });
+        instance.sync(&data);
+
+        instance.config = IndicatorConfig::Sma { period: 25 };
+        instance.sync(&data);
+        assert_matches_reference(&instance, &data);
+    }
+
+    #[test]
+    fn session_vwap_resets_at_midnight_utc() {
+        // Hourly candles across two days, the second day trading higher
+        let data: VecDeque<CandleData> = (0..48)
+            .map(|i| {
+                let mut candle = candle(i, if i < 24 { 100.0 } else { 200.0 });
+                candle.timestamp = i as f64 * 3600.0;
+                candle
+            })
+            .collect();
+        let mut instance = IndicatorInstance::new(0, IndicatorConfig::SessionVwap);
+        instance.sync(&data);
+
+        let points = &instance.visible_series(f64::MIN, f64::MAX)[0].points;
+        assert_eq!(points[23], (23.0 * 3600.0, 100.0));
+        assert_eq!(points[24], (86_400.0, 200.0));
+        assert_matches_reference(&instance, &data);
+    }
+
+    #[test]
+    fn volume_profile_spreads_volume_over_each_range() {
+        let candles = [
+            CandleData { timestamp: 0.0, open: 10.0, high: 14.0, low: 10.0, close: 12.0, volume: 8.0 },
+            CandleData { timestamp: 60.0, open: 13.0, high: 13.0, low: 13.0, close: 13.0, volume: 5.0 },
+        ];
+        let profile = volume_profile(&candles, 4);
+
+        assert_eq!(profile.len(), 4);
+        assert_eq!((profile[0].low, profile[3].high), (10.0, 14.0));
+        let volumes: Vec<f64> = profile.iter().map(|level| level.volume).collect();
+        assert_eq!(volumes, vec![2.0, 2.0, 2.0, 7.0]);
+    }
+
+    #[test]
+    fn atr_includes_gaps_from_the_previous_close() {
+        // A 10 point gap up on the third candle, each candle 2 points tall
+        let data: VecDeque<CandleData> = [100.0, 100.0, 110.0, 110.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| candle(i, close))
+            .collect();
+        let mut instance = IndicatorInstance::new(0, IndicatorConfig::Atr { period: 2 });
+        instance.sync(&data);
+
+        // True ranges 2, 2, 11, 2: seeded with (2 + 2) / 2, then Wilder-smoothed
+        let points = &instance.visible_series(f64::MIN, f64::MAX)[0].points;
+        let values: Vec<f64> = points.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, vec![2.0, 6.5, 4.25]);
+    }
+
+    #[test]
+    fn ichimoku_cloud_is_projected_forward() {
+        let data = series(200);
+        let config = IndicatorConfig::Ichimoku { tenkan: 9, kijun: 26, senkou_b: 52, displacement: 26 };
+        let mut instance = IndicatorInstance::new(0, config);
+        instance.sync(&data);
+
+        // The cloud runs 26 candles past the last one and starts where Senkou B does
+        let fills = instance.visible_fills(f64::MIN, f64::MAX);
+        assert_eq!(fills.len(), 1);
+        let cloud = &fills[0].points;
+        assert_eq!(cloud.first().unwrap().0, (51.0 + 26.0) * 60.0);
+        assert_eq!(cloud.last().unwrap().0, (199.0 + 26.0) * 60.0);
+
+        // Only the part inside the requested range is returned
+        let window = instance.visible_fills(200.0 * 60.0, 210.0 * 60.0);
+        assert_eq!(window[0].points.len(), 11);
+    }
+
+    #[test]
+    fn supertrend_flips_and_breaks_its_lines() {
+        // Up for 30 candles, then down for 30
+        let data: VecDeque<CandleData> = (0..60)
+            .map(|i| candle(i, if i < 30 { 100.0 + i as f64 } else { 160.0 - i as f64 }))
+            .collect();
+        let mut instance = IndicatorInstance::new(0, IndicatorConfig::Supertrend { period: 5, multiplier: 2.0 });
+        instance.sync(&data);
+
+        let series = instance.visible_series(f64::MIN, f64::MAX);
+        let names: Vec<&str> = series.iter().map(|series| series.name.as_str()).collect();
+        assert_eq!(names, vec!["Supertrend 5 2 Up", "Supertrend 5 2 Down", "Supertrend 5 2 Down"]);
+
+        // At or below price in the uptrend, above it after the turn
+        let up = &series[0].points;
+        assert!(up.iter().all(|(t, value)| *value <= data[(*t / 60.0) as usize].close));
+        let last = series[2].points.last().unwrap();
+        assert!(last.1 > data[59].close);
+    }
+
+    #[test]
+    fn other_timeframes_show_closed_candles_as_steps() {
+        // Hourly candles for an MA 2 on a minute chart; the last hour is still forming
+        let data: VecDeque<CandleData> = [10.0, 20.0, 30.0, 40.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| CandleData { timestamp: i as f64 * 3600.0, ..candle(0, close) })
+            .collect();
+        let mut instance = IndicatorInstance::new(0, IndicatorConfig::Sma { period: 2 });
+        instance.set_timeframe(Some(Timeframe::H1));
+        instance.sync(&data);
+
+        let series = instance.visible_series(f64::MIN, f64::MAX);
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].name, "MA 2 (1h)");
+
+        // 15 from the close of hour 1 until hour 2 closes, then 25 until the forming hour closes
+        let hour = 3600.0;
+        assert_eq!(
+            series[0].points,
+            vec![(2.0 * hour, 15.0), (3.0 * hour, 15.0), (3.0 * hour, 25.0), (4.0 * hour, 25.0)]
+        );
+    }
+
+    #[test]
+    fn crosshair_values_match_what_is_drawn() {
+        let data: VecDeque<CandleData> = (0..60).map(|i| candle(i, 100.0 + i as f64)).collect();
+        let mut sma = IndicatorInstance::new(0, IndicatorConfig::Sma { period: 3 });
+        sma.sync(&data);
+        assert_eq!(sma.values_at(10.0 * 60.0), vec![("MA 3".to_string(), 109.0)]);
+        assert!(sma.values_at(60.0).is_empty()); // Not enough candles yet
+
+        // Only the side Supertrend is on has a value
+        let mut supertrend = IndicatorInstance::new(1, IndicatorConfig::Supertrend { period: 5, multiplier: 2.0 });
+        supertrend.sync(&data);
+        let names: Vec<String> = supertrend.values_at(30.0 * 60.0).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["Supertrend 5 2 Up"]);
+
+        // Hourly values hold from one close to the next
+        let hourly: VecDeque<CandleData> = [10.0, 20.0, 30.0, 40.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| CandleData { timestamp: i as f64 * 3600.0, ..candle(0, close) })
+            .collect();
+        sma.set_timeframe(Some(Timeframe::H1));
+        sma.sync(&hourly);
+        let hour = 3600.0;
+        assert!(sma.values_at(2.5 * hour).is_empty());
+        assert_eq!(sma.values_at(3.5 * hour), vec![("MA 3 (1h)".to_string(), 20.0)]);
+        assert!(sma.values_at(4.5 * hour).is_empty()); // The forming hour has no value yet
+    }
+
+    #[test]
+    fn volume_profile_of_nothing() {
+        assert!(volume_profile(&[], 10).is_empty());
+    }
+}
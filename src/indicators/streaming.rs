@@ -0,0 +1,890 @@
+use crate::indicators::IndicatorConfig;
+use crate::CandleData;
+use std::collections::VecDeque;
+
+// An indicator computed one candle at a time. The newest candle may still be
+// forming, so it is only ever peeked at; it is committed once the next candle
+// opens. Both calls are O(1).
+pub trait Calculator: Send {
+    // Output values for `candle` on top of the committed candles, one per
+    // series; None while the indicator is still warming up
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>>;
+
+    // Fold a closed candle into the state
+    fn commit(&mut self, candle: &CandleData);
+}
+
+pub fn calculator(config: &IndicatorConfig) -> Box<dyn Calculator> {
+    match *config {
+        IndicatorConfig::Sma { period } => Box::new(Sma::new(period)),
+        IndicatorConfig::Ema { period } => Box::new(EmaLine::new(period)),
+        IndicatorConfig::Bollinger { period, std_dev } => Box::new(Bollinger::new(period, std_dev)),
+        IndicatorConfig::Macd { fast, slow, signal } => Box::new(Macd::new(fast, slow, signal)),
+        IndicatorConfig::Rsi { period } => Box::new(Rsi::new(period)),
+        IndicatorConfig::SessionVwap => Box::new(Vwap::new(None)),
+        IndicatorConfig::AnchoredVwap { anchor } => Box::new(Vwap::new(Some(anchor))),
+        IndicatorConfig::Obv => Box::new(Obv::default()),
+        IndicatorConfig::Stochastic { period, smooth_k, smooth_d } => {
+            Box::new(Stochastic::new(period, smooth_k, smooth_d))
+        }
+        IndicatorConfig::Atr { period } => Box::new(Atr::new(period)),
+        IndicatorConfig::Adx { period, smoothing } => Box::new(Adx::new(period, smoothing)),
+        IndicatorConfig::Cci { period } => Box::new(Cci::new(period)),
+        IndicatorConfig::Ichimoku { tenkan, kijun, senkou_b, .. } => {
+            Box::new(Ichimoku::new(tenkan, kijun, senkou_b))
+        }
+        IndicatorConfig::Supertrend { period, multiplier } => {
+            Box::new(Supertrend::new(period, multiplier))
+        }
+    }
+}
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+// The last `capacity` committed values with running sums. Sums are taken
+// relative to the first value seen to keep the variance numerically stable.
+struct RollingWindow {
+    values: VecDeque<f64>,
+    capacity: usize,
+    shift: Option<f64>,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl RollingWindow {
+    fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity + 1),
+            capacity,
+            shift: None,
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn push(&mut self, value: f64) {
+        let shift = *self.shift.get_or_insert(value);
+        self.values.push_back(value);
+        self.sum += value - shift;
+        self.sum_sq += (value - shift) * (value - shift);
+
+        if self.values.len() > self.capacity {
+            if let Some(old) = self.values.pop_front() {
+                self.sum -= old - shift;
+                self.sum_sq -= (old - shift) * (old - shift);
+            }
+        }
+    }
+
+    // Mean and population variance of the window plus `value`
+    fn stats_with(&self, value: f64) -> (f64, f64) {
+        let shift = self.shift.unwrap_or(value);
+        let n = (self.values.len() + 1) as f64;
+        let mean = (self.sum + value - shift) / n;
+        let mean_sq = (self.sum_sq + (value - shift) * (value - shift)) / n;
+        (mean + shift, (mean_sq - mean * mean).max(0.0))
+    }
+
+    fn sum_with(&self, value: f64) -> f64 {
+        let shift = self.shift.unwrap_or(0.0);
+        self.sum + shift * self.values.len() as f64 + value
+    }
+}
+
+// Exponential moving average seeded with the simple average of the first
+// `period` values, as TradingView and Binance charts do
+struct Ema {
+    period: usize,
+    alpha: f64,
+    seed_sum: f64,
+    count: usize,
+    value: Option<f64>,
+}
+
+impl Ema {
+    fn new(period: usize) -> Self {
+        Self::with_alpha(period, 2.0 / (period.max(1) as f64 + 1.0))
+    }
+
+    // Wilder's smoothing is an EMA with alpha = 1 / period
+    fn wilder(period: usize) -> Self {
+        Self::with_alpha(period, 1.0 / period.max(1) as f64)
+    }
+
+    fn with_alpha(period: usize, alpha: f64) -> Self {
+        Self {
+            period: period.max(1),
+            alpha,
+            seed_sum: 0.0,
+            count: 0,
+            value: None,
+        }
+    }
+
+    // None until `period` values have been seen
+    fn peek(&self, x: f64) -> Option<f64> {
+        match self.value {
+            Some(previous) => Some(self.alpha * x + (1.0 - self.alpha) * previous),
+            None if self.count + 1 >= self.period => Some((self.seed_sum + x) / self.period as f64),
+            None => None,
+        }
+    }
+
+    fn commit(&mut self, x: f64) {
+        let value = self.peek(x);
+        if self.value.is_none() {
+            self.seed_sum += x;
+        }
+        self.value = value;
+        self.count += 1;
+    }
+}
+
+// Simple moving average of the last `period` values
+struct Mean {
+    period: usize,
+    window: RollingWindow,
+}
+
+impl Mean {
+    fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self {
+            period,
+            window: RollingWindow::new(period - 1),
+        }
+    }
+
+    fn peek(&self, x: f64) -> Option<f64> {
+        (self.window.len() + 1 >= self.period).then(|| self.window.sum_with(x) / self.period as f64)
+    }
+
+    fn commit(&mut self, x: f64) {
+        self.window.push(x);
+    }
+}
+
+// Highest (or lowest) of the last `capacity` committed values. The deque only
+// holds values that can still become the extreme, so pushes are amortized O(1).
+struct RollingExtreme {
+    capacity: usize,
+    highest: bool,
+    count: usize,
+    candidates: VecDeque<(usize, f64)>,
+}
+
+impl RollingExtreme {
+    fn new(capacity: usize, highest: bool) -> Self {
+        Self {
+            capacity,
+            highest,
+            count: 0,
+            candidates: VecDeque::new(),
+        }
+    }
+
+    // `a` is at least as extreme as `b`
+    fn beats(&self, a: f64, b: f64) -> bool {
+        if self.highest {
+            a >= b
+        } else {
+            a <= b
+        }
+    }
+
+    fn push(&mut self, value: f64) {
+        while self.candidates.back().is_some_and(|&(_, last)| self.beats(value, last)) {
+            self.candidates.pop_back();
+        }
+        self.candidates.push_back((self.count, value));
+        self.count += 1;
+
+        while self.candidates.front().is_some_and(|&(index, _)| index + self.capacity < self.count) {
+            self.candidates.pop_front();
+        }
+    }
+
+    // Extreme of the window plus `value`
+    fn extreme_with(&self, value: f64) -> f64 {
+        match self.candidates.front() {
+            Some(&(_, extreme)) if self.beats(extreme, value) => extreme,
+            _ => value,
+        }
+    }
+}
+
+struct Sma {
+    mean: Mean,
+}
+
+impl Sma {
+    fn new(period: usize) -> Self {
+        Self { mean: Mean::new(period) }
+    }
+}
+
+impl Calculator for Sma {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        vec![self.mean.peek(candle.close)]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        self.mean.commit(candle.close);
+    }
+}
+
+struct EmaLine {
+    ema: Ema,
+}
+
+impl EmaLine {
+    fn new(period: usize) -> Self {
+        Self { ema: Ema::new(period) }
+    }
+}
+
+impl Calculator for EmaLine {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        vec![self.ema.peek(candle.close)]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        self.ema.commit(candle.close);
+    }
+}
+
+// Upper, middle and lower band
+struct Bollinger {
+    period: usize,
+    std_dev: f64,
+    window: RollingWindow,
+}
+
+impl Bollinger {
+    fn new(period: usize, std_dev: f64) -> Self {
+        let period = period.max(1);
+        Self {
+            period,
+            std_dev,
+            window: RollingWindow::new(period - 1),
+        }
+    }
+}
+
+impl Calculator for Bollinger {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        if self.window.len() + 1 < self.period {
+            return vec![None; 3];
+        }
+
+        let (mean, variance) = self.window.stats_with(candle.close);
+        let deviation = self.std_dev * variance.sqrt();
+        vec![Some(mean + deviation), Some(mean), Some(mean - deviation)]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        self.window.push(candle.close);
+    }
+}
+
+// MACD line, signal line and histogram. The signal line averages MACD values
+// from the first candle the slow average is ready on.
+struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Self {
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+        }
+    }
+
+    fn macd_with(&self, close: f64) -> Option<f64> {
+        Some(self.fast.peek(close)? - self.slow.peek(close)?)
+    }
+}
+
+impl Calculator for Macd {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        let macd = match self.macd_with(candle.close) {
+            Some(macd) => macd,
+            None => return vec![None; 3],
+        };
+
+        let signal = self.signal.peek(macd);
+        vec![Some(macd), signal, signal.map(|signal| macd - signal)]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        if let Some(macd) = self.macd_with(candle.close) {
+            self.signal.commit(macd);
+        }
+        self.fast.commit(candle.close);
+        self.slow.commit(candle.close);
+    }
+}
+
+// Wilder's RSI: average gains and losses start as the simple average of the
+// first `period` changes and are smoothed with alpha = 1 / period after that
+struct Rsi {
+    previous_close: Option<f64>,
+    gains: Ema,
+    losses: Ema,
+}
+
+impl Rsi {
+    fn new(period: usize) -> Self {
+        Self {
+            previous_close: None,
+            gains: Ema::wilder(period),
+            losses: Ema::wilder(period),
+        }
+    }
+}
+
+impl Calculator for Rsi {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        let change = match self.previous_close {
+            Some(previous) => candle.close - previous,
+            None => return vec![None],
+        };
+
+        let avg_gain = self.gains.peek(change.max(0.0));
+        let avg_loss = self.losses.peek((-change).max(0.0));
+        vec![avg_gain.zip(avg_loss).map(|(gain, loss)| rsi(gain, loss))]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        if let Some(previous) = self.previous_close {
+            let change = candle.close - previous;
+            self.gains.commit(change.max(0.0));
+            self.losses.commit((-change).max(0.0));
+        }
+        self.previous_close = Some(candle.close);
+    }
+}
+
+// Volume-weighted average of the typical price, accumulated from `anchor` on,
+// or from 00:00 UTC of each day without one
+struct Vwap {
+    anchor: Option<f64>,
+    period_start: Option<f64>,
+    price_volume: f64,
+    volume: f64,
+}
+
+impl Vwap {
+    fn new(anchor: Option<f64>) -> Self {
+        Self {
+            anchor,
+            period_start: None,
+            price_volume: 0.0,
+            volume: 0.0,
+        }
+    }
+
+    // Start of the accumulation period `timestamp` falls in; None before the anchor
+    fn period_start(&self, timestamp: f64) -> Option<f64> {
+        match self.anchor {
+            Some(anchor) => (timestamp >= anchor).then_some(anchor),
+            None => Some(timestamp - timestamp.rem_euclid(SECONDS_PER_DAY)),
+        }
+    }
+
+    // Period start plus running price-volume and volume including `candle`
+    fn totals_with(&self, candle: &CandleData) -> Option<(f64, f64, f64)> {
+        let start = self.period_start(candle.timestamp)?;
+        let (price_volume, volume) = if self.period_start == Some(start) {
+            (self.price_volume, self.volume)
+        } else {
+            (0.0, 0.0)
+        };
+
+        let typical = (candle.high + candle.low + candle.close) / 3.0;
+        Some((start, price_volume + typical * candle.volume, volume + candle.volume))
+    }
+}
+
+impl Calculator for Vwap {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        let vwap = self.totals_with(candle).and_then(|(_, price_volume, volume)| {
+            (volume > 0.0).then(|| price_volume / volume)
+        });
+        vec![vwap]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        if let Some((start, price_volume, volume)) = self.totals_with(candle) {
+            self.period_start = Some(start);
+            self.price_volume = price_volume;
+            self.volume = volume;
+        }
+    }
+}
+
+// On-balance volume: volume added on up closes and subtracted on down closes
+#[derive(Default)]
+struct Obv {
+    previous_close: Option<f64>,
+    total: f64,
+}
+
+impl Obv {
+    fn total_with(&self, candle: &CandleData) -> f64 {
+        match self.previous_close {
+            Some(previous) if candle.close > previous => self.total + candle.volume,
+            Some(previous) if candle.close < previous => self.total - candle.volume,
+            _ => self.total,
+        }
+    }
+}
+
+impl Calculator for Obv {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        vec![Some(self.total_with(candle))]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        self.total = self.total_with(candle);
+        self.previous_close = Some(candle.close);
+    }
+}
+
+// Slow stochastic: raw %K is where the close sits in the high-low range of the
+// last `period` candles, smoothed into %K and again into %D
+struct Stochastic {
+    highs: RollingExtreme,
+    lows: RollingExtreme,
+    period: usize,
+    k: Mean,
+    d: Mean,
+}
+
+impl Stochastic {
+    fn new(period: usize, smooth_k: usize, smooth_d: usize) -> Self {
+        let period = period.max(1);
+        Self {
+            highs: RollingExtreme::new(period - 1, true),
+            lows: RollingExtreme::new(period - 1, false),
+            period,
+            k: Mean::new(smooth_k),
+            d: Mean::new(smooth_d),
+        }
+    }
+
+    fn raw_k(&self, candle: &CandleData) -> Option<f64> {
+        if self.highs.count + 1 < self.period {
+            return None;
+        }
+        let high = self.highs.extreme_with(candle.high);
+        let low = self.lows.extreme_with(candle.low);
+        Some(if high > low { 100.0 * (candle.close - low) / (high - low) } else { 50.0 })
+    }
+}
+
+impl Calculator for Stochastic {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        let k = self.raw_k(candle).and_then(|raw| self.k.peek(raw));
+        vec![k, k.and_then(|k| self.d.peek(k))]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        if let Some(raw) = self.raw_k(candle) {
+            if let Some(k) = self.k.peek(raw) {
+                self.d.commit(k);
+            }
+            self.k.commit(raw);
+        }
+        self.highs.push(candle.high);
+        self.lows.push(candle.low);
+    }
+}
+
+// True range against the previous close; just high - low for the first candle
+fn true_range(candle: &CandleData, previous: Option<&CandleData>) -> f64 {
+    match previous {
+        Some(previous) => (candle.high - candle.low)
+            .max((candle.high - previous.close).abs())
+            .max((candle.low - previous.close).abs()),
+        None => candle.high - candle.low,
+    }
+}
+
+// Average true range with Wilder's smoothing
+struct Atr {
+    previous: Option<CandleData>,
+    average: Ema,
+}
+
+impl Atr {
+    fn new(period: usize) -> Self {
+        Self {
+            previous: None,
+            average: Ema::wilder(period),
+        }
+    }
+
+    fn peek_value(&self, candle: &CandleData) -> Option<f64> {
+        self.average.peek(true_range(candle, self.previous.as_ref()))
+    }
+}
+
+impl Calculator for Atr {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        vec![self.peek_value(candle)]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        self.average.commit(true_range(candle, self.previous.as_ref()));
+        self.previous = Some(candle.clone());
+    }
+}
+
+// ADX, +DI and -DI. Directional movement needs a previous candle, so the
+// first candle only seeds the state.
+struct Adx {
+    previous: Option<CandleData>,
+    true_range: Ema,
+    plus_dm: Ema,
+    minus_dm: Ema,
+    adx: Ema,
+}
+
+impl Adx {
+    fn new(period: usize, smoothing: usize) -> Self {
+        Self {
+            previous: None,
+            true_range: Ema::wilder(period),
+            plus_dm: Ema::wilder(period),
+            minus_dm: Ema::wilder(period),
+            adx: Ema::wilder(smoothing),
+        }
+    }
+
+    // True range, +DM and -DM of `candle` against the previous candle
+    fn movement(&self, candle: &CandleData) -> Option<(f64, f64, f64)> {
+        let previous = self.previous.as_ref()?;
+        let up = candle.high - previous.high;
+        let down = previous.low - candle.low;
+        let plus = if up > down && up > 0.0 { up } else { 0.0 };
+        let minus = if down > up && down > 0.0 { down } else { 0.0 };
+        Some((true_range(candle, Some(previous)), plus, minus))
+    }
+
+    // +DI, -DI and DX from smoothed movement
+    fn directional(true_range: f64, plus_dm: f64, minus_dm: f64) -> (f64, f64, f64) {
+        let (plus, minus) = if true_range > 0.0 {
+            (100.0 * plus_dm / true_range, 100.0 * minus_dm / true_range)
+        } else {
+            (0.0, 0.0)
+        };
+        let dx = if plus + minus > 0.0 { 100.0 * (plus - minus).abs() / (plus + minus) } else { 0.0 };
+        (plus, minus, dx)
+    }
+}
+
+impl Calculator for Adx {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        let smoothed = self.movement(candle).and_then(|(tr, plus, minus)| {
+            Some((self.true_range.peek(tr)?, self.plus_dm.peek(plus)?, self.minus_dm.peek(minus)?))
+        });
+        match smoothed {
+            Some((tr, plus, minus)) => {
+                let (plus, minus, dx) = Self::directional(tr, plus, minus);
+                vec![self.adx.peek(dx), Some(plus), Some(minus)]
+            }
+            None => vec![None; 3],
+        }
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        if let Some((tr, plus, minus)) = self.movement(candle) {
+            let smoothed = (self.true_range.peek(tr), self.plus_dm.peek(plus), self.minus_dm.peek(minus));
+            if let (Some(tr), Some(plus), Some(minus)) = smoothed {
+                self.adx.commit(Self::directional(tr, plus, minus).2);
+            }
+            self.true_range.commit(tr);
+            self.plus_dm.commit(plus);
+            self.minus_dm.commit(minus);
+        }
+        self.previous = Some(candle.clone());
+    }
+}
+
+// Commodity channel index of the typical price. The mean deviation has no
+// running form, so peeking walks the window: O(period).
+struct Cci {
+    period: usize,
+    window: VecDeque<f64>,
+}
+
+impl Cci {
+    fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            window: VecDeque::new(),
+        }
+    }
+
+    fn typical(candle: &CandleData) -> f64 {
+        (candle.high + candle.low + candle.close) / 3.0
+    }
+}
+
+impl Calculator for Cci {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        if self.window.len() + 1 < self.period {
+            return vec![None];
+        }
+
+        let typical = Self::typical(candle);
+        let n = self.period as f64;
+        let mean = (self.window.iter().sum::<f64>() + typical) / n;
+        let deviation =
+            (self.window.iter().map(|x| (x - mean).abs()).sum::<f64>() + (typical - mean).abs()) / n;
+        vec![Some(if deviation > 0.0 { (typical - mean) / (0.015 * deviation) } else { 0.0 })]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        self.window.push_back(Self::typical(candle));
+        if self.window.len() >= self.period {
+            self.window.pop_front();
+        }
+    }
+}
+
+// Middle of the high-low range of the last `period` candles
+struct Midpoint {
+    period: usize,
+    highs: RollingExtreme,
+    lows: RollingExtreme,
+}
+
+impl Midpoint {
+    fn new(period: usize) -> Self {
+        let period = period.max(1);
+        Self {
+            period,
+            highs: RollingExtreme::new(period - 1, true),
+            lows: RollingExtreme::new(period - 1, false),
+        }
+    }
+
+    fn peek(&self, candle: &CandleData) -> Option<f64> {
+        (self.highs.count + 1 >= self.period)
+            .then(|| (self.highs.extreme_with(candle.high) + self.lows.extreme_with(candle.low)) / 2.0)
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        self.highs.push(candle.high);
+        self.lows.push(candle.low);
+    }
+}
+
+// Tenkan, Kijun, Senkou A and Senkou B, each at the candle it is computed
+// from. Shifting the Senkou lines forward is left to drawing.
+struct Ichimoku {
+    tenkan: Midpoint,
+    kijun: Midpoint,
+    senkou_b: Midpoint,
+}
+
+impl Ichimoku {
+    fn new(tenkan: usize, kijun: usize, senkou_b: usize) -> Self {
+        Self {
+            tenkan: Midpoint::new(tenkan),
+            kijun: Midpoint::new(kijun),
+            senkou_b: Midpoint::new(senkou_b),
+        }
+    }
+}
+
+impl Calculator for Ichimoku {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        let tenkan = self.tenkan.peek(candle);
+        let kijun = self.kijun.peek(candle);
+        let senkou_a = tenkan.zip(kijun).map(|(tenkan, kijun)| (tenkan + kijun) / 2.0);
+        vec![tenkan, kijun, senkou_a, self.senkou_b.peek(candle)]
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        self.tenkan.commit(candle);
+        self.kijun.commit(candle);
+        self.senkou_b.commit(candle);
+    }
+}
+
+// Final bands and trend after a candle
+#[derive(Clone, Copy)]
+struct SupertrendState {
+    upper: f64,
+    lower: f64,
+    uptrend: bool,
+}
+
+// ATR bands around the high-low midpoint that only tighten while the trend
+// holds. Outputs the lower band in an uptrend and the upper band in a downtrend.
+struct Supertrend {
+    multiplier: f64,
+    atr: Atr,
+    state: Option<SupertrendState>,
+}
+
+impl Supertrend {
+    fn new(period: usize, multiplier: f64) -> Self {
+        Self {
+            multiplier,
+            atr: Atr::new(period),
+            state: None,
+        }
+    }
+
+    fn state_with(&self, candle: &CandleData) -> Option<SupertrendState> {
+        let atr = self.atr.peek_value(candle)?;
+        let middle = (candle.high + candle.low) / 2.0;
+        let mut upper = middle + self.multiplier * atr;
+        let mut lower = middle - self.multiplier * atr;
+
+        // The first candle with an ATR starts in a downtrend
+        let (state, previous) = match (self.state, &self.atr.previous) {
+            (Some(state), Some(previous)) => (state, previous),
+            _ => return Some(SupertrendState { upper, lower, uptrend: false }),
+        };
+
+        if upper > state.upper && previous.close <= state.upper {
+            upper = state.upper;
+        }
+        if lower < state.lower && previous.close >= state.lower {
+            lower = state.lower;
+        }
+        let uptrend = if state.uptrend {
+            candle.close >= lower
+        } else {
+            candle.close > upper
+        };
+        Some(SupertrendState { upper, lower, uptrend })
+    }
+}
+
+impl Calculator for Supertrend {
+    fn peek(&self, candle: &CandleData) -> Vec<Option<f64>> {
+        match self.state_with(candle) {
+            Some(state) if state.uptrend => vec![Some(state.lower), None],
+            Some(state) => vec![None, Some(state.upper)],
+            None => vec![None; 2],
+        }
+    }
+
+    fn commit(&mut self, candle: &CandleData) {
+        if let Some(state) = self.state_with(candle) {
+            self.state = Some(state);
+        }
+        self.atr.commit(candle);
+    }
+}
+
+fn rsi(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        100.0
+    } else if avg_gain == 0.0 {
+        0.0
+    } else {
+        100.0 - (100.0 / (1.0 + avg_gain / avg_loss))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(calculator: &mut dyn Calculator, closes: &[f64]) -> Vec<Vec<Option<f64>>> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| {
+                let candle = CandleData {
+                    timestamp: i as f64,
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 0.0,
+                };
+                let values = calculator.peek(&candle);
+                calculator.commit(&candle);
+                values
+            })
+            .collect()
+    }
+
+    // Reference values are rounded to two decimals
+    fn assert_close(actual: &[Option<f64>], expected: &[f64], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (i, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+            let actual = actual.unwrap_or_else(|| panic!("no value at {}", i));
+            assert!((actual - expected).abs() <= tolerance, "{}: {} vs {}", i, actual, expected);
+        }
+    }
+
+    // StockCharts' RSI worked example (14 periods)
+    const RSI_CLOSES: [f64; 33] = [
+        44.3389, 44.0902, 44.1497, 43.6124, 44.3278, 44.8264, 45.0955, 45.4245, 45.8433, 46.0826, 45.8931,
+        46.0328, 45.6140, 46.2820, 46.2820, 46.0028, 46.0328, 46.4116, 46.2222, 45.6439, 46.2122, 46.2521,
+        45.7137, 46.4515, 45.7835, 45.3548, 44.0288, 44.1783, 44.2181, 44.5672, 43.4205, 42.6628, 43.1314,
+    ];
+    const RSI_EXPECTED: [f64; 19] = [
+        70.53, 66.32, 66.55, 69.41, 66.36, 57.97, 62.93, 63.26, 56.06, 62.38, 54.71, 50.42, 39.99, 41.46,
+        41.87, 45.46, 37.30, 33.08, 37.77,
+    ];
+
+    // StockCharts' EMA worked example (10 periods)
+    const EMA_CLOSES: [f64; 30] = [
+        22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39, 22.38, 22.61,
+        23.36, 24.05, 23.75, 23.83, 23.95, 23.63, 23.82, 23.87, 23.65, 23.19, 23.10, 23.33, 22.68, 23.10,
+        22.40, 22.17,
+    ];
+    const EMA_EXPECTED: [f64; 21] = [
+        22.22, 22.21, 22.24, 22.27, 22.33, 22.52, 22.80, 22.97, 23.13, 23.28, 23.34, 23.43, 23.51, 23.54,
+        23.47, 23.40, 23.39, 23.26, 23.23, 23.08, 22.92,
+    ];
+
+    #[test]
+    fn wilder_rsi_matches_reference() {
+        let values: Vec<Option<f64>> = run(&mut Rsi::new(14), &RSI_CLOSES)
+            .into_iter()
+            .map(|values| values[0])
+            .collect();
+
+        assert!(values[..14].iter().all(Option::is_none));
+        assert_close(&values[14..], &RSI_EXPECTED, 0.005);
+    }
+
+    #[test]
+    fn ema_is_seeded_with_the_simple_average() {
+        let values: Vec<Option<f64>> = run(&mut EmaLine::new(10), &EMA_CLOSES)
+            .into_iter()
+            .map(|values| values[0])
+            .collect();
+
+        assert!(values[..9].iter().all(Option::is_none));
+        assert_close(&values[9..], &EMA_EXPECTED, 0.01);
+    }
+
+    #[test]
+    fn rsi_without_losses_or_gains() {
+        let rising: Vec<f64> = (0..20).map(f64::from).collect();
+        let falling: Vec<f64> = rising.iter().rev().copied().collect();
+
+        assert_eq!(run(&mut Rsi::new(14), &rising)[19][0], Some(100.0));
+        assert_eq!(run(&mut Rsi::new(14), &falling)[19][0], Some(0.0));
+    }
+}
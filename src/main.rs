@@ -1,13 +1,118 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![allow(rustdoc::missing_crate_level_docs)]
 
+use chrono::Datelike;
+use clap::Parser;
 use eframe::egui;
-use egui_plot::{Line, Plot, PlotPoints, BoxPlot, BoxElem, BoxSpread};
+use egui_plot::{Bar, BarChart, Line, Plot, PlotPoint, PlotPoints, PlotTransform, Points, Polygon, BoxPlot, BoxElem, BoxSpread, Text};
 use std::collections::VecDeque;
 use tokio::sync::mpsc;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use exchange::account::TradingAccount;
+use exchange::aggregated::AggregatedSource;
+use exchange::mock::MockSource;
+use exchange::{ExchangeKind, MarketDataSource, MarketType};
+use indicators::{Fill, IndicatorConfig, IndicatorInstance, Param, Placement, Series, SeriesStyle};
+use drawings::{Anchor, Drawing, DrawingStore, Shape, Tool};
+use orderbook::OrderBook;
+use tape::TradeTape;
+use alerts::{AlertBook, AlertEvent, Comparison, Condition};
+use replay::{ReplayControl, ReplaySource, ReplaySpeed};
+
+mod alerts;
+mod drawings;
+mod exchange;
+mod feed;
+mod indicators;
+mod orderbook;
+mod replay;
+mod store;
+mod tape;
+mod transform;
+
+const DEFAULT_MAX_CANDLES: usize = 10000;
+const VOLUME_PROFILE_BINS: usize = 40; // Price bands in the volume profile
+const CURSOR_GROUP: &str = "chart_cursor"; // Plots that mirror the hovered time
+const X_AXIS_GROUP: &str = "chart_x_axis"; // Plots that pan and zoom together
+const MIN_VISIBLE_CANDLES: f64 = 10.0;
+const MAX_VISIBLE_CANDLES: f64 = 2000.0;
+const DRAWING_HIT_DISTANCE: f64 = 6.0; // Pixels from a drawing or handle that still grab it
+const LADDER_ROWS: usize = 12; // Order book levels listed on each side
+const TAPE_ROWS: usize = 100; // Trades listed in time & sales
+const FLOW_WINDOW: f64 = 60.0; // Seconds of trades in the buy/sell delta
+// Played with alert notifications; names are per platform
+const ALERT_SOUND: &str = if cfg!(target_os = "macos") {
+    "Glass"
+} else if cfg!(windows) {
+    "Default"
+} else {
+    "message-new-instant"
+};
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Crypto trading chart")]
+struct Args {
+    /// Maximum number of candles kept in memory (live updates and history paging)
+    #[arg(long, default_value_t = DEFAULT_MAX_CANDLES)]
+    max_candles: usize,
+    
+    /// Directory for the on-disk candle cache (one file per symbol/interval)
+    #[arg(long, default_value = "candle_cache")]
+    cache_dir: PathBuf,
+    
+    /// Directory where chart drawings are saved (one file per symbol)
+    #[arg(long, default_value = "drawings")]
+    drawings_dir: PathBuf,
+    
+    /// Shell command run when an alert triggers, e.g. to play a sound or send a desktop
+    /// notification. ALERT_SYMBOL, ALERT_MESSAGE and ALERT_PRICE describe the alert.
+    #[arg(long)]
+    alert_command: Option<String>,
+    
+    /// Replay recorded candles from a CSV or JSON file instead of connecting to an exchange
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    
+    /// Use the built-in deterministic mock market (no network needed)
+    #[arg(long, conflicts_with = "replay")]
+    mock: bool,
+    
+    /// Exchange to connect to at startup
+    #[arg(long, value_enum, default_value_t = ExchangeKind::Binance)]
+    exchange: ExchangeKind,
+    
+    /// Start on the spot market instead of USDT perpetual futures
+    #[arg(long)]
+    spot: bool,
+}
 
 fn main() -> eframe::Result {
+    let args = Args::parse();
+    
+    let (source, replay): (Arc<dyn MarketDataSource>, Option<ReplayState>) = match &args.replay {
+        Some(path) => match replay::load_candles(path) {
+            Ok(candles) => {
+                let source = ReplaySource::new(path, candles);
+                let replay = ReplayState {
+                    control: source.control(),
+                    candle_interval: source.candle_interval(),
+                };
+                (Arc::new(source), Some(replay))
+            }
+            Err(e) => {
+                eprintln!("Error loading replay file {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None if args.mock => (Arc::new(AggregatedSource::new(Arc::new(MockSource::new(42)))), None),
+        None => {
+            let market = if args.spot { MarketType::Spot } else { MarketType::Futures };
+            (exchange::create_source(args.exchange, market), None)
+        }
+    };
+    
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1600.0, 900.0])
@@ -18,11 +123,11 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "Crypto Trading Chart",
         options,
-        Box::new(|_cc| Ok(Box::<CryptoApp>::default())),
+        Box::new(move |_cc| Ok(Box::new(CryptoApp::new(source, args, replay)))),
     )
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 struct CandleData {
     timestamp: f64,
     open: f64,
@@ -36,9 +141,31 @@ struct CandleData {
 enum ChartType {
     Line,
     Candlestick,
+    HeikinAshi,
+    OhlcBars,
+    Renko,
 }
 
-#[derive(Clone, PartialEq)]
+impl ChartType {
+    fn to_display_string(&self) -> &'static str {
+        match self {
+            ChartType::Line => "Line",
+            ChartType::Candlestick => "Candle",
+            ChartType::HeikinAshi => "Heikin-Ashi",
+            ChartType::OhlcBars => "OHLC",
+            ChartType::Renko => "Renko",
+        }
+    }
+}
+
+// Part of a drawing being dragged on the price chart
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Grab {
+    Anchor(usize), // Moves one anchor
+    Body,          // Moves the whole drawing
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 enum Timeframe {
     M1,   // 1 minute
     M3,   // 3 minutes
@@ -51,10 +178,48 @@ enum Timeframe {
     D1,   // Daily
     W1,   // Weekly
     MN1,  // Monthly
+    Custom(u32), // Any other whole number of minutes, aggregated client-side
 }
 
+const ALL_TIMEFRAMES: [Timeframe; 11] = [
+    Timeframe::M1,
+    Timeframe::M3,
+    Timeframe::M5,
+    Timeframe::M15,
+    Timeframe::M30,
+    Timeframe::H1,
+    Timeframe::H4,
+    Timeframe::H12,
+    Timeframe::D1,
+    Timeframe::W1,
+    Timeframe::MN1,
+];
+
+// Offered alongside the native intervals; each is built from the largest
+// native interval that divides it
+const CUSTOM_TIMEFRAMES: [Timeframe; 5] = [
+    Timeframe::Custom(2),
+    Timeframe::Custom(10),
+    Timeframe::Custom(120),
+    Timeframe::Custom(360),
+    Timeframe::Custom(3 * 1440),
+];
+
 impl Timeframe {
-    fn to_api_string(&self) -> &'static str {
+    // Closest native timeframe for a candle spacing in seconds
+    fn from_candle_interval(interval: f64) -> Timeframe {
+        ALL_TIMEFRAMES
+            .iter()
+            .min_by(|a, b| {
+                let da = (a.get_candle_interval() - interval).abs();
+                let db = (b.get_candle_interval() - interval).abs();
+                da.total_cmp(&db)
+            })
+            .cloned()
+            .unwrap_or(Timeframe::M1)
+    }
+    
+    fn to_api_string(&self) -> String {
         match self {
             Timeframe::M1 => "1m",
             Timeframe::M3 => "3m",
@@ -67,10 +232,13 @@ impl Timeframe {
             Timeframe::D1 => "1d",
             Timeframe::W1 => "1w",
             Timeframe::MN1 => "1M",
+            // Not an exchange interval; names the cache for aggregated candles
+            Timeframe::Custom(_) => return self.to_display_string(),
         }
+        .to_string()
     }
     
-    fn to_display_string(&self) -> &'static str {
+    fn to_display_string(&self) -> String {
         match self {
             Timeframe::M1 => "1m",
             Timeframe::M3 => "3m",
@@ -83,7 +251,11 @@ impl Timeframe {
             Timeframe::D1 => "1d",
             Timeframe::W1 => "1w",
             Timeframe::MN1 => "1M",
+            Timeframe::Custom(minutes) if minutes % 1440 == 0 => return format!("{}d", minutes / 1440),
+            Timeframe::Custom(minutes) if minutes % 60 == 0 => return format!("{}h", minutes / 60),
+            Timeframe::Custom(minutes) => return format!("{}m", minutes),
         }
+        .to_string()
     }
     
     fn get_window_size(&self) -> f64 {
@@ -99,6 +271,7 @@ impl Timeframe {
             Timeframe::D1 => 60.0 * 60.0 * 24.0 * 100.0, // 100 days
             Timeframe::W1 => 60.0 * 60.0 * 24.0 * 7.0 * 50.0, // 50 weeks
             Timeframe::MN1 => 60.0 * 60.0 * 24.0 * 30.0 * 12.0, // 12 months
+            Timeframe::Custom(_) => self.get_candle_interval() * 100.0, // 100 candles
         }
     }
     
@@ -115,7 +288,50 @@ impl Timeframe {
             Timeframe::H12 => 43200.0,       // 12 hours
             Timeframe::D1 => 86400.0,        // 1 day
             Timeframe::W1 => 604800.0,       // 1 week
-            Timeframe::MN1 => 2592000.0,     // 1 month (30 days)
+            Timeframe::MN1 => 2592000.0,     // 1 month (30 days); see candle_open
+            Timeframe::Custom(minutes) => *minutes as f64 * 60.0,
+        }
+    }
+    
+    // Native interval a custom timeframe is aggregated from: the largest one
+    // up to a day that divides it. None for native timeframes.
+    fn native_base(&self) -> Option<Timeframe> {
+        let Timeframe::Custom(_) = self else { return None };
+        let interval = self.get_candle_interval();
+        ALL_TIMEFRAMES[..9]
+            .iter()
+            .rev()
+            .find(|native| interval % native.get_candle_interval() == 0.0)
+            .cloned()
+    }
+    
+    // Open time of the candle containing `timestamp`. Weeks start on Monday and
+    // months on the 1st, both at 00:00 UTC like exchange klines.
+    fn candle_open(&self, timestamp: f64) -> f64 {
+        const MONDAY_OFFSET: f64 = 4.0 * 86400.0; // The epoch was a Thursday
+        match self {
+            Timeframe::W1 => {
+                let interval = self.get_candle_interval();
+                ((timestamp - MONDAY_OFFSET) / interval).floor() * interval + MONDAY_OFFSET
+            }
+            Timeframe::MN1 => chrono::DateTime::from_timestamp(timestamp.floor() as i64, 0)
+                .and_then(|time| time.date_naive().with_day(1))
+                .and_then(|first| first.and_hms_opt(0, 0, 0))
+                .map(|midnight| midnight.and_utc().timestamp() as f64)
+                .unwrap_or(timestamp),
+            _ => {
+                let interval = self.get_candle_interval();
+                (timestamp / interval).floor() * interval
+            }
+        }
+    }
+    
+    // Close time of the candle opening at `open`
+    fn candle_close(&self, open: f64) -> f64 {
+        match self {
+            // Any time in the next month, rounded down to its start
+            Timeframe::MN1 => self.candle_open(open + 32.0 * 86400.0),
+            _ => open + self.get_candle_interval(),
         }
     }
 }
@@ -139,7 +355,30 @@ struct TradingPanel {
     price: String,
     current_price: f64,
     balance_usdt: f64,
-    balance_btc: f64,
+    base_balances: HashMap<String, f64>, // Virtual holdings per base asset
+    live_orders: bool,                   // Send orders to the exchange spot account
+    exchange_balances: Option<HashMap<String, f64>>,
+    order_status: Option<Result<String, String>>,
+}
+
+impl TradingPanel {
+    fn quote_balance(&self) -> f64 {
+        match (&self.exchange_balances, self.live_orders) {
+            (Some(balances), true) => balances.get(exchange::QUOTE_ASSET).copied().unwrap_or(0.0),
+            _ => self.balance_usdt,
+        }
+    }
+    
+    fn base_balance(&self, asset: &str) -> f64 {
+        match (&self.exchange_balances, self.live_orders) {
+            (Some(balances), true) => balances.get(asset).copied().unwrap_or(0.0),
+            _ => self.base_balances.get(asset).copied().unwrap_or(0.0),
+        }
+    }
+
+    fn base_balance_mut(&mut self, asset: &str) -> &mut f64 {
+        self.base_balances.entry(asset.to_string()).or_insert(0.0)
+    }
 }
 
 impl Default for TradingPanel {
@@ -151,454 +390,1481 @@ impl Default for TradingPanel {
             price: "0.0".to_string(),
             current_price: 0.0,
             balance_usdt: 10000.0,  // Virtual balance
-            balance_btc: 0.0,
+            base_balances: HashMap::new(),
+            live_orders: false,
+            exchange_balances: None,
+            order_status: None,
         }
     }
 }
 
+enum AccountEvent {
+    Balances(HashMap<String, f64>),
+    OrderResult(Result<String, String>),
+}
+
+// UI-side handle for a replay source
+struct ReplayState {
+    control: Arc<ReplayControl>,
+    candle_interval: Option<f64>,
+}
+
+// Candles of a timeframe other than the chart's, for indicators that use one
+struct TimeframeSeries {
+    timeframe: Timeframe,
+    candle_data: Arc<Mutex<VecDeque<CandleData>>>,
+    receiver: Option<mpsc::UnboundedReceiver<feed::FeedEvent>>,
+    task: Option<tokio::task::JoinHandle<()>>, // None in replay, where candles are aggregated instead
+    aggregate: Option<transform::RollingAggregate>, // Replay only, built from the chart's candles
+}
+
+// Heikin-Ashi or Renko candles as last built, and what they were built from
+#[derive(Default)]
+struct TransformedCandles {
+    built_for: Option<(ChartType, transform::BoxSize)>,
+    built_from: (usize, Option<CandleData>, Option<CandleData>), // Length and the candles at either end
+    candles: Option<VecDeque<CandleData>>,
+}
+
+impl TransformedCandles {
+    // Rebuilt only when the chart type, the brick size or the candles change.
+    // Candles are only added or updated at either end.
+    fn get(&mut self, chart_type: &ChartType, renko_box: transform::BoxSize, data: &VecDeque<CandleData>) -> Option<&VecDeque<CandleData>> {
+        let built_for = Some((chart_type.clone(), renko_box));
+        let built_from = (data.len(), data.front().cloned(), data.back().cloned());
+        if self.built_for != built_for || self.built_from != built_from {
+            self.candles = transformed_candles(chart_type, renko_box, data);
+            self.built_for = built_for;
+            self.built_from = built_from;
+        }
+        self.candles.as_ref()
+    }
+}
+
 struct CryptoApp {
+    source: Arc<dyn MarketDataSource>,
+    exchange: ExchangeKind,
+    market_type: Option<MarketType>, // None when the source is not an exchange
+    account: Option<Arc<dyn TradingAccount>>,
+    account_sender: mpsc::UnboundedSender<AccountEvent>,
+    account_receiver: mpsc::UnboundedReceiver<AccountEvent>,
+    account_task: Option<tokio::task::JoinHandle<()>>,
     candle_data: Arc<Mutex<VecDeque<CandleData>>>,
     chart_type: ChartType,
     timeframe: Timeframe,
     candle_width: f64,
     is_loading: bool,
     runtime: Option<tokio::runtime::Runtime>,
-    data_receiver: Option<mpsc::UnboundedReceiver<Vec<CandleData>>>,
+    data_receiver: Option<mpsc::UnboundedReceiver<feed::FeedEvent>>,
+    health: feed::ConnectionHealth,
+    fetch_task: Option<tokio::task::JoinHandle<()>>,
+    trade_receiver: Option<mpsc::UnboundedReceiver<feed::TradeEvent>>,
+    trade_task: Option<tokio::task::JoinHandle<()>>,
+    trade_tape: TradeTape,
+    tape_error: Option<String>, // Latest trade feed problem, kept until the feed restarts
+    show_tape: bool,
+    large_trade: f64, // Notional in the quote asset from which trades are highlighted
+    depth_receiver: Option<mpsc::UnboundedReceiver<feed::DepthEvent>>,
+    depth_task: Option<tokio::task::JoinHandle<()>>,
+    order_book: Option<OrderBook>,
+    order_book_error: Option<String>, // Since the last book, e.g. while resyncing
+    show_order_book: bool,
+    max_candles: usize,
+    cache_dir: PathBuf,
+    history_receiver: Option<mpsc::UnboundedReceiver<feed::HistoryPage>>,
+    history_task: Option<tokio::task::JoinHandle<()>>,
+    history_loading: bool,
+    history_exhausted: bool,
+    history_backoff: feed::HistoryBackoff, // After failed pages
+    symbol: String,
+    symbols: Vec<String>,
+    symbols_receiver: Option<mpsc::UnboundedReceiver<Vec<String>>>,
     latest_timestamp: f64,
     view_window_start: f64,
     window_size: f64,
+    zoom_levels: HashMap<Timeframe, f64>, // Window size last chosen for each timeframe
     is_dragging: bool,
     is_live_mode: bool,
-    timeframe_changed: bool,
     trading_panel: TradingPanel,
-    show_ma20: bool,
-    show_bollinger: bool,
-    show_macd: bool,
-    show_rsi: bool,
+    drawing_store: DrawingStore,
+    drawings: Vec<Drawing>, // For the current symbol
+    next_drawing_id: u64,
+    drawing_tool: Option<Tool>,
+    pending_anchors: Vec<Anchor>, // Placed so far with the active tool
+    selected_drawing: Option<u64>,
+    drawing_grab: Option<(u64, Grab)>,
+    price_transform: Option<PlotTransform>, // Price chart as last drawn, for hit tests before the next frame
+    alerts: AlertBook,
+    alert_command: String, // Empty to run nothing
+    alert_errors: (mpsc::UnboundedSender<String>, mpsc::UnboundedReceiver<String>),
+    alert_error: Option<String>, // Last failure of the command or notification, shown in the alerts window
+    alert_notify: bool, // Desktop notification with sound on trigger
+    alert_toasts: Vec<(AlertEvent, std::time::Instant)>, // Shown over the chart until they expire or are dismissed
+    alert_log_open: bool,
+    new_alert: Condition, // Condition being set up in the trading panel
+    alert_menu_price: Option<f64>, // Price right-clicked on the chart
+    indicators: Vec<IndicatorInstance>,
+    next_indicator_id: u64,
+    indicator_dialog_open: bool,
+    new_indicator: IndicatorConfig, // Kind and parameters picked in the add dialog
+    new_indicator_timeframe: Option<Timeframe>,
+    timeframe_series: Vec<TimeframeSeries>,
     show_volume: bool,
+    show_volume_profile: bool,
+    renko_box: transform::BoxSize,
+    indicators_on_chart: bool, // Compute indicators from Heikin-Ashi/Renko prices
+    indicator_source: Option<ChartType>, // Transform the indicators were last synced with
+    transformed: TransformedCandles,
+    replay: Option<ReplayState>,
 }
 
-impl Default for CryptoApp {
-    fn default() -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
+impl CryptoApp {
+    fn new(source: Arc<dyn MarketDataSource>, args: Args, replay: Option<ReplayState>) -> Self {
+        let market_type = match (&replay, args.mock, args.spot) {
+            (None, false, true) => Some(MarketType::Spot),
+            (None, false, false) => Some(MarketType::Futures),
+            _ => None,
+        };
+        let (account_sender, account_receiver) = mpsc::unbounded_channel();
         
-        let timeframe = Timeframe::M1;
+        let timeframe = replay
+            .as_ref()
+            .and_then(|replay| replay.candle_interval)
+            .map(Timeframe::from_candle_interval)
+            .unwrap_or(Timeframe::M1);
         let window_size = timeframe.get_window_size();
         
-        let app = Self {
+        let mut app = Self {
+            source,
+            exchange: args.exchange,
+            market_type,
+            account: exchange::create_account(args.exchange),
+            account_sender,
+            account_receiver,
+            account_task: None,
             candle_data: Arc::new(Mutex::new(VecDeque::new())),
             chart_type: ChartType::Candlestick,
             timeframe,
             candle_width: 0.8,
             is_loading: true,
             runtime: Some(tokio::runtime::Runtime::new().unwrap()),
-            data_receiver: Some(rx),
+            data_receiver: None,
+            health: feed::ConnectionHealth::default(),
+            fetch_task: None,
+            trade_receiver: None,
+            trade_task: None,
+            trade_tape: TradeTape::new(TAPE_ROWS, FLOW_WINDOW),
+            tape_error: None,
+            show_tape: true,
+            large_trade: 50000.0,
+            depth_receiver: None,
+            depth_task: None,
+            order_book: None,
+            order_book_error: None,
+            show_order_book: true,
+            max_candles: args.max_candles.max(1),
+            cache_dir: args.cache_dir,
+            history_receiver: None,
+            history_task: None,
+            history_loading: false,
+            history_exhausted: false,
+            history_backoff: feed::HistoryBackoff::default(),
+            symbol: "BTCUSDT".to_string(),
+            symbols: exchange::default_symbols(),
+            symbols_receiver: None,
             latest_timestamp: 0.0,
             view_window_start: 0.0,
             window_size,
+            zoom_levels: HashMap::new(),
             is_dragging: false,
             is_live_mode: true,
-            timeframe_changed: false,
             trading_panel: TradingPanel::default(),
-            show_ma20: true,
-            show_bollinger: false,
-            show_macd: true,
-            show_rsi: true,
+            drawing_store: DrawingStore::new(&args.drawings_dir),
+            drawings: Vec::new(),
+            next_drawing_id: 0,
+            drawing_tool: None,
+            pending_anchors: Vec::new(),
+            selected_drawing: None,
+            drawing_grab: None,
+            price_transform: None,
+            alerts: AlertBook::default(),
+            alert_command: args.alert_command.unwrap_or_default(),
+            alert_errors: mpsc::unbounded_channel(),
+            alert_error: None,
+            alert_notify: true,
+            alert_toasts: Vec::new(),
+            alert_log_open: false,
+            new_alert: Condition::PriceCross { level: 0.0 },
+            alert_menu_price: None,
+            indicators: Vec::new(),
+            next_indicator_id: 0,
+            indicator_dialog_open: false,
+            new_indicator: IndicatorConfig::Sma { period: 20 },
+            new_indicator_timeframe: None,
+            timeframe_series: Vec::new(),
             show_volume: true,
+            show_volume_profile: false,
+            renko_box: transform::BoxSize::Atr(14),
+            indicators_on_chart: false,
+            indicator_source: None,
+            transformed: TransformedCandles::default(),
+            replay,
         };
         
-        // Start fetching data
-        if let Some(rt) = &app.runtime {
-            let candle_data_clone = app.candle_data.clone();
-            let timeframe_clone = app.timeframe.clone();
-            rt.spawn(fetch_binance_data(tx, candle_data_clone, timeframe_clone));
+        for config in [
+            IndicatorConfig::Sma { period: 20 },
+            IndicatorConfig::Macd { fast: 12, slow: 26, signal: 9 },
+            IndicatorConfig::Rsi { period: 14 },
+        ] {
+            app.add_indicator(config, None);
         }
         
+        app.load_drawings();
+        
+        // Start fetching data
+        app.restart_data_feed();
+        app.restart_trade_feed();
+        app.restart_depth_feed();
+        app.refresh_symbols();
+        app.restart_account_feed();
+        
         app
     }
-}
-
-async fn fetch_binance_data(
-    tx: mpsc::UnboundedSender<Vec<CandleData>>,
-    candle_data: Arc<Mutex<VecDeque<CandleData>>>,
-    timeframe: Timeframe,
-) {
-    loop {
-        match fetch_klines_latest(&timeframe).await {
-            Ok(candles) => {
-                if let Ok(mut data) = candle_data.lock() {
-                    if data.is_empty() {
-                        data.extend(candles.iter().cloned());
-                    } else {
-                        let latest_existing_time = data.back().map(|d| d.timestamp).unwrap_or(0.0);
-                        
-                        for new_candle in &candles {
-                            if new_candle.timestamp > latest_existing_time {
-                                data.push_back(new_candle.clone());
-                            } else if let Some(existing_pos) = data.iter().position(|existing| 
-                                (existing.timestamp - new_candle.timestamp).abs() < 1.0) {
-                                data[existing_pos] = new_candle.clone();
-                            }
-                        }
-                        
-                        while data.len() > 10000 {
-                            data.pop_front();
-                        }
-                    }
-                }
-                
-                if tx.send(candles).is_err() {
-                    break;
-                }
+    
+    // Evaluate the current symbol's alerts against the latest candles and
+    // announce any that trigger
+    fn check_alerts(&mut self, ctx: &egui::Context) {
+        let now = match self.replay {
+            Some(_) => self.latest_timestamp,
+            None => chrono::Utc::now().timestamp() as f64,
+        };
+        let events = match self.candle_data.lock() {
+            Ok(data) => self.alerts.check(&self.symbol, now, &data),
+            Err(_) => return,
+        };
+        if events.is_empty() {
+            return;
+        }
+        
+        self.alert_log_open = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(egui::UserAttentionType::Informational));
+        for event in events {
+            if self.alert_notify {
+                self.notify_desktop(&event);
             }
-            Err(e) => {
-                eprintln!("Error fetching data: {}", e);
+            self.run_alert_command(&event);
+            self.alert_toasts.push((event, std::time::Instant::now()));
+        }
+    }
+    
+    fn notify_desktop(&self, event: &AlertEvent) {
+        let Some(rt) = &self.runtime else { return };
+        let summary = format!("{} alert", event.symbol);
+        let body = format!("{} at {:.2}", event.message, event.price);
+        
+        // Showing it can block on the notification service
+        let errors = self.alert_errors.0.clone();
+        rt.spawn_blocking(move || {
+            let shown = notify_rust::Notification::new()
+                .appname("Asterism")
+                .summary(&summary)
+                .body(&body)
+                .sound_name(ALERT_SOUND)
+                .show();
+            if let Err(e) = shown {
+                let _ = errors.send(format!("Desktop notification failed: {}", e));
             }
+        });
+    }
+    
+    fn run_alert_command(&self, event: &AlertEvent) {
+        let Some(rt) = &self.runtime else { return };
+        if self.alert_command.trim().is_empty() {
+            return;
         }
         
-        let update_interval = match timeframe {
-            Timeframe::M1 | Timeframe::M3 | Timeframe::M5 => 3,
-            Timeframe::M15 | Timeframe::M30 => 30,
-            Timeframe::H1 | Timeframe::H4 => 60,
-            _ => 300,
+        let mut command = if cfg!(windows) {
+            let mut command = tokio::process::Command::new("cmd");
+            command.arg("/C");
+            command
+        } else {
+            let mut command = tokio::process::Command::new("sh");
+            command.arg("-c");
+            command
         };
+        command
+            .arg(&self.alert_command)
+            .env("ALERT_SYMBOL", &event.symbol)
+            .env("ALERT_MESSAGE", &event.message)
+            .env("ALERT_PRICE", event.price.to_string());
         
-        tokio::time::sleep(tokio::time::Duration::from_secs(update_interval)).await;
+        let errors = self.alert_errors.0.clone();
+        rt.spawn(async move {
+            let error = match command.status().await {
+                Ok(status) if !status.success() => format!("Alert command failed: {}", status),
+                Ok(_) => return,
+                Err(e) => format!("Failed to run alert command: {}", e),
+            };
+            let _ = errors.send(error);
+        });
     }
-}
-
-async fn fetch_klines_latest(timeframe: &Timeframe) -> Result<Vec<CandleData>, Box<dyn std::error::Error>> {
-    let url = format!(
-        "https://fapi.binance.com/fapi/v1/klines?symbol=BTCUSDT&interval={}&limit=500",
-        timeframe.to_api_string()
-    );
     
-    let client = reqwest::Client::new();
-    let response = client.get(&url).send().await?;
+    fn add_indicator(&mut self, config: IndicatorConfig, timeframe: Option<Timeframe>) {
+        let mut instance = IndicatorInstance::new(self.next_indicator_id, config);
+        instance.set_timeframe(timeframe);
+        self.indicators.push(instance);
+        self.next_indicator_id += 1;
+    }
     
-    if !response.status().is_success() {
-        return Err(format!("API error: {}", response.status()).into());
+    // Run a feed for each other timeframe indicators use, and only for those
+    fn sync_timeframe_series(&mut self) {
+        let mut needed: Vec<Timeframe> = Vec::new();
+        for timeframe in self.indicators.iter().filter_map(|instance| instance.timeframe()) {
+            if *timeframe != self.timeframe && !needed.contains(timeframe) {
+                needed.push(timeframe.clone());
+            }
+        }
+        
+        self.timeframe_series.retain(|series| {
+            let keep = needed.contains(&series.timeframe);
+            if let (false, Some(task)) = (keep, &series.task) {
+                task.abort();
+            }
+            keep
+        });
+        
+        for timeframe in needed {
+            if self.timeframe_series.iter().any(|series| series.timeframe == timeframe) {
+                continue;
+            }
+            
+            let candle_data = Arc::new(Mutex::new(VecDeque::new()));
+            let (mut receiver, mut task, mut aggregate) = (None, None, None);
+            if self.replay.is_some() {
+                aggregate = Some(transform::RollingAggregate::new(timeframe.clone()));
+            } else if let Some(rt) = &self.runtime {
+                let (tx, rx) = mpsc::unbounded_channel();
+                let store = feed::open_store(self.source.as_ref(), &self.cache_dir, &self.symbol, &timeframe);
+                task = Some(rt.spawn(feed::run_data_feed(self.source.clone(), tx, candle_data.clone(), self.symbol.clone(), timeframe.clone(), self.max_candles, store)));
+                receiver = Some(rx);
+            }
+            self.timeframe_series.push(TimeframeSeries { timeframe, candle_data, receiver, task, aggregate });
+        }
+        
+        // In replay, fold the chart's new candles into each timeframe
+        if let Ok(data) = self.candle_data.lock() {
+            for aggregate in self.timeframe_series.iter_mut().filter_map(|series| series.aggregate.as_mut()) {
+                aggregate.update(&data);
+            }
+        }
+        
+        // Connection state is only reported for the chart's own feed
+        for series in &mut self.timeframe_series {
+            if let Some(receiver) = &mut series.receiver {
+                while receiver.try_recv().is_ok() {}
+            }
+        }
     }
     
-    let text = response.text().await?;
-    let json: serde_json::Value = serde_json::from_str(&text)?;
+    // Extend the symbol list with what the source offers (top-volume pairs for Binance)
+    fn refresh_symbols(&mut self) {
+        if let Some(rt) = &self.runtime {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.symbols_receiver = Some(rx);
+            let source = self.source.clone();
+            rt.spawn(async move {
+                match source.symbols().await {
+                    Ok(symbols) => {
+                        let _ = tx.send(symbols.into_iter().map(|info| info.symbol).collect());
+                    }
+                    Err(e) => eprintln!("Error fetching symbols from {}: {}", source.name(), e),
+                }
+            });
+        }
+    }
     
-    let mut candles = Vec::new();
+    fn switch_venue(&mut self, exchange: ExchangeKind, market: MarketType) {
+        if exchange != self.exchange {
+            self.exchange = exchange;
+            self.account = exchange::create_account(exchange);
+            self.trading_panel.live_orders = false;
+        }
+        self.market_type = Some(market);
+        self.source = exchange::create_source(exchange, market);
+        self.symbols = exchange::default_symbols();
+        self.trading_panel.current_price = 0.0;
+        self.restart_data_feed();
+        self.restart_trade_feed();
+        self.restart_depth_feed();
+        self.refresh_symbols();
+        self.restart_account_feed();
+    }
     
-    if let Some(array) = json.as_array() {
-        for item in array {
-            if let Some(kline_array) = item.as_array() {
-                if kline_array.len() >= 11 {
-                    let timestamp = kline_array[0].as_i64().unwrap_or(0) as f64;
-                    let open = kline_array[1].as_str().unwrap_or("0").parse::<f64>().unwrap_or(0.0);
-                    let high = kline_array[2].as_str().unwrap_or("0").parse::<f64>().unwrap_or(0.0);
-                    let low = kline_array[3].as_str().unwrap_or("0").parse::<f64>().unwrap_or(0.0);
-                    let close = kline_array[4].as_str().unwrap_or("0").parse::<f64>().unwrap_or(0.0);
-                    let volume = kline_array[5].as_str().unwrap_or("0").parse::<f64>().unwrap_or(0.0);
-                    
-                    if open > 0.0 && high > 0.0 && low > 0.0 && close > 0.0 {
-                        candles.push(CandleData {
-                            timestamp: timestamp / 1000.0,
-                            open,
-                            high,
-                            low,
-                            close,
-                            volume,
-                        });
+    // Poll spot balances while on the spot market with API keys configured
+    fn restart_account_feed(&mut self) {
+        if let Some(task) = self.account_task.take() {
+            task.abort();
+        }
+        self.trading_panel.exchange_balances = None;
+        
+        if self.market_type != Some(MarketType::Spot) {
+            self.trading_panel.live_orders = false;
+            return;
+        }
+        
+        if let (Some(rt), Some(account)) = (&self.runtime, &self.account) {
+            let account = account.clone();
+            let tx = self.account_sender.clone();
+            self.account_task = Some(rt.spawn(async move {
+                loop {
+                    match account.balances().await {
+                        Ok(balances) => {
+                            if tx.send(AccountEvent::Balances(balances)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => eprintln!("Error fetching {} balances: {}", account.name(), e),
                     }
+                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                 }
+            }));
+        }
+    }
+    
+    fn submit_live_order(&mut self) {
+        if let (Some(rt), Some(account)) = (&self.runtime, &self.account) {
+            let account = account.clone();
+            let tx = self.account_sender.clone();
+            let symbol = self.symbol.clone();
+            let order_type = self.trading_panel.order_type.clone();
+            let order_mode = self.trading_panel.order_mode.clone();
+            let quantity = self.trading_panel.quantity.trim().to_string();
+            let price = self.trading_panel.price.trim().to_string();
+            self.trading_panel.order_status = None;
+            rt.spawn(async move {
+                let result = account
+                    .place_order(&symbol, &order_type, &order_mode, &quantity, &price)
+                    .await
+                    .map_err(|e| e.to_string());
+                let _ = tx.send(AccountEvent::OrderResult(result));
+            });
+        }
+    }
+    
+    // Trades only depend on the symbol, so timeframe changes keep the connection
+    fn restart_trade_feed(&mut self) {
+        if let Some(task) = self.trade_task.take() {
+            task.abort();
+        }
+        self.trade_tape.clear();
+        self.tape_error = None;
+        
+        if let Some(rt) = &self.runtime {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.trade_receiver = Some(rx);
+            self.trade_task = Some(rt.spawn(feed::run_trade_feed(self.source.clone(), tx, self.symbol.clone())));
+        }
+    }
+    
+    fn restart_depth_feed(&mut self) {
+        if let Some(task) = self.depth_task.take() {
+            task.abort();
+        }
+        self.order_book = None;
+        self.order_book_error = None;
+        
+        if let Some(rt) = &self.runtime {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.depth_receiver = Some(rx);
+            self.depth_task = Some(rt.spawn(feed::run_depth_feed(self.source.clone(), tx, self.symbol.clone())));
+        }
+    }
+    
+    // Drawings of the current symbol, replacing those of the previous one
+    fn load_drawings(&mut self) {
+        self.drawings = self.drawing_store.load(&self.symbol).unwrap_or_else(|e| {
+            eprintln!("Error loading drawings for {}: {}", self.symbol, e);
+            Vec::new()
+        });
+        self.next_drawing_id = self.drawings.iter().map(|drawing| drawing.id + 1).max().unwrap_or(0);
+        self.pending_anchors.clear();
+        self.selected_drawing = None;
+        self.drawing_grab = None;
+    }
+    
+    fn save_drawings(&self) {
+        if let Err(e) = self.drawing_store.save(&self.symbol, &self.drawings) {
+            eprintln!("Error saving drawings for {}: {}", self.symbol, e);
+        }
+    }
+    
+    fn delete_selected_drawing(&mut self) {
+        if let Some(id) = self.selected_drawing.take() {
+            self.drawings.retain(|drawing| drawing.id != id);
+            self.save_drawings();
+        }
+    }
+    
+    // Room left after the latest candle while following live data
+    fn live_buffer(&self) -> f64 {
+        match self.timeframe {
+            Timeframe::M1 => 60.0 * 5.0,
+            Timeframe::M3 => 60.0 * 15.0,
+            Timeframe::M5 => 60.0 * 25.0,
+            Timeframe::M15 => 60.0 * 75.0,
+            Timeframe::M30 => 60.0 * 150.0,
+            Timeframe::H1 => 60.0 * 60.0 * 5.0,
+            Timeframe::H4 => 60.0 * 60.0 * 20.0,
+            _ => 60.0 * 60.0 * 24.0 * 5.0,
+        }
+    }
+    
+    // (Re)start the background fetcher for the current symbol and timeframe
+    fn restart_data_feed(&mut self) {
+        if let Some(task) = self.fetch_task.take() {
+            task.abort();
+        }
+        if let Some(task) = self.history_task.take() {
+            task.abort();
+        }
+        self.history_receiver = None;
+        self.history_loading = false;
+        self.history_exhausted = false;
+        self.history_backoff = feed::HistoryBackoff::default();
+        self.health = feed::ConnectionHealth::default();
+        
+        if let Ok(mut data) = self.candle_data.lock() {
+            data.clear();
+        }
+        
+        // Other timeframes restart for the new symbol on the next frame
+        for series in self.timeframe_series.drain(..) {
+            if let Some(task) = series.task {
+                task.abort();
             }
         }
+        for instance in &mut self.indicators {
+            instance.reset();
+        }
+        
+        if let Some(rt) = &self.runtime {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.data_receiver = Some(rx);
+            
+            let source = self.source.clone();
+            let candle_data_clone = self.candle_data.clone();
+            let symbol_clone = self.symbol.clone();
+            let timeframe_clone = self.timeframe.clone();
+            let max_candles = self.max_candles;
+            let store = feed::open_store(self.source.as_ref(), &self.cache_dir, &self.symbol, &self.timeframe);
+            self.fetch_task = Some(rt.spawn(feed::run_data_feed(source, tx, candle_data_clone, symbol_clone, timeframe_clone, max_candles, store)));
+        }
+        
+        self.is_loading = true;
+        self.view_window_start = 0.0;
+        self.is_live_mode = true;
     }
     
-    Ok(candles)
+    // Page in older klines once the view scrolls past the oldest loaded candle
+    fn request_older_history(&mut self) {
+        if let Some(receiver) = &mut self.history_receiver {
+            if let Ok(result) = receiver.try_recv() {
+                match result {
+                    Ok((0, _)) => self.history_exhausted = true,
+                    Ok((_, skipped)) => {
+                        self.history_backoff.succeeded();
+                        if let Some(e) = skipped {
+                            eprintln!("Malformed history: {}", e);
+                            self.health.last_error = Some(e);
+                        }
+                    }
+                    // Try again after a while; scrolling left keeps asking
+                    Err((e, rate_limited)) => {
+                        eprintln!("Error fetching history: {}", e);
+                        self.health.last_error = Some(e);
+                        self.history_backoff.failed(rate_limited, std::time::Instant::now());
+                    }
+                }
+                self.history_loading = false;
+                self.history_receiver = None;
+                self.history_task = None;
+            }
+        }
+        
+        // Recorded data has no older history to page in
+        if self.is_loading || self.history_loading || self.history_exhausted || self.replay.is_some() {
+            return;
+        }
+        if !self.history_backoff.ready(std::time::Instant::now()) {
+            return;
+        }
+        
+        let (oldest_timestamp, loaded) = match self.candle_data.lock() {
+            Ok(data) => match data.front() {
+                Some(candle) => (candle.timestamp, data.len()),
+                None => return,
+            },
+            Err(_) => return,
+        };
+        
+        if self.view_window_start >= oldest_timestamp {
+            return;
+        }
+        
+        if loaded >= self.max_candles {
+            self.history_exhausted = true;
+            return;
+        }
+        
+        if let Some(rt) = &self.runtime {
+            let (tx, rx) = mpsc::unbounded_channel();
+            self.history_receiver = Some(rx);
+            self.history_loading = true;
+            
+            let source = self.source.clone();
+            let candle_data_clone = self.candle_data.clone();
+            let symbol_clone = self.symbol.clone();
+            let timeframe_clone = self.timeframe.clone();
+            let max_candles = self.max_candles;
+            let store = feed::open_store(self.source.as_ref(), &self.cache_dir, &self.symbol, &self.timeframe);
+            self.history_task = Some(rt.spawn(async move {
+                let result = feed::load_older_history(source, candle_data_clone, symbol_clone, timeframe_clone, oldest_timestamp, max_candles, store).await;
+                let _ = tx.send(result);
+            }));
+        }
+    }
 }
 
-// Calculate 20-period Moving Average
-fn calculate_ma20(data: &[CandleData]) -> Vec<(f64, f64)> {
-    let mut ma_points = Vec::new();
-    
-    for i in 19..data.len() {
-        let sum: f64 = data[i-19..=i].iter().map(|candle| candle.close).sum();
-        let ma_value = sum / 20.0;
-        ma_points.push((data[i].timestamp, ma_value));
+// Candles around the visible window, with a margin so lines run off the edges
+fn visible_candles(data: &VecDeque<CandleData>, view_window_start: f64, window_size: f64) -> Vec<CandleData> {
+    let margin = window_size * 0.1;
+    let window_end = view_window_start + window_size;
+    data.iter()
+        .filter(|candle| candle.timestamp >= (view_window_start - margin) && candle.timestamp <= (window_end + margin))
+        .cloned()
+        .collect()
+}
+
+// Heikin-Ashi candles or Renko bricks for the chart type; None for raw candles
+fn transformed_candles(chart_type: &ChartType, renko_box: transform::BoxSize, data: &VecDeque<CandleData>) -> Option<VecDeque<CandleData>> {
+    match chart_type {
+        ChartType::HeikinAshi => Some(transform::heikin_ashi(data)),
+        ChartType::Renko => Some(renko_box.resolve(data).map_or_else(VecDeque::new, |size| transform::renko(data, size))),
+        _ => None,
     }
-    
-    ma_points
 }
 
-// Calculate Bollinger Bands (20-period, 2 standard deviations)
-fn calculate_bollinger_bands(data: &[CandleData]) -> (Vec<(f64, f64)>, Vec<(f64, f64)>, Vec<(f64, f64)>) {
-    let mut upper_band = Vec::new();
-    let mut middle_band = Vec::new();
-    let mut lower_band = Vec::new();
+// Draw candles as boxes with wicks
+fn plot_candles(plot_ui: &mut egui_plot::PlotUi, name: &str, candles: &[CandleData], width: f64) {
+    let mut box_elements = Vec::new();
     
-    for i in 19..data.len() {
-        let window = &data[i-19..=i];
-        let sum: f64 = window.iter().map(|candle| candle.close).sum();
-        let ma = sum / 20.0;
+    for candle in candles {
+        let is_bullish = candle.close >= candle.open;
+        let color = if is_bullish {
+            egui::Color32::from_rgb(0, 255, 150)
+        } else {
+            egui::Color32::from_rgb(255, 80, 80)
+        };
         
-        // Calculate standard deviation
-        let variance: f64 = window.iter()
-            .map(|candle| {
-                let diff = candle.close - ma;
-                diff * diff
-            })
-            .sum::<f64>() / 20.0;
+        let box_spread = BoxSpread::new(
+            candle.low,
+            candle.open.min(candle.close),
+            (candle.open + candle.close) / 2.0,
+            candle.open.max(candle.close),
+            candle.high,
+        );
         
-        let std_dev = variance.sqrt();
-        let upper = ma + (2.0 * std_dev);
-        let lower = ma - (2.0 * std_dev);
+        let box_elem = BoxElem::new(candle.timestamp, box_spread)
+            .whisker_width(width * 0.1)
+            .box_width(width)
+            .fill(color)
+            .stroke(egui::Stroke::new(1.5, color));
         
-        let timestamp = data[i].timestamp;
-        upper_band.push((timestamp, upper));
-        middle_band.push((timestamp, ma));
-        lower_band.push((timestamp, lower));
+        box_elements.push(box_elem);
     }
     
-    (upper_band, middle_band, lower_band)
+    plot_ui.box_plot(BoxPlot::new(name, box_elements));
 }
 
-// Calculate MACD (12, 26, 9)
-fn calculate_macd(data: &[CandleData]) -> (Vec<(f64, f64)>, Vec<(f64, f64)>, Vec<(f64, f64)>) {
-    if data.len() < 26 {
-        return (Vec::new(), Vec::new(), Vec::new());
+// Draw one indicator series into a plot
+fn plot_series(plot_ui: &mut egui_plot::PlotUi, series: Series, bar_width: f64) {
+    if series.points.is_empty() {
+        return;
     }
     
-    let mut ema12 = Vec::new();
-    let mut ema26 = Vec::new();
-    let mut macd_line = Vec::new();
-    let mut signal_line = Vec::new();
-    let mut histogram = Vec::new();
-    
-    // Calculate EMA12 and EMA26
-    let alpha12 = 2.0 / (12.0 + 1.0);
-    let alpha26 = 2.0 / (26.0 + 1.0);
-    
-    let mut ema12_value = data[0].close;
-    let mut ema26_value = data[0].close;
-    
-    for (i, candle) in data.iter().enumerate() {
-        if i == 0 {
-            ema12.push(candle.close);
-            ema26.push(candle.close);
-        } else {
-            ema12_value = alpha12 * candle.close + (1.0 - alpha12) * ema12_value;
-            ema26_value = alpha26 * candle.close + (1.0 - alpha26) * ema26_value;
-            ema12.push(ema12_value);
-            ema26.push(ema26_value);
+    match series.style {
+        SeriesStyle::Line { width } => {
+            let points: PlotPoints = series.points.iter().map(|(t, v)| [*t, *v]).collect();
+            plot_ui.line(Line::new(series.name, points).color(series.color).width(width));
         }
-        
-        if i >= 25 {
-            let macd_value = ema12[i] - ema26[i];
-            macd_line.push((candle.timestamp, macd_value));
+        SeriesStyle::Histogram => {
+            let mut bars = Vec::new();
+            for (timestamp, value) in &series.points {
+                let color = if *value >= 0.0 {
+                    egui::Color32::from_rgb(0, 200, 100)
+                } else {
+                    egui::Color32::from_rgb(255, 100, 100)
+                };
+                
+                let box_spread = if *value >= 0.0 {
+                    BoxSpread::new(0.0, 0.0, value / 2.0, *value, *value)
+                } else {
+                    BoxSpread::new(*value, *value, value / 2.0, 0.0, 0.0)
+                };
+                
+                bars.push(BoxElem::new(*timestamp, box_spread)
+                    .box_width(bar_width)
+                    .fill(color)
+                    .stroke(egui::Stroke::new(1.0, color)));
+            }
+            
+            plot_ui.box_plot(BoxPlot::new(series.name, bars));
         }
     }
+}
+
+// Shade the area between two lines. Plot polygons must be convex, so each
+// step is its own quad, split into two triangles where the lines cross.
+fn plot_fill(plot_ui: &mut egui_plot::PlotUi, fill: Fill) {
+    let color = |difference: f64| if difference >= 0.0 { fill.above_color } else { fill.below_color };
+    let mut shape = |points: Vec<[f64; 2]>, color: egui::Color32| {
+        plot_ui.polygon(Polygon::new(fill.name.clone(), points).fill_color(color).stroke(egui::Stroke::NONE));
+    };
     
-    // Calculate Signal line (9-period EMA of MACD)
-    if !macd_line.is_empty() {
-        let alpha9 = 2.0 / (9.0 + 1.0);
-        let mut signal_value = macd_line[0].1;
+    for pair in fill.points.windows(2) {
+        let (t0, a0, b0) = pair[0];
+        let (t1, a1, b1) = pair[1];
+        let (d0, d1) = (a0 - b0, a1 - b1);
         
-        for (i, (timestamp, macd_val)) in macd_line.iter().enumerate() {
-            if i == 0 {
-                signal_line.push((*timestamp, *macd_val));
-                signal_value = *macd_val;
-            } else {
-                signal_value = alpha9 * macd_val + (1.0 - alpha9) * signal_value;
-                signal_line.push((*timestamp, signal_value));
-            }
-            
-            if i >= 8 {
-                let hist_value = macd_val - signal_line[i].1;
-                histogram.push((*timestamp, hist_value));
-            }
+        if d0 * d1 >= 0.0 {
+            shape(vec![[t0, a0], [t1, a1], [t1, b1], [t0, b0]], color(d0 + d1));
+        } else {
+            let fraction = d0 / (d0 - d1);
+            let crossing = [t0 + (t1 - t0) * fraction, a0 + (a1 - a0) * fraction];
+            shape(vec![[t0, a0], crossing, [t0, b0]], color(d0));
+            shape(vec![crossing, [t1, a1], [t1, b1]], color(d1));
         }
     }
-    
-    (macd_line, signal_line, histogram)
 }
 
-// Calculate RSI (14-period)
-fn calculate_rsi(data: &[CandleData]) -> Vec<(f64, f64)> {
-    if data.len() < 15 {
-        return Vec::new();
+// Whether two (start, size) x ranges match, allowing for rounding
+fn same_range(a: (f64, f64), b: (f64, f64)) -> bool {
+    let tolerance = a.1.abs().max(b.1.abs()) * 1e-9;
+    (a.0 - b.0).abs() <= tolerance && (a.1 - b.1).abs() <= tolerance
+}
+
+// Time axis settings shared by every pane. Each pane keeps its own x bounds
+// and the link copies them to the others, so dragging, pinching or wheeling
+// any pane moves them all. X auto bounds only apply until the first link.
+fn linked_plot(plot: Plot<'_>, view_window_start: f64, window_size: f64) -> Plot<'_> {
+    plot.allow_zoom([true, false])
+        .allow_drag([true, false])
+        .allow_scroll(false)
+        .allow_double_click_reset(false)
+        .default_x_bounds(view_window_start, view_window_start + window_size)
+        .link_axis(X_AXIS_GROUP, [true, false])
+        .link_cursor(CURSOR_GROUP, [true, false])
+}
+
+// The plain mouse wheel zooms the time axis around the pointer
+fn wheel_zoom(plot_ui: &mut egui_plot::PlotUi) {
+    let scroll = plot_ui.ctx().input(|input| input.smooth_scroll_delta.y);
+    if scroll == 0.0 || !plot_ui.response().hovered() {
+        return;
     }
+    if let Some(pointer) = plot_ui.pointer_coordinate() {
+        let bounds = plot_ui.plot_bounds();
+        let factor = (-scroll as f64 * 0.002).exp(); // Scrolling up narrows the window
+        let (min, max) = (bounds.min()[0], bounds.max()[0]);
+        plot_ui.set_plot_bounds_x(pointer.x - (pointer.x - min) * factor..=pointer.x + (max - pointer.x) * factor);
+    }
+}
+
+// One indicator in its own pane below the price chart, over the same x range.
+// Returns whether the pane is being dragged.
+fn draw_subpane(ui: &mut egui::Ui, instance: &IndicatorInstance, view_window_start: f64, window_size: f64, bar_width: f64) -> bool {
+    ui.label(format!("📈 {}", instance.label()));
+    let mut plot = linked_plot(Plot::new(("indicator", instance.id)), view_window_start, window_size);
+    plot = match instance.config.y_bounds() {
+        Some((min, max)) => plot.auto_bounds(egui::Vec2b::new(true, false)).default_y_bounds(min, max),
+        None => plot.auto_bounds(egui::Vec2b::new(true, true)),
+    };
     
-    let mut rsi_points = Vec::new();
-    let period = 14;
-    
-    for i in period..data.len() {
-        let mut gains = 0.0;
-        let mut losses = 0.0;
+    let response = plot.show(ui, |plot_ui| {
+        wheel_zoom(plot_ui);
+        let margin = window_size * 0.1;
+        for series in instance.visible_series(view_window_start - margin, view_window_start + window_size + margin) {
+            plot_series(plot_ui, series, bar_width);
+        }
         
-        for j in (i - period + 1)..=i {
-            let change = data[j].close - data[j - 1].close;
-            if change > 0.0 {
-                gains += change;
-            } else {
-                losses += change.abs();
+        // Reference levels such as RSI 30/50/70
+        for level in instance.config.levels() {
+            let points: PlotPoints = vec![[view_window_start, level], [view_window_start + window_size, level]].into();
+            plot_ui.line(Line::new(format!("{}", level), points)
+                .color(egui::Color32::from_rgb(128, 128, 128))
+                .width(1.0));
+        }
+    });
+    response.response.dragged()
+}
+
+// Drawing under the screen position `pointer` on the price chart, topmost
+// first, and whether an anchor handle or the outline was hit
+fn drawing_at(drawings: &[Drawing], transform: &PlotTransform, pointer: egui::Pos2) -> Option<(u64, Grab)> {
+    let to_screen = |anchor: Anchor| {
+        let position = transform.position_from_point(&PlotPoint::new(anchor.time, anchor.price));
+        (position.x as f64, position.y as f64)
+    };
+    let pointer = (pointer.x as f64, pointer.y as f64);
+    
+    drawings.iter().rev().find_map(|drawing| {
+        let handle = drawing.shape.anchors().into_iter().position(|anchor| {
+            let (x, y) = to_screen(anchor);
+            (x - pointer.0).hypot(y - pointer.1) <= DRAWING_HIT_DISTANCE
+        });
+        match handle {
+            Some(index) => Some((drawing.id, Grab::Anchor(index))),
+            None if drawing.shape.distance(pointer, to_screen) <= DRAWING_HIT_DISTANCE => Some((drawing.id, Grab::Body)),
+            None => None,
+        }
+    })
+}
+
+// One drawing on the price chart. Rays run to `right_edge`; a selected
+// drawing shows handles on its anchors.
+fn plot_drawing(plot_ui: &mut egui_plot::PlotUi, shape: &Shape, selected: bool, right_edge: f64) {
+    let color = if selected { egui::Color32::from_rgb(255, 215, 0) } else { egui::Color32::from_rgb(100, 180, 255) };
+    let segment = |name: String, from: [f64; 2], to: [f64; 2]| Line::new(name, vec![from, to]).color(color).width(1.5);
+    
+    match shape {
+        Shape::Trendline { start, end } => {
+            plot_ui.line(segment("Trendline".to_string(), [start.time, start.price], [end.time, end.price]));
+        }
+        Shape::HorizontalRay { start } => {
+            plot_ui.line(segment("Ray".to_string(), [start.time, start.price], [right_edge.max(start.time), start.price]));
+        }
+        Shape::Fibonacci { start, end } => {
+            let (left, right) = (start.time.min(end.time), start.time.max(end.time));
+            for (ratio, price) in drawings::fibonacci_levels(*start, *end) {
+                let name = format!("Fib {:.1}%", ratio * 100.0);
+                plot_ui.line(segment(name.clone(), [left, price], [right, price]));
+                plot_ui.text(Text::new(name.clone(), PlotPoint::new(left, price), format!("{} ({:.2})", name, price))
+                    .anchor(egui::Align2::LEFT_BOTTOM)
+                    .color(color));
             }
         }
-        
-        let avg_gain = gains / period as f64;
-        let avg_loss = losses / period as f64;
-        
-        let rs = if avg_loss != 0.0 { avg_gain / avg_loss } else { 100.0 };
-        let rsi = 100.0 - (100.0 / (1.0 + rs));
-        
-        rsi_points.push((data[i].timestamp, rsi));
+        Shape::Rectangle { start, end } => {
+            let corners = vec![
+                [start.time, start.price], [end.time, start.price],
+                [end.time, end.price], [start.time, end.price],
+            ];
+            plot_ui.polygon(Polygon::new("Rectangle", PlotPoints::from(corners))
+                .fill_color(color.gamma_multiply(0.15))
+                .stroke(egui::Stroke::new(1.5, color)));
+        }
     }
     
-    rsi_points
+    if selected {
+        let handles: Vec<[f64; 2]> = shape.anchors().iter().map(|anchor| [anchor.time, anchor.price]).collect();
+        plot_ui.points(Points::new("Handles", handles).radius(4.0).color(color));
+    }
+}
+
+// Crosshair tooltip for the candle under `t`: its time, OHLC, change from the
+// previous close, volume and the value of every indicator there
+fn crosshair_readout(candles: &VecDeque<CandleData>, timeframe: &Timeframe, indicators: &[IndicatorInstance], t: f64) -> String {
+    let format_time = |t: f64| chrono::DateTime::from_timestamp(t as i64, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default();
+    let index = candles.partition_point(|candle| candle.timestamp <= t);
+    let candle = match index.checked_sub(1).map(|i| &candles[i]) {
+        Some(candle) if t < timeframe.candle_close(candle.timestamp) => candle,
+        _ => return format_time(t),
+    };
+    
+    let previous_close = if index >= 2 { candles[index - 2].close } else { candle.open };
+    let change = (candle.close - previous_close) / previous_close * 100.0;
+    let mut lines = vec![
+        format_time(candle.timestamp),
+        format!("O {:.2}  H {:.2}  L {:.2}  C {:.2}", candle.open, candle.high, candle.low, candle.close),
+        format!("Change {:+.2}%  Volume {:.2}", change, candle.volume),
+    ];
+    for instance in indicators {
+        for (name, value) in instance.values_at(candle.timestamp) {
+            lines.push(format!("{}: {:.2}", name, value));
+        }
+    }
+    lines.join("\n")
+}
+
+// Enough decimals to tell neighbouring price levels apart, even for coins
+// quoted far below a dollar
+fn price_text(price: f64) -> String {
+    if price.abs() >= 1.0 || price == 0.0 {
+        format!("{:.2}", price)
+    } else {
+        let decimals = (-price.abs().log10()).ceil() as usize + 3;
+        format!("{:.*}", decimals, price)
+    }
+}
+
+// Timeframe an indicator is computed on; None follows the chart. Returns
+// whether the selection changed.
+fn timeframe_combo(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, timeframe: &mut Option<Timeframe>) -> bool {
+    let before = timeframe.clone();
+    egui::ComboBox::from_id_salt(id_salt)
+        .width(60.0)
+        .selected_text(timeframe.as_ref().map_or("Chart".to_string(), |timeframe| timeframe.to_display_string()))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(timeframe, None, "Chart");
+            for option in ALL_TIMEFRAMES.iter().chain(CUSTOM_TIMEFRAMES.iter()) {
+                ui.selectable_value(timeframe, Some(option.clone()), option.to_display_string());
+            }
+        });
+    *timeframe != before
+}
+
+// Editor for an indicator's typed parameters. Times can be set to `view_start`.
+fn indicator_params_ui(ui: &mut egui::Ui, config: &mut IndicatorConfig, view_start: f64) {
+    for param in config.params_mut() {
+        match param {
+            Param::Period(label, value) => {
+                ui.label(label);
+                ui.add(egui::DragValue::new(value).range(1..=500));
+            }
+            Param::Factor(label, value) => {
+                ui.label(label);
+                ui.add(egui::DragValue::new(value).range(0.1..=10.0).speed(0.1));
+            }
+            Param::Time(label, value) => {
+                ui.label(label);
+                let time = chrono::DateTime::from_timestamp(*value as i64, 0)
+                    .filter(|_| *value > 0.0)
+                    .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+                    .unwrap_or_else(|| "First candle".to_string());
+                ui.label(time);
+                if ui.small_button("⇤").on_hover_text("Anchor at the left edge of the chart").clicked() {
+                    *value = view_start;
+                }
+            }
+        }
+    }
 }
 
 impl eframe::App for CryptoApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Check for new data
+        let mut price_updated = false;
         if !self.is_dragging {
+            let live_buffer = self.live_buffer();
             if let Some(receiver) = &mut self.data_receiver {
-                while let Ok(new_candles) = receiver.try_recv() {
+                while let Ok(event) = receiver.try_recv() {
+                    self.health.apply(&event);
+                    let new_candles = match event {
+                        feed::FeedEvent::Candles(candles) => candles,
+                        _ => continue,
+                    };
+                    
                     if !new_candles.is_empty() {
                         self.is_loading = false;
                         
                         if let Some(latest) = new_candles.last() {
                             self.latest_timestamp = latest.timestamp;
                             self.trading_panel.current_price = latest.close;
+                            price_updated = true;
                             
                             if self.view_window_start == 0.0 {
                                 self.view_window_start = self.latest_timestamp - self.window_size;
                                 self.is_live_mode = true;
                             } else if self.is_live_mode {
-                                let buffer = match self.timeframe {
-                                    Timeframe::M1 => 60.0 * 5.0,
-                                    Timeframe::M3 => 60.0 * 15.0,
-                                    Timeframe::M5 => 60.0 * 25.0,
-                                    Timeframe::M15 => 60.0 * 75.0,
-                                    Timeframe::M30 => 60.0 * 150.0,
-                                    Timeframe::H1 => 60.0 * 60.0 * 5.0,
-                                    Timeframe::H4 => 60.0 * 60.0 * 20.0,
-                                    _ => 60.0 * 60.0 * 24.0 * 5.0,
-                                };
-                                self.view_window_start = self.latest_timestamp + buffer - self.window_size;
+                                self.view_window_start = self.latest_timestamp + live_buffer - self.window_size;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        
+        // Keep indicator values in step with the candles they are computed from
+        self.sync_timeframe_series();
+        if let Ok(data) = self.candle_data.lock() {
+            let transformed = match self.indicators_on_chart {
+                true => self.transformed.get(&self.chart_type, self.renko_box, &data),
+                false => None,
+            };
+            let source = transformed.map(|_| self.chart_type.clone());
+            if source != self.indicator_source {
+                for instance in &mut self.indicators {
+                    instance.reset();
+                }
+                self.indicator_source = source;
+            }
+            
+            for instance in &mut self.indicators {
+                match instance.timeframe().cloned() {
+                    Some(timeframe) if timeframe != self.timeframe => {
+                        match self.timeframe_series.iter().find(|series| series.timeframe == timeframe) {
+                            Some(TimeframeSeries { aggregate: Some(aggregate), .. }) => instance.sync(aggregate.candles()),
+                            Some(series) => {
+                                if let Ok(candles) = series.candle_data.lock() {
+                                    instance.sync(&candles);
+                                }
                             }
+                            None => {}
+                        }
+                    }
+                    Some(_) => instance.sync(&data),
+                    None => instance.sync(transformed.unwrap_or(&data)),
+                }
+            }
+        }
+        
+        if price_updated {
+            self.check_alerts(ctx);
+        }
+        while let Ok(error) = self.alert_errors.1.try_recv() {
+            self.alert_error = Some(error);
+            self.alert_log_open = true;
+        }
+        
+        if let Some(receiver) = &mut self.trade_receiver {
+            while let Ok(event) = receiver.try_recv() {
+                match event {
+                    feed::TradeEvent::Trade(trade) => self.trade_tape.push(trade),
+                    feed::TradeEvent::Error(e) => self.tape_error = Some(e),
+                }
+            }
+        }
+        
+        if let Some(receiver) = &mut self.depth_receiver {
+            while let Ok(event) = receiver.try_recv() {
+                match event {
+                    feed::DepthEvent::Book(book) => {
+                        self.order_book = Some(book);
+                        self.order_book_error = None;
+                    }
+                    feed::DepthEvent::Error(e) => self.order_book_error = Some(e),
+                }
+            }
+        }
+        
+        while let Ok(event) = self.account_receiver.try_recv() {
+            match event {
+                AccountEvent::Balances(balances) => self.trading_panel.exchange_balances = Some(balances),
+                AccountEvent::OrderResult(result) => self.trading_panel.order_status = Some(result),
+            }
+        }
+        
+        if let Some(receiver) = &mut self.symbols_receiver {
+            if let Ok(top_symbols) = receiver.try_recv() {
+                exchange::merge_symbols(&mut self.symbols, top_symbols);
+                self.symbols_receiver = None;
+            }
+        }
+        
+        let base_asset = exchange::base_asset(&self.symbol).to_string();
+        
+        // Top controls
+        egui::TopBottomPanel::top("control_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                // Symbol and timeframe are fixed by the recording in replay mode
+                let is_replay = self.replay.is_some();
+                
+                if let Some(market) = self.market_type {
+                    ui.label("Exchange:");
+                    let mut selected_exchange = self.exchange;
+                    egui::ComboBox::from_id_salt("exchange")
+                        .selected_text(selected_exchange.to_display_string())
+                        .show_ui(ui, |ui| {
+                            for option in [ExchangeKind::Binance, ExchangeKind::Bybit] {
+                                ui.selectable_value(&mut selected_exchange, option, option.to_display_string());
+                            }
+                        });
+                    if selected_exchange != self.exchange {
+                        self.switch_venue(selected_exchange, market);
+                    }
+                    
+                    ui.label("Market:");
+                    let mut selected = market;
+                    egui::ComboBox::from_id_salt("market_type")
+                        .selected_text(selected.to_display_string())
+                        .show_ui(ui, |ui| {
+                            for option in [MarketType::Futures, MarketType::Spot] {
+                                ui.selectable_value(&mut selected, option, option.to_display_string());
+                            }
+                        });
+                    if selected != market {
+                        self.switch_venue(self.exchange, selected);
+                    }
+                    
+                    ui.separator();
+                }
+                
+                ui.label("Symbol:");
+                let old_symbol = self.symbol.clone();
+                ui.add_enabled_ui(!is_replay, |ui| {
+                    egui::ComboBox::from_id_salt("symbol")
+                        .selected_text(self.symbol.as_str())
+                        .show_ui(ui, |ui| {
+                            for symbol in &self.symbols {
+                                ui.selectable_value(&mut self.symbol, symbol.clone(), symbol.as_str());
+                            }
+                        });
+                });
+                
+                if old_symbol != self.symbol {
+                    self.trading_panel.current_price = 0.0;
+                    self.load_drawings();
+                    self.restart_data_feed();
+                    self.restart_trade_feed();
+                    self.restart_depth_feed();
+                }
+                
+                ui.separator();
+                
+                ui.label("Timeframe:");
+                let old_timeframe = self.timeframe.clone();
+                ui.add_enabled_ui(!is_replay, |ui| egui::ComboBox::from_id_salt("timeframe")
+                    .selected_text(self.timeframe.to_display_string())
+                    .show_ui(ui, |ui| {
+                        for option in ALL_TIMEFRAMES.iter() {
+                            ui.selectable_value(&mut self.timeframe, option.clone(), option.to_display_string());
+                        }
+                        ui.separator();
+                        for option in CUSTOM_TIMEFRAMES.iter() {
+                            ui.selectable_value(&mut self.timeframe, option.clone(), option.to_display_string());
+                        }
+                    }));
+                
+                if old_timeframe != self.timeframe {
+                    self.window_size = self.zoom_levels.get(&self.timeframe).copied().unwrap_or_else(|| self.timeframe.get_window_size());
+                    self.restart_data_feed();
+                }
+                
+                ui.separator();
+                
+                ui.label("Chart:");
+                egui::ComboBox::from_id_salt("chart_type")
+                    .selected_text(self.chart_type.to_display_string())
+                    .show_ui(ui, |ui| {
+                        for chart_type in [ChartType::Line, ChartType::Candlestick, ChartType::HeikinAshi, ChartType::OhlcBars, ChartType::Renko] {
+                            let label = chart_type.to_display_string();
+                            ui.selectable_value(&mut self.chart_type, chart_type, label);
+                        }
+                    });
+                
+                if self.chart_type == ChartType::Renko {
+                    let mut atr_based = matches!(self.renko_box, transform::BoxSize::Atr(_));
+                    if ui.checkbox(&mut atr_based, "ATR box").changed() {
+                        self.renko_box = if atr_based {
+                            transform::BoxSize::Atr(14)
+                        } else {
+                            transform::BoxSize::Fixed((self.trading_panel.current_price * 0.001).max(0.01))
+                        };
+                    }
+                    match &mut self.renko_box {
+                        transform::BoxSize::Atr(period) => {
+                            ui.add(egui::DragValue::new(period).range(1..=200).prefix("ATR "));
+                        }
+                        transform::BoxSize::Fixed(size) => {
+                            let speed = *size * 0.01;
+                            ui.add(egui::DragValue::new(size).range(0.00000001..=f64::MAX).speed(speed).prefix("Box "));
+                        }
+                    }
+                }
+                if matches!(self.chart_type, ChartType::HeikinAshi | ChartType::Renko) {
+                    ui.checkbox(&mut self.indicators_on_chart, "Indicators on chart prices");
+                }
+                
+                ui.separator();
+                
+                if ui.button(format!("📈 Indicators ({})", self.indicators.len())).clicked() {
+                    self.indicator_dialog_open = !self.indicator_dialog_open;
+                }
+                let active_alerts = self.alerts.alerts.iter().filter(|alert| alert.active).count();
+                if ui.button(format!("🔔 Alerts ({})", active_alerts)).clicked() {
+                    self.alert_log_open = !self.alert_log_open;
+                }
+                ui.checkbox(&mut self.show_volume, "Volume");
+                ui.checkbox(&mut self.show_volume_profile, "Profile").on_hover_text("Volume by price for the visible candles");
+                ui.checkbox(&mut self.show_order_book, "Book");
+                ui.checkbox(&mut self.show_tape, "Tape").on_hover_text("Time & sales");
+                
+                ui.separator();
+                
+                if let Some(replay) = &self.replay {
+                    ui.label("Replay:");
+                    
+                    let paused = replay.control.is_paused();
+                    if ui.button(if paused { "▶ Play" } else { "⏸ Pause" }).clicked() {
+                        replay.control.set_paused(!paused);
+                    }
+                    
+                    if ui.add_enabled(paused, egui::Button::new("Step")).clicked() {
+                        replay.control.step();
+                    }
+                    
+                    let mut speed = replay.control.speed();
+                    egui::ComboBox::from_id_salt("replay_speed")
+                        .selected_text(speed.to_display_string())
+                        .show_ui(ui, |ui| {
+                            for option in [ReplaySpeed::X1, ReplaySpeed::X10, ReplaySpeed::Max] {
+                                ui.selectable_value(&mut speed, option, option.to_display_string());
+                            }
+                        });
+                    if speed != replay.control.speed() {
+                        replay.control.set_speed(speed);
+                    }
+                    
+                    ui.separator();
+                }
+                
+                if ui.button("Live").clicked() {
+                    self.view_window_start = self.latest_timestamp + self.live_buffer() - self.window_size;
+                    self.is_live_mode = true;
+                }
+                
+                ui.separator();
+                
+                if !self.trading_panel.current_price.is_nan() && self.trading_panel.current_price > 0.0 {
+                    ui.colored_label(egui::Color32::WHITE, format!("Price: ${:.2}", self.trading_panel.current_price));
+                }
+                
+                // Most recent print, colored by the taker's side
+                if let Some(trade) = self.trade_tape.latest().next() {
+                    let color = if trade.is_buyer_maker { egui::Color32::from_rgb(255, 100, 100) } else { egui::Color32::from_rgb(0, 200, 100) };
+                    ui.colored_label(color, format!("Last: {:.2} × {}", trade.price, trade.quantity));
+                }
+                
+                if self.is_loading {
+                    ui.colored_label(egui::Color32::YELLOW, "Loading...");
+                } else {
+                    if self.replay.is_some() {
+                        ui.colored_label(egui::Color32::GOLD, "⏪ REPLAY");
+                    } else if self.is_live_mode {
+                        let color = if self.health.is_connected() { egui::Color32::GREEN } else { egui::Color32::GRAY };
+                        ui.colored_label(color, "🔴 LIVE");
+                    } else {
+                        ui.colored_label(egui::Color32::LIGHT_BLUE, "📜 History");
+                    }
+                    if self.history_loading {
+                        ui.colored_label(egui::Color32::YELLOW, "Loading history...");
+                    }
+                }
+                
+                // Connection health; hover for the last error
+                if self.replay.is_none() {
+                    let retry_in = self.health.retry_in().as_secs_f64().ceil();
+                    let (color, text) = match &self.health.state {
+                        feed::ConnectionState::Connecting => (egui::Color32::YELLOW, "Connecting...".to_string()),
+                        feed::ConnectionState::Connected => match self.health.last_update_age() {
+                            Some(age) => (egui::Color32::GREEN, format!("● Connected, updated {}s ago", age.as_secs())),
+                            None => (egui::Color32::GREEN, "● Connected".to_string()),
+                        },
+                        feed::ConnectionState::Retrying { attempt, .. } => (egui::Color32::YELLOW, format!("↻ Retry #{} in {}s", attempt, retry_in)),
+                        feed::ConnectionState::RateLimited { .. } => (egui::Color32::from_rgb(255, 165, 0), format!("⏳ Rate limited, retry in {}s", retry_in)),
+                        feed::ConnectionState::Offline { .. } => (egui::Color32::from_rgb(255, 100, 100), format!("✖ Offline, retry in {}s", retry_in)),
+                    };
+                    let response = ui.colored_label(color, text);
+                    
+                    if let Some(error) = &self.health.last_error {
+                        response.on_hover_text(error);
+                        if !self.health.is_connected() {
+                            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠ {}", error));
                         }
                     }
                 }
-            }
-        }
-        
-        // Top controls
-        egui::TopBottomPanel::top("control_panel").show(ctx, |ui| {
+            });
+            
+            // Drawing tools
             ui.horizontal(|ui| {
-                ui.label("Timeframe:");
-                let old_timeframe = self.timeframe.clone();
-                egui::ComboBox::from_id_salt("timeframe")
-                    .selected_text(self.timeframe.to_display_string())
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut self.timeframe, Timeframe::M1, "1m");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::M3, "3m");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::M5, "5m");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::M15, "15m");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::M30, "30m");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::H1, "1h");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::H4, "4h");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::H12, "12h");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::D1, "1d");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::W1, "1w");
-                        ui.selectable_value(&mut self.timeframe, Timeframe::MN1, "1M");
-                    });
+                ui.label("Draw:");
+                for tool in Tool::ALL {
+                    let active = self.drawing_tool == Some(tool);
+                    if ui.selectable_label(active, tool.to_display_string()).clicked() {
+                        self.drawing_tool = if active { None } else { Some(tool) };
+                        self.pending_anchors.clear();
+                    }
+                }
+                if ui.add_enabled(self.selected_drawing.is_some(), egui::Button::new("🗑 Delete")).clicked() {
+                    self.delete_selected_drawing();
+                }
                 
-                if old_timeframe != self.timeframe {
-                    self.window_size = self.timeframe.get_window_size();
-                    self.is_loading = true;
-                    
-                    if let Ok(mut data) = self.candle_data.lock() {
-                        data.clear();
+                match self.drawing_tool {
+                    Some(tool) => {
+                        let remaining = tool.anchor_count() - self.pending_anchors.len();
+                        ui.label(format!("Click {} more point{} on the chart, Esc to cancel", remaining, if remaining == 1 { "" } else { "s" }));
                     }
-                    
-                    if let Some(rt) = &self.runtime {
-                        let (tx, rx) = mpsc::unbounded_channel();
-                        self.data_receiver = Some(rx);
-                        
-                        let candle_data_clone = self.candle_data.clone();
-                        let timeframe_clone = self.timeframe.clone();
-                        rt.spawn(fetch_binance_data(tx, candle_data_clone, timeframe_clone));
+                    None if self.selected_drawing.is_some() => {
+                        ui.label("Drag the drawing or its handles to edit, Del to delete");
                     }
-                    
-                    self.view_window_start = 0.0;
-                    self.is_live_mode = true;
+                    None => {}
                 }
-                
-                ui.separator();
-                
-                ui.label("Chart:");
-                egui::ComboBox::from_id_salt("chart_type")
-                    .selected_text(match self.chart_type {
-                        ChartType::Line => "Line",
-                        ChartType::Candlestick => "Candle",
-                    })
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut self.chart_type, ChartType::Line, "Line");
-                        ui.selectable_value(&mut self.chart_type, ChartType::Candlestick, "Candle");
+            });
+        });
+        
+        // Drawing shortcuts, unless a text field has focus
+        if ctx.memory(|memory| memory.focused().is_none()) {
+            if ctx.input(|input| input.key_pressed(egui::Key::Escape)) {
+                self.drawing_tool = None;
+                self.pending_anchors.clear();
+                self.selected_drawing = None;
+            }
+            if ctx.input(|input| input.key_pressed(egui::Key::Delete) || input.key_pressed(egui::Key::Backspace)) {
+                self.delete_selected_drawing();
+            }
+        }
+        
+        // Add/remove indicator dialog
+        let mut dialog_open = self.indicator_dialog_open;
+        egui::Window::new("Indicators")
+            .open(&mut dialog_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let view_start = self.view_window_start;
+                let mut removed = None;
+                for instance in &mut self.indicators {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(instance.color, "■");
+                        ui.label(instance.config.kind_name());
+                        indicator_params_ui(ui, &mut instance.config, view_start);
+                        let mut timeframe = instance.timeframe().cloned();
+                        if timeframe_combo(ui, ("indicator_timeframe", instance.id), &mut timeframe) {
+                            instance.set_timeframe(timeframe);
+                        }
+                        if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                            removed = Some(instance.id);
+                        }
                     });
+                }
+                if let Some(id) = removed {
+                    self.indicators.retain(|instance| instance.id != id);
+                }
+                if self.indicators.is_empty() {
+                    ui.label("No indicators");
+                }
                 
                 ui.separator();
                 
-                ui.label("Indicators:");
                 ui.horizontal(|ui| {
-                    ui.checkbox(&mut self.show_ma20, "MA20");
-                    ui.checkbox(&mut self.show_bollinger, "Bollinger");
-                    ui.checkbox(&mut self.show_macd, "MACD");
-                    ui.checkbox(&mut self.show_rsi, "RSI");
-                    ui.checkbox(&mut self.show_volume, "Volume");
+                    egui::ComboBox::from_id_salt("new_indicator")
+                        .selected_text(self.new_indicator.kind_name())
+                        .show_ui(ui, |ui| {
+                            for option in indicators::registry() {
+                                let selected = option.kind_name() == self.new_indicator.kind_name();
+                                if ui.selectable_label(selected, option.kind_name()).clicked() {
+                                    self.new_indicator = option;
+                                }
+                            }
+                        });
+                    indicator_params_ui(ui, &mut self.new_indicator, view_start);
+                    timeframe_combo(ui, "new_indicator_timeframe", &mut self.new_indicator_timeframe);
+                    if ui.button("➕ Add").clicked() {
+                        self.add_indicator(self.new_indicator.clone(), self.new_indicator_timeframe.clone());
+                    }
                 });
+            });
+        self.indicator_dialog_open = dialog_open;
+        
+        // Alerts and the log of those that triggered
+        let mut alert_log_open = self.alert_log_open;
+        egui::Window::new("🔔 Alerts")
+            .open(&mut alert_log_open)
+            .default_width(380.0)
+            .show(ctx, |ui| {
+                let mut removed = None;
+                for alert in &mut self.alerts.alerts {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut alert.active, "").on_hover_text("Armed");
+                        ui.label(format!("{}: {}", alert.symbol, alert.condition.describe()));
+                        if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                            removed = Some(alert.id);
+                        }
+                    });
+                }
+                if let Some(id) = removed {
+                    self.alerts.remove(id);
+                }
+                if self.alerts.alerts.is_empty() {
+                    ui.label("No alerts. Add one from the trading panel or by right-clicking the chart.");
+                }
                 
                 ui.separator();
                 
-                if ui.button("Live").clicked() {
-                    let buffer = match self.timeframe {
-                        Timeframe::M1 => 60.0 * 5.0,
-                        Timeframe::M3 => 60.0 * 15.0,
-                        Timeframe::M5 => 60.0 * 25.0,
-                        Timeframe::M15 => 60.0 * 75.0,
-                        Timeframe::M30 => 60.0 * 150.0,
-                        Timeframe::H1 => 60.0 * 60.0 * 5.0,
-                        Timeframe::H4 => 60.0 * 60.0 * 20.0,
-                        _ => 60.0 * 60.0 * 24.0 * 5.0,
-                    };
-                    self.view_window_start = self.latest_timestamp + buffer - self.window_size;
-                    self.is_live_mode = true;
+                ui.horizontal(|ui| {
+                    ui.label("Run on trigger:");
+                    ui.text_edit_singleline(&mut self.alert_command)
+                        .on_hover_text("Shell command, with ALERT_SYMBOL, ALERT_MESSAGE and ALERT_PRICE set");
+                });
+                if let Some(error) = &self.alert_error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠ {}", error));
                 }
+                ui.checkbox(&mut self.alert_notify, "Desktop notification with sound");
                 
                 ui.separator();
                 
-                if !self.trading_panel.current_price.is_nan() && self.trading_panel.current_price > 0.0 {
-                    ui.colored_label(egui::Color32::WHITE, format!("Price: ${:.2}", self.trading_panel.current_price));
-                }
-                
-                if self.is_loading {
-                    ui.colored_label(egui::Color32::YELLOW, "Loading...");
-                } else {
-                    if self.is_live_mode {
-                        ui.colored_label(egui::Color32::GREEN, "🔴 LIVE");
-                    } else {
-                        ui.colored_label(egui::Color32::LIGHT_BLUE, "📜 History");
+                ui.label("Triggered:");
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    for event in self.alerts.log.iter().rev() {
+                        let time = chrono::DateTime::from_timestamp(event.time as i64, 0)
+                            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+                            .unwrap_or_default();
+                        ui.label(format!("{}  {}: {} at {:.2}", time, event.symbol, event.message, event.price));
                     }
-                }
+                    if self.alerts.log.is_empty() {
+                        ui.label("Nothing yet");
+                    }
+                });
             });
-        });
+        self.alert_log_open = alert_log_open;
+        
+        // Triggered alerts pop up over the chart for a while
+        self.alert_toasts.retain(|(_, shown)| shown.elapsed() < std::time::Duration::from_secs(10));
+        if !self.alert_toasts.is_empty() {
+            let mut dismissed = false;
+            egui::Area::new(egui::Id::new("alert_toasts"))
+                .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        for (event, _) in &self.alert_toasts {
+                            ui.colored_label(egui::Color32::GOLD, format!("🔔 {}: {} at {:.2}", event.symbol, event.message, event.price));
+                        }
+                        dismissed = ui.small_button("Dismiss").clicked();
+                    });
+                });
+            if dismissed {
+                self.alert_toasts.clear();
+            }
+        }
         
         // Main layout with side panel for trading
         egui::SidePanel::right("trading_panel").min_width(300.0).show(ctx, |ui| {
             ui.heading("💰 Trading");
             ui.separator();
             
+            // Live spot trading needs API keys; otherwise orders stay virtual
+            let account_name = self.account.as_ref().map(|account| account.name().to_string());
+            if let (Some(MarketType::Spot), Some(account_name)) = (self.market_type, &account_name) {
+                ui.checkbox(&mut self.trading_panel.live_orders, format!("Live orders ({})", account_name));
+                if self.trading_panel.live_orders {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), "⚠ Orders are sent to your account");
+                }
+                ui.separator();
+            }
+            
             // Balance display
             ui.group(|ui| {
-                ui.label("💳 Balance");
-                ui.label(format!("USDT: ${:.2}", self.trading_panel.balance_usdt));
-                ui.label(format!("BTC: {:.6}", self.trading_panel.balance_btc));
+                if let (true, Some(account_name)) = (self.trading_panel.live_orders, &account_name) {
+                    ui.label(format!("💳 Balance ({})", account_name));
+                    if self.trading_panel.exchange_balances.is_none() {
+                        ui.label("Loading...");
+                    }
+                } else {
+                    ui.label("💳 Balance");
+                }
+                ui.label(format!("USDT: ${:.2}", self.trading_panel.quote_balance()));
+                ui.label(format!("{}: {:.6}", base_asset, self.trading_panel.base_balance(&base_asset)));
             });
             
             ui.separator();
@@ -623,7 +1889,7 @@ impl eframe::App for CryptoApp {
             ui.horizontal(|ui| {
                 ui.label("Quantity:");
                 ui.text_edit_singleline(&mut self.trading_panel.quantity);
-                ui.label("BTC");
+                ui.label(base_asset.as_str());
             });
             
             // Price input (only for limit orders)
@@ -661,8 +1927,9 @@ impl eframe::App for CryptoApp {
             if ui.add_sized([ui.available_width(), 40.0], 
                 egui::Button::new(button_text).fill(button_color)
             ).clicked() {
-                // Order processing (virtual trading)
-                if let Ok(quantity) = self.trading_panel.quantity.parse::<f64>() {
+                if self.trading_panel.live_orders {
+                    self.submit_live_order();
+                } else if let Ok(quantity) = self.trading_panel.quantity.parse::<f64>() {
                     let price = if self.trading_panel.order_mode == OrderMode::Market {
                         self.trading_panel.current_price
                     } else {
@@ -675,12 +1942,12 @@ impl eframe::App for CryptoApp {
                                 let total_cost = price * quantity;
                                 if total_cost <= self.trading_panel.balance_usdt {
                                     self.trading_panel.balance_usdt -= total_cost;
-                                    self.trading_panel.balance_btc += quantity;
+                                    *self.trading_panel.base_balance_mut(&base_asset) += quantity;
                                 }
                             },
                             OrderType::Sell => {
-                                if quantity <= self.trading_panel.balance_btc {
-                                    self.trading_panel.balance_btc -= quantity;
+                                if quantity <= self.trading_panel.base_balance(&base_asset) {
+                                    *self.trading_panel.base_balance_mut(&base_asset) -= quantity;
                                     self.trading_panel.balance_usdt += price * quantity;
                                 }
                             }
@@ -689,6 +1956,16 @@ impl eframe::App for CryptoApp {
                 }
             }
             
+            match &self.trading_panel.order_status {
+                Some(Ok(message)) => {
+                    ui.colored_label(egui::Color32::GREEN, message);
+                }
+                Some(Err(message)) => {
+                    ui.colored_label(egui::Color32::RED, message);
+                }
+                None => {}
+            }
+            
             ui.separator();
             
             // Quick order buttons
@@ -698,12 +1975,12 @@ impl eframe::App for CryptoApp {
                     match self.trading_panel.order_type {
                         OrderType::Buy => {
                             if self.trading_panel.current_price > 0.0 {
-                                let amount = (self.trading_panel.balance_usdt * 0.25) / self.trading_panel.current_price;
+                                let amount = (self.trading_panel.quote_balance() * 0.25) / self.trading_panel.current_price;
                                 self.trading_panel.quantity = format!("{:.6}", amount);
                             }
                         },
                         OrderType::Sell => {
-                            let amount = self.trading_panel.balance_btc * 0.25;
+                            let amount = self.trading_panel.base_balance(&base_asset) * 0.25;
                             self.trading_panel.quantity = format!("{:.6}", amount);
                         }
                     }
@@ -712,12 +1989,12 @@ impl eframe::App for CryptoApp {
                     match self.trading_panel.order_type {
                         OrderType::Buy => {
                             if self.trading_panel.current_price > 0.0 {
-                                let amount = (self.trading_panel.balance_usdt * 0.5) / self.trading_panel.current_price;
+                                let amount = (self.trading_panel.quote_balance() * 0.5) / self.trading_panel.current_price;
                                 self.trading_panel.quantity = format!("{:.6}", amount);
                             }
                         },
                         OrderType::Sell => {
-                            let amount = self.trading_panel.balance_btc * 0.5;
+                            let amount = self.trading_panel.base_balance(&base_asset) * 0.5;
                             self.trading_panel.quantity = format!("{:.6}", amount);
                         }
                     }
@@ -726,21 +2003,227 @@ impl eframe::App for CryptoApp {
                     match self.trading_panel.order_type {
                         OrderType::Buy => {
                             if self.trading_panel.current_price > 0.0 {
-                                let amount = self.trading_panel.balance_usdt / self.trading_panel.current_price;
+                                let amount = self.trading_panel.quote_balance() / self.trading_panel.current_price;
                                 self.trading_panel.quantity = format!("{:.6}", amount);
                             }
                         },
                         OrderType::Sell => {
-                            self.trading_panel.quantity = format!("{:.6}", self.trading_panel.balance_btc);
+                            self.trading_panel.quantity = format!("{:.6}", self.trading_panel.base_balance(&base_asset));
+                        }
+                    }
+                }
+            });
+            
+            ui.separator();
+            
+            // New alert for the current symbol
+            ui.label("🔔 Alert:");
+            let current_price = self.trading_panel.current_price;
+            ui.horizontal(|ui| {
+                let options = [
+                    ("Price", Condition::PriceCross { level: current_price }),
+                    ("% Move", Condition::PercentMove { percent: 2.0, minutes: 5 }),
+                    ("Indicator", Condition::Indicator { config: IndicatorConfig::Rsi { period: 14 }, comparison: Comparison::Above, threshold: 70.0 }),
+                ];
+                for (label, option) in options {
+                    let selected = std::mem::discriminant(&self.new_alert) == std::mem::discriminant(&option);
+                    if ui.selectable_label(selected, label).clicked() && !selected {
+                        self.new_alert = option;
+                    }
+                }
+            });
+            if let Condition::PriceCross { level } = &mut self.new_alert {
+                if *level == 0.0 {
+                    *level = current_price; // Start from the price once it is known
+                }
+            }
+            ui.horizontal_wrapped(|ui| {
+                match &mut self.new_alert {
+                    Condition::PriceCross { level } => {
+                        ui.label("Crosses");
+                        let speed = (*level * 0.0005).max(0.01);
+                        ui.add(egui::DragValue::new(level).speed(speed).max_decimals(2));
+                        if ui.small_button("Now").on_hover_text("Use the current price").clicked() {
+                            *level = current_price;
                         }
                     }
+                    Condition::PercentMove { percent, minutes } => {
+                        ui.label("Moves");
+                        ui.add(egui::DragValue::new(percent).range(0.1..=100.0).speed(0.1).suffix("%"));
+                        ui.label("within");
+                        ui.add(egui::DragValue::new(minutes).range(1..=1440).suffix(" min"));
+                    }
+                    Condition::Indicator { config, comparison, threshold } => {
+                        egui::ComboBox::from_id_salt("alert_indicator")
+                            .selected_text(config.kind_name())
+                            .show_ui(ui, |ui| {
+                                for option in indicators::registry() {
+                                    let selected = option.kind_name() == config.kind_name();
+                                    if ui.selectable_label(selected, option.kind_name()).clicked() {
+                                        *config = option;
+                                    }
+                                }
+                            });
+                        indicator_params_ui(ui, config, self.view_window_start);
+                        ui.selectable_value(comparison, Comparison::Above, "Above");
+                        ui.selectable_value(comparison, Comparison::Below, "Below");
+                        ui.add(egui::DragValue::new(threshold).speed(0.1).max_decimals(2));
+                    }
                 }
             });
+            if ui.button("➕ Add Alert").clicked() {
+                self.alerts.add(&self.symbol, self.new_alert.clone());
+            }
         });
         
+        // Order book ladder and depth chart, left of the trading panel
+        if self.show_order_book {
+            egui::SidePanel::right("order_book_panel").min_width(220.0).show(ctx, |ui| {
+                ui.heading("📖 Order Book");
+                ui.separator();
+                
+                if let Some(error) = &self.order_book_error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠ {}", error));
+                }
+                let Some(book) = &self.order_book else {
+                    if self.order_book_error.is_none() {
+                        ui.label("Waiting for the order book...");
+                    }
+                    return;
+                };
+                if let Some(spread) = book.spread() {
+                    ui.label(format!("Spread: {}", price_text(spread)));
+                }
+                
+                // Asks above bids, best prices meeting in the middle. Clicking
+                // a level makes it the limit price.
+                let asks = &book.asks[..book.asks.len().min(LADDER_ROWS)];
+                let bids = &book.bids[..book.bids.len().min(LADDER_ROWS)];
+                let mut clicked = None;
+                egui::Grid::new("order_book_ladder").num_columns(3).striped(true).show(ui, |ui| {
+                    ui.label("Price");
+                    ui.label("Size");
+                    ui.label("Total");
+                    ui.end_row();
+                    
+                    let ask_rows = asks.iter().zip(orderbook::cumulative(asks)).rev();
+                    let bid_rows = bids.iter().zip(orderbook::cumulative(bids));
+                    let rows = ask_rows
+                        .map(|row| (row, egui::Color32::from_rgb(255, 100, 100)))
+                        .chain(bid_rows.map(|row| (row, egui::Color32::from_rgb(0, 200, 100))));
+                    for ((level, (_, total)), color) in rows {
+                        let text = egui::RichText::new(price_text(level.price)).color(color);
+                        if ui.selectable_label(false, text).on_hover_text("Use as limit price").clicked() {
+                            clicked = Some(level.price);
+                        }
+                        ui.label(format!("{:.3}", level.quantity));
+                        ui.label(format!("{:.3}", total));
+                        ui.end_row();
+                    }
+                });
+                if let Some(price) = clicked {
+                    self.trading_panel.order_mode = OrderMode::Limit;
+                    self.trading_panel.price = price.to_string();
+                }
+                
+                ui.separator();
+                
+                // Cumulative size stepping out from the best price on each side
+                let depth_line = |levels: &[orderbook::Level]| -> PlotPoints {
+                    orderbook::cumulative(levels)
+                        .into_iter()
+                        .scan(0.0, |previous, (price, total)| {
+                            let step = [[price, *previous], [price, total]];
+                            *previous = total;
+                            Some(step)
+                        })
+                        .flatten()
+                        .collect()
+                };
+                Plot::new("depth_chart")
+                    .height(160.0)
+                    .allow_drag(false)
+                    .allow_zoom(false)
+                    .allow_scroll(false)
+                    .show_grid([false, true])
+                    .label_formatter(|_, point| format!("{}\n{:.3}", price_text(point.x), point.y))
+                    .show(ui, |plot_ui| {
+                        plot_ui.line(Line::new("Bids", depth_line(&book.bids))
+                            .color(egui::Color32::from_rgb(0, 200, 100))
+                            .fill(0.0));
+                        plot_ui.line(Line::new("Asks", depth_line(&book.asks))
+                            .color(egui::Color32::from_rgb(255, 100, 100))
+                            .fill(0.0));
+                    });
+            });
+        }
+        
+        // Time & sales, newest first, with the taker flow over the last minute
+        if self.show_tape {
+            egui::SidePanel::right("trade_tape_panel").min_width(200.0).show(ctx, |ui| {
+                ui.heading("🧾 Time & Sales");
+                ui.separator();
+                
+                if let Some(error) = &self.tape_error {
+                    ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠ {}", error));
+                }
+                let buy_color = egui::Color32::from_rgb(0, 200, 100);
+                let sell_color = egui::Color32::from_rgb(255, 100, 100);
+                let flow = self.trade_tape.flow();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{:.0}s", FLOW_WINDOW));
+                    ui.colored_label(buy_color, format!("Buy {:.3}", flow.buy));
+                    ui.colored_label(sell_color, format!("Sell {:.3}", flow.sell));
+                });
+                let delta_color = if flow.delta() >= 0.0 { buy_color } else { sell_color };
+                ui.colored_label(delta_color, format!("Delta: {:+.3} {}", flow.delta(), base_asset));
+                ui.horizontal(|ui| {
+                    ui.label("Highlight ≥");
+                    ui.add(egui::DragValue::new(&mut self.large_trade).range(0.0..=f64::MAX).speed(1000.0).suffix(" USDT"));
+                });
+                
+                ui.separator();
+                
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("trade_tape").num_columns(3).striped(true).show(ui, |ui| {
+                        ui.label("Time");
+                        ui.label("Price");
+                        ui.label("Size");
+                        ui.end_row();
+                        
+                        for trade in self.trade_tape.latest() {
+                            let color = if trade.is_buyer_maker { sell_color } else { buy_color };
+                            let large = trade.price * trade.quantity >= self.large_trade;
+                            let cell = |text: String| {
+                                let text = egui::RichText::new(text).color(color);
+                                match large {
+                                    true => text.strong().background_color(color.gamma_multiply(0.25)),
+                                    false => text,
+                                }
+                            };
+                            let time = chrono::DateTime::from_timestamp_millis((trade.timestamp * 1000.0) as i64)
+                                .map(|time| time.format("%H:%M:%S").to_string())
+                                .unwrap_or_default();
+                            ui.label(cell(time));
+                            ui.label(cell(price_text(trade.price)));
+                            ui.label(cell(format!("{}", trade.quantity)));
+                            ui.end_row();
+                        }
+                    });
+                    if self.trade_tape.latest().next().is_none() {
+                        ui.label("Waiting for trades...");
+                    }
+                });
+            });
+        }
+        
         // Chart area (now takes remaining space)
+        let mut drawings_changed = false;
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading(format!("📊 BTC/USDT ({})", self.timeframe.to_display_string()));
+            match &self.replay {
+                Some(_) => ui.heading(format!("📊 {} ({}, replay)", self.source.name(), self.timeframe.to_display_string())),
+                None => ui.heading(format!("📊 {}/{} ({})", base_asset, exchange::QUOTE_ASSET, self.timeframe.to_display_string())),
+            };
             
             if self.is_loading {
                 ui.centered_and_justified(|ui| {
@@ -758,25 +2241,29 @@ impl eframe::App for CryptoApp {
             }
             
             let mut view_window_start = self.view_window_start;
-            let window_size = self.window_size;
+            let mut window_size = self.window_size;
             let latest_timestamp = self.latest_timestamp;
+            let live_buffer = self.live_buffer();
             let chart_type = self.chart_type.clone();
             let candle_width = self.candle_width;
             let candle_interval = self.timeframe.get_candle_interval();
+            let show_volume_profile = self.show_volume_profile;
+            let chart_data = self.transformed.get(&chart_type, self.renko_box, &data).unwrap_or(&data);
             
             // Calculate available height for charts
             let available_height = ui.available_height();
-            let mut total_charts = 1; // Main price chart
-            
-            if self.show_macd { total_charts += 1; }
-            if self.show_rsi { total_charts += 1; }
+            let subpanes: Vec<&IndicatorInstance> = self.indicators
+                .iter()
+                .filter(|instance| instance.config.placement() == Placement::Subpane)
+                .collect();
             
-            // Distribute heights: main chart gets 70%, indicators share 30%
-            let main_chart_height = available_height * 0.7;
-            let indicator_height = if total_charts > 1 { 
-                (available_height * 0.3) / (total_charts - 1) as f32 
-            } else { 
-                0.0 
+            // Distribute heights: each subpane takes 15% of the space, up to half of it in total
+            let indicator_share = (0.15 * subpanes.len() as f32).min(0.5);
+            let main_chart_height = available_height * (1.0 - indicator_share);
+            let indicator_height = if subpanes.is_empty() {
+                0.0
+            } else {
+                available_height * indicator_share / subpanes.len() as f32
             };
             
             // Main Price Chart
@@ -784,44 +2271,36 @@ impl eframe::App for CryptoApp {
                 egui::Vec2::new(ui.available_width(), main_chart_height),
                 egui::Layout::top_down(egui::Align::LEFT),
                 |ui| {
-                    let plot = Plot::new("price_chart")
+                    let candles: &VecDeque<CandleData> = &data;
+                    let timeframe = &self.timeframe;
+                    let indicators = &self.indicators;
+                    
+                    // Dragging the selected drawing edits it instead of panning, as
+                    // does any drag while placing a new one
+                    let hovered_drawing = match (self.price_transform, ui.ctx().pointer_hover_pos()) {
+                        (Some(transform), Some(pointer)) if transform.frame().contains(pointer) => drawing_at(&self.drawings, &transform, pointer),
+                        _ => None,
+                    };
+                    let on_selected = hovered_drawing.is_some_and(|(id, _)| Some(id) == self.selected_drawing);
+                    let pan = self.drawing_tool.is_none() && self.drawing_grab.is_none() && !on_selected;
+                    
+                    let plot = linked_plot(Plot::new("price_chart"), view_window_start, window_size)
                         .view_aspect(3.0)
-                        .allow_zoom([false, false])
-                        .allow_drag([true, false])
-                        .allow_scroll(false)
-                        .auto_bounds(egui::Vec2b::new(false, true))
-                        .default_x_bounds(view_window_start, view_window_start + window_size);
+                        .allow_drag([pan, false])
+                        .auto_bounds(egui::Vec2b::new(true, true))
+                        .label_formatter(|_, point| crosshair_readout(candles, timeframe, indicators, point.x));
                     
                     let plot_response = plot.show(ui, |plot_ui| {
-                        if plot_ui.response().dragged() {
-                            let drag_delta = plot_ui.pointer_coordinate_drag_delta();
-                            if drag_delta.x.abs() > 0.1 {
-                                let proposed_start = view_window_start - drag_delta.x as f64;
-                                let proposed_end = proposed_start + window_size;
-                                
-                                if proposed_end <= latest_timestamp && proposed_start >= 0.0 {
-                                    view_window_start = proposed_start;
-                                    self.is_live_mode = false;
-                                } else if proposed_end > latest_timestamp {
-                                    view_window_start = latest_timestamp - window_size;
-                                    self.is_live_mode = true;
-                                } else if proposed_start < 0.0 {
-                                    view_window_start = 0.0;
-                                    self.is_live_mode = false;
-                                }
-                            }
+                        // The app moved the view (live updates, Live, a new timeframe)
+                        // since the last frame, or this is the first one; otherwise
+                        // the linked panes lead
+                        let last = plot_ui.plot_bounds();
+                        if plot_ui.auto_bounds().x || !same_range((last.min()[0], last.width()), (view_window_start, window_size)) {
+                            plot_ui.set_plot_bounds_x(view_window_start..=view_window_start + window_size);
                         }
+                        wheel_zoom(plot_ui);
                         
-                        let window_end = view_window_start + window_size;
-                        let filtered_data: Vec<_> = data
-                            .iter()
-                            .filter(|candle| {
-                                let margin = window_size * 0.1;
-                                candle.timestamp >= (view_window_start - margin) && 
-                                candle.timestamp <= (window_end + margin)
-                            })
-                            .cloned()
-                            .collect();
+                        let filtered_data = visible_candles(&data, view_window_start, window_size);
                         
                         match chart_type {
                             ChartType::Line => {
@@ -836,88 +2315,196 @@ impl eframe::App for CryptoApp {
                                 
                                 plot_ui.line(price_line);
                             },
-                            ChartType::Candlestick => {
-                                let mut box_elements = Vec::new();
-                                
+                            ChartType::Candlestick | ChartType::HeikinAshi => {
+                                let chart_candles = visible_candles(chart_data, view_window_start, window_size);
+                                plot_candles(plot_ui, chart_type.to_display_string(), &chart_candles, candle_interval * candle_width * 0.8);
+                            }
+                            ChartType::OhlcBars => {
                                 for candle in &filtered_data {
-                                    let is_bullish = candle.close >= candle.open;
-                                    let color = if is_bullish {
+                                    let tick = candle_interval * candle_width * 0.35;
+                                    let color = if candle.close >= candle.open {
                                         egui::Color32::from_rgb(0, 255, 150)
                                     } else {
                                         egui::Color32::from_rgb(255, 80, 80)
                                     };
                                     
-                                    let box_spread = BoxSpread::new(
-                                        candle.low,
-                                        candle.open.min(candle.close),
-                                        (candle.open + candle.close) / 2.0,
-                                        candle.open.max(candle.close),
-                                        candle.high,
-                                    );
-                                    
-                                    let actual_candle_width = candle_interval * candle_width * 0.8;
-                                    
-                                    let box_elem = BoxElem::new(candle.timestamp, box_spread)
-                                        .whisker_width(actual_candle_width * 0.1)
-                                        .box_width(actual_candle_width)
+                                    // Open tick, high-low range and close tick as one path
+                                    let t = candle.timestamp;
+                                    let points: PlotPoints = vec![
+                                        [t - tick, candle.open], [t, candle.open], [t, candle.high],
+                                        [t, candle.low], [t, candle.close], [t + tick, candle.close],
+                                    ].into();
+                                    plot_ui.line(Line::new("OHLC", points).color(color).width(1.5));
+                                }
+                            }
+                            ChartType::Renko => {
+                                let bricks = visible_candles(chart_data, view_window_start, window_size);
+                                let mut box_elements = Vec::new();
+                                
+                                for (index, brick) in bricks.iter().enumerate() {
+                                    // Bricks from one candle share its interval
+                                    let width = bricks.get(index + 1).map_or(candle_interval, |next| next.timestamp - brick.timestamp);
+                                    let color = if brick.close >= brick.open {
+                                        egui::Color32::from_rgb(0, 255, 150)
+                                    } else {
+                                        egui::Color32::from_rgb(255, 80, 80)
+                                    };
+                                    let box_spread = BoxSpread::new(brick.low, brick.low, (brick.low + brick.high) / 2.0, brick.high, brick.high);
+                                    box_elements.push(BoxElem::new(brick.timestamp + width / 2.0, box_spread)
+                                        .box_width(width.min(candle_interval) * 0.9)
+                                        .whisker_width(0.0)
                                         .fill(color)
-                                        .stroke(egui::Stroke::new(1.5, color));
-                                    
-                                    box_elements.push(box_elem);
+                                        .stroke(egui::Stroke::new(1.0, color)));
                                 }
                                 
-                                let candlestick_plot = BoxPlot::new("Candlestick", box_elements);
-                                plot_ui.box_plot(candlestick_plot);
+                                plot_ui.box_plot(BoxPlot::new("Renko", box_elements));
                             }
                         }
                         
-                        // Add Bollinger Bands
-                        if self.show_bollinger && filtered_data.len() >= 20 {
-                            let (upper_band, middle_band, lower_band) = calculate_bollinger_bands(&filtered_data);
-                            
-                            if !upper_band.is_empty() {
-                                let upper_points: PlotPoints = upper_band.iter().map(|(t, v)| [*t, *v]).collect();
-                                let upper_line = Line::new("BB Upper", upper_points)
-                                    .color(egui::Color32::from_rgb(128, 128, 128))
-                                    .width(1.5);
-                                plot_ui.line(upper_line);
-                                
-                                let lower_points: PlotPoints = lower_band.iter().map(|(t, v)| [*t, *v]).collect();
-                                let lower_line = Line::new("BB Lower", lower_points)
-                                    .color(egui::Color32::from_rgb(128, 128, 128))
-                                    .width(1.5);
-                                plot_ui.line(lower_line);
-                                
-                                if !self.show_ma20 {
-                                    let middle_points: PlotPoints = middle_band.iter().map(|(t, v)| [*t, *v]).collect();
-                                    let middle_line = Line::new("BB Middle", middle_points)
-                                        .color(egui::Color32::from_rgb(255, 215, 0))
-                                        .width(1.5);
-                                    plot_ui.line(middle_line);
-                                }
+                        // Overlay indicators
+                        let margin = window_size * 0.1;
+                        for instance in self.indicators.iter().filter(|instance| instance.config.placement() == Placement::Overlay) {
+                            for fill in instance.visible_fills(view_window_start - margin, view_window_start + window_size + margin) {
+                                plot_fill(plot_ui, fill);
+                            }
+                            for series in instance.visible_series(view_window_start - margin, view_window_start + window_size + margin) {
+                                plot_series(plot_ui, series, candle_interval * 0.5);
                             }
                         }
                         
-                        // Add MA20 line
-                        if self.show_ma20 && filtered_data.len() >= 20 {
-                            let ma20_points = calculate_ma20(&filtered_data);
-                            if !ma20_points.is_empty() {
-                                let ma20_plot_points: PlotPoints = ma20_points
+                        // Volume profile growing left from the right edge
+                        if show_volume_profile {
+                            let window_end = view_window_start + window_size;
+                            let in_view: Vec<CandleData> = filtered_data
+                                .iter()
+                                .filter(|candle| candle.timestamp >= view_window_start && candle.timestamp <= window_end)
+                                .cloned()
+                                .collect();
+                            let profile = indicators::volume_profile(&in_view, VOLUME_PROFILE_BINS);
+                            let max_volume = profile.iter().map(|level| level.volume).fold(0.0, f64::max);
+                            
+                            if max_volume > 0.0 {
+                                let bars = profile
                                     .iter()
-                                    .map(|(timestamp, ma_value)| [*timestamp, *ma_value])
+                                    .map(|level| {
+                                        // Point of control stands out from the other levels
+                                        let color = if level.volume == max_volume {
+                                            egui::Color32::from_rgba_unmultiplied(255, 215, 0, 120)
+                                        } else {
+                                            egui::Color32::from_rgba_unmultiplied(120, 160, 255, 70)
+                                        };
+                                        Bar::new((level.low + level.high) / 2.0, -level.volume / max_volume * window_size * 0.25)
+                                            .base_offset(window_end)
+                                            .width(level.high - level.low)
+                                            .fill(color)
+                                            .stroke(egui::Stroke::NONE)
+                                    })
                                     .collect();
+                                plot_ui.bar_chart(BarChart::new("Volume Profile", bars).horizontal());
+                            }
+                        }
+                        
+                        // Place, select and edit drawings
+                        let right_edge = view_window_start + window_size * 1.1;
+                        let pointer = plot_ui.pointer_coordinate().map(|point| Anchor { time: point.x, price: point.y });
+                        let response = plot_ui.response().clone();
+                        match (self.drawing_tool, pointer) {
+                            (Some(tool), Some(pointer)) => {
+                                if response.clicked() {
+                                    self.pending_anchors.push(pointer);
+                                }
+                                let mut preview_anchors = self.pending_anchors.clone();
+                                preview_anchors.push(pointer);
                                 
-                                let ma20_line = Line::new("MA20", ma20_plot_points)
-                                    .color(egui::Color32::from_rgb(255, 215, 0))
-                                    .width(2.5);
-                                
-                                plot_ui.line(ma20_line);
+                                if let Some(shape) = tool.shape(&self.pending_anchors) {
+                                    self.drawings.push(Drawing { id: self.next_drawing_id, shape });
+                                    self.selected_drawing = Some(self.next_drawing_id);
+                                    self.next_drawing_id += 1;
+                                    self.drawing_tool = None;
+                                    self.pending_anchors.clear();
+                                    drawings_changed = true;
+                                } else if let Some(preview) = tool.shape(&preview_anchors) {
+                                    plot_drawing(plot_ui, &preview, true, right_edge);
+                                }
+                            }
+                            (Some(_), None) => {}
+                            (None, _) => {
+                                if response.drag_started() && on_selected {
+                                    self.drawing_grab = hovered_drawing;
+                                }
+                                if let (Some((id, grab)), true) = (self.drawing_grab, response.dragged()) {
+                                    let delta = plot_ui.pointer_coordinate_drag_delta();
+                                    if let Some(drawing) = self.drawings.iter_mut().find(|drawing| drawing.id == id) {
+                                        match (grab, pointer) {
+                                            (Grab::Anchor(index), Some(pointer)) => {
+                                                if let Some(anchor) = drawing.shape.anchors_mut().into_iter().nth(index) {
+                                                    *anchor = pointer;
+                                                }
+                                            }
+                                            (Grab::Anchor(_), None) => {}
+                                            (Grab::Body, _) => drawing.shape.translate(delta.x as f64, delta.y as f64),
+                                        }
+                                    }
+                                }
+                                if response.drag_stopped() && self.drawing_grab.take().is_some() {
+                                    drawings_changed = true;
+                                }
+                                if response.clicked() {
+                                    self.selected_drawing = hovered_drawing.map(|(id, _)| id);
+                                }
+                            }
+                        }
+                        if response.secondary_clicked() {
+                            self.alert_menu_price = pointer.map(|pointer| pointer.price);
+                        }
+                        
+                        for drawing in &self.drawings {
+                            plot_drawing(plot_ui, &drawing.shape, self.selected_drawing == Some(drawing.id), right_edge);
+                        }
+                        
+                        // Armed price alerts
+                        for alert in self.alerts.alerts.iter().filter(|alert| alert.active && alert.symbol == self.symbol) {
+                            if let Condition::PriceCross { level } = alert.condition {
+                                plot_ui.hline(egui_plot::HLine::new("Alerts", level)
+                                    .color(egui::Color32::from_rgb(255, 170, 0))
+                                    .style(egui_plot::LineStyle::dashed_dense()));
+                            }
+                        }
+                    });
+                    self.price_transform = Some(plot_response.transform);
+                    
+                    // Right-click a price to set an alert there
+                    plot_response.response.context_menu(|ui| {
+                        if let Some(price) = self.alert_menu_price {
+                            if ui.button(format!("🔔 Alert at {:.2}", price)).clicked() {
+                                self.alerts.add(&self.symbol, Condition::PriceCross { level: price });
+                                ui.close();
                             }
                         }
                     });
                     
                     self.is_dragging = plot_response.response.dragged();
-                    self.view_window_start = view_window_start;
+                    
+                    // Follow whatever pane the user panned or zoomed
+                    let bounds = plot_response.transform.bounds();
+                    let (start, size) = (bounds.min()[0], bounds.width());
+                    if !same_range((start, size), (view_window_start, window_size)) {
+                        let size = size.clamp(candle_interval * MIN_VISIBLE_CANDLES, candle_interval * MAX_VISIBLE_CANDLES);
+                        let start = start + (bounds.width() - size) / 2.0;
+                        // Zooming while live keeps the latest candle in view
+                        let live_end = latest_timestamp + live_buffer;
+                        if start + size >= live_end || (self.is_live_mode && size != window_size) {
+                            view_window_start = live_end - size;
+                            self.is_live_mode = true;
+                        } else {
+                            view_window_start = start.max(0.0);
+                            self.is_live_mode = false;
+                        }
+                        if size != window_size {
+                            window_size = size;
+                            self.zoom_levels.insert(self.timeframe.clone(), size);
+                        }
+                    }
                 }
             );
             
@@ -930,26 +2517,14 @@ impl eframe::App for CryptoApp {
                         ui.spacing_mut().item_spacing.y = 0.0; // Remove spacing
                         ui.style_mut().visuals.widgets.inactive.bg_fill = egui::Color32::TRANSPARENT;
                         
-                        let volume_plot = Plot::new("volume_overlay")
-                            .allow_zoom([false, false])
-                            .allow_drag([true, false])
-                            .allow_scroll(false)
-                            .auto_bounds(egui::Vec2b::new(false, true))
-                            .default_x_bounds(view_window_start, view_window_start + window_size)
+                        let volume_plot = linked_plot(Plot::new("volume_overlay"), view_window_start, window_size)
+                            .auto_bounds(egui::Vec2b::new(true, true))
                             .show_background(false)
                             .show_axes([false, true]); // Only show Y axis
                         
-                        volume_plot.show(ui, |plot_ui| {
-                            let window_end = view_window_start + window_size;
-                            let filtered_data: Vec<_> = data
-                                .iter()
-                                .filter(|candle| {
-                                    let margin = window_size * 0.1;
-                                    candle.timestamp >= (view_window_start - margin) && 
-                                    candle.timestamp <= (window_end + margin)
-                                })
-                                .cloned()
-                                .collect();
+                        let volume_response = volume_plot.show(ui, |plot_ui| {
+                            wheel_zoom(plot_ui);
+                            let filtered_data = visible_candles(&data, view_window_start, window_size);
                             
                             let mut volume_bars = Vec::new();
                             for candle in &filtered_data {
@@ -972,155 +2547,33 @@ impl eframe::App for CryptoApp {
                             let volume_plot = BoxPlot::new("Volume", volume_bars);
                             plot_ui.box_plot(volume_plot);
                         });
+                        self.is_dragging |= volume_response.response.dragged();
                     }
                 );
             }
             
             
-            // MACD Chart
-            if self.show_macd {
-                ui.allocate_ui_with_layout(
+            // Indicator subpanes
+            for instance in subpanes {
+                let dragged = ui.allocate_ui_with_layout(
                     egui::Vec2::new(ui.available_width(), indicator_height),
                     egui::Layout::top_down(egui::Align::LEFT),
-                    |ui| {
-                        ui.label("📈 MACD");
-                        let macd_plot = Plot::new("macd_chart")
-                            .allow_zoom([false, false])
-                            .allow_drag([true, false])
-                            .allow_scroll(false)
-                            .auto_bounds(egui::Vec2b::new(false, true))
-                            .default_x_bounds(view_window_start, view_window_start + window_size);
-                        
-                        macd_plot.show(ui, |plot_ui| {
-                            let window_end = view_window_start + window_size;
-                            let filtered_data: Vec<_> = data
-                                .iter()
-                                .filter(|candle| {
-                                    let margin = window_size * 0.1;
-                                    candle.timestamp >= (view_window_start - margin) && 
-                                    candle.timestamp <= (window_end + margin)
-                                })
-                                .cloned()
-                                .collect();
-                            
-                            if filtered_data.len() >= 35 { // Increased minimum data requirement
-                                let (macd_line, signal_line, histogram) = calculate_macd(&filtered_data);
-                                
-                                // MACD Line
-                                if !macd_line.is_empty() {
-                                    let macd_points: PlotPoints = macd_line.iter().map(|(t, v)| [*t, *v]).collect();
-                                    let macd = Line::new("MACD", macd_points)
-                                        .color(egui::Color32::from_rgb(0, 150, 255))
-                                        .width(2.0);
-                                    plot_ui.line(macd);
-                                }
-                                
-                                // Signal Line
-                                if !signal_line.is_empty() {
-                                    let signal_points: PlotPoints = signal_line.iter().map(|(t, v)| [*t, *v]).collect();
-                                    let signal = Line::new("Signal", signal_points)
-                                        .color(egui::Color32::from_rgb(255, 150, 0))
-                                        .width(2.0);
-                                    plot_ui.line(signal);
-                                }
-                                
-                                // Histogram
-                                if !histogram.is_empty() {
-                                    let mut hist_bars = Vec::new();
-                                    for (timestamp, value) in &histogram {
-                                        let color = if *value >= 0.0 {
-                                            egui::Color32::from_rgb(0, 200, 100)
-                                        } else {
-                                            egui::Color32::from_rgb(255, 100, 100)
-                                        };
-                                        
-                                        let actual_width = candle_interval * 0.5;
-                                        let box_spread = if *value >= 0.0 {
-                                            BoxSpread::new(0.0, 0.0, value / 2.0, *value, *value)
-                                        } else {
-                                            BoxSpread::new(*value, *value, value / 2.0, 0.0, 0.0)
-                                        };
-                                        
-                                        let box_elem = BoxElem::new(*timestamp, box_spread)
-                                            .box_width(actual_width)
-                                            .fill(color)
-                                            .stroke(egui::Stroke::new(1.0, color));
-                                        
-                                        hist_bars.push(box_elem);
-                                    }
-                                    
-                                    let hist_plot = BoxPlot::new("MACD Histogram", hist_bars);
-                                    plot_ui.box_plot(hist_plot);
-                                }
-                            }
-                        });
-                    }
-                );
+                    |ui| draw_subpane(ui, instance, view_window_start, window_size, candle_interval * 0.5)
+                ).inner;
+                self.is_dragging |= dragged;
             }
             
-            // RSI Chart
-            if self.show_rsi {
-                ui.allocate_ui_with_layout(
-                    egui::Vec2::new(ui.available_width(), indicator_height),
-                    egui::Layout::top_down(egui::Align::LEFT),
-                    |ui| {
-                        ui.label("⚡ RSI");
-                        let rsi_plot = Plot::new("rsi_chart")
-                            .allow_zoom([false, false])
-                            .allow_drag([true, false])
-                            .allow_scroll(false)
-                            .auto_bounds(egui::Vec2b::new(false, false))
-                            .default_x_bounds(view_window_start, view_window_start + window_size)
-                            .default_y_bounds(0.0, 100.0);
-                        
-                        rsi_plot.show(ui, |plot_ui| {
-                            let window_end = view_window_start + window_size;
-                            let filtered_data: Vec<_> = data
-                                .iter()
-                                .filter(|candle| {
-                                    let margin = window_size * 0.1;
-                                    candle.timestamp >= (view_window_start - margin) && 
-                                    candle.timestamp <= (window_end + margin)
-                                })
-                                .cloned()
-                                .collect();
-                            
-                            if filtered_data.len() >= 15 {
-                                let rsi_points = calculate_rsi(&filtered_data);
-                                
-                                if !rsi_points.is_empty() {
-                                    let rsi_plot_points: PlotPoints = rsi_points.iter().map(|(t, v)| [*t, *v]).collect();
-                                    let rsi_line = Line::new("RSI", rsi_plot_points)
-                                        .color(egui::Color32::from_rgb(255, 100, 255))
-                                        .width(2.0);
-                                    plot_ui.line(rsi_line);
-                                    
-                                    // Add RSI reference lines
-                                    let overbought: PlotPoints = vec![[view_window_start, 70.0], [view_window_start + window_size, 70.0]].into();
-                                    let oversold: PlotPoints = vec![[view_window_start, 30.0], [view_window_start + window_size, 30.0]].into();
-                                    let middle: PlotPoints = vec![[view_window_start, 50.0], [view_window_start + window_size, 50.0]].into();
-                                    
-                                    let overbought_line = Line::new("Overbought", overbought)
-                                        .color(egui::Color32::from_rgb(255, 100, 100))
-                                        .width(1.0);
-                                    let oversold_line = Line::new("Oversold", oversold)
-                                        .color(egui::Color32::from_rgb(100, 255, 100))
-                                        .width(1.0);
-                                    let middle_line = Line::new("Middle", middle)
-                                        .color(egui::Color32::from_rgb(128, 128, 128))
-                                        .width(1.0);
-                                    
-                                    plot_ui.line(overbought_line);
-                                    plot_ui.line(oversold_line);
-                                    plot_ui.line(middle_line);
-                                }
-                            }
-                        });
-                    }
-                );
-            }
+            self.view_window_start = view_window_start;
+            self.window_size = window_size;
         });
         
+        if drawings_changed {
+            self.save_drawings();
+        }
+        
+        // Load older candles if the view moved past the loaded range
+        self.request_older_history();
+        
         // Repaint every second for live updates
         ctx.request_repaint_after(std::time::Duration::from_secs(1));
     }
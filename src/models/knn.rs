@@ -1,5 +1,6 @@
+use crate::indicators::{self, IndicatorConfig};
 use crate::models::OptimizedKNNPredictor;
-use crate::Candlestick;
+use crate::{CandleData, Candlestick};
 use std::collections::VecDeque;
 
 /*
@@ -37,7 +38,7 @@ impl OptimizedKNNPredictor {
 
         // 기술적 지표 계산
         let (ma5, ma20) = self.calculate_moving_averages(candlesticks);
-        let rsi = self.calculate_rsi(&price_changes, 14);
+        let rsi = self.calculate_rsi(candlesticks, 14);
         let volume_ratio = self.calculate_volume_ratio(candlesticks);
 
         // 특성 결합
@@ -70,28 +71,26 @@ impl OptimizedKNNPredictor {
         (ma5, ma20)
     }
 
-    // RSI 계산 최적화
-    //period:기준 날짜
-    pub fn calculate_rsi(&self, price_changes: &[f32], period: usize) -> f32 {
-        let (gains, losses): (Vec<_>, Vec<_>) = price_changes
+    // Wilder RSI of the window's closes, from the shared indicator module;
+    // neutral while the window is shorter than the period
+    pub fn calculate_rsi(&self, data: &[(&u64, &Candlestick)], period: usize) -> f32 {
+        let candles: VecDeque<CandleData> = data
             .iter()
-            .map(|&change| {
-                if change > 0.0 {
-                    (change, 0.0)
-                } else {
-                    (0.0, -change)
-                }
+            .map(|(timestamp, candle)| CandleData {
+                timestamp: **timestamp as f64,
+                open: candle.open as f64,
+                high: candle.high as f64,
+                low: candle.low as f64,
+                close: candle.close as f64,
+                volume: candle.volume as f64,
             })
-            .unzip();
-
-        let avg_gain: f32 = gains.iter().sum::<f32>() / period as f32;
-        let avg_loss: f32 = losses.iter().sum::<f32>() / period as f32;
+            .collect();
 
-        if avg_loss == 0.0 {
-            100.0
-        } else {
-            100.0 - (100.0 / (1.0 + (avg_gain / avg_loss)))
-        }
+        indicators::last_values(&IndicatorConfig::Rsi { period }, &candles)
+            .first()
+            .copied()
+            .flatten()
+            .map_or(50.0, |rsi| rsi as f32)
     }
 
     // 거래량 비율 계산
@@ -0,0 +1,249 @@
+use crate::exchange::MarketType;
+use std::cmp::Ordering;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Level {
+    pub price: f64,
+    pub quantity: f64,
+}
+
+// Changed levels between two update ids. Quantities are absolute; zero
+// removes the level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthDiff {
+    pub first_update_id: u64,
+    pub final_update_id: u64,
+    pub previous_update_id: Option<u64>, // Final id of the previous diff, where the stream sends it (futures)
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+// A diff that does not follow on from the book's last update
+#[derive(Debug, PartialEq)]
+pub struct SequenceGap {
+    pub last_update_id: u64,
+    pub first_update_id: u64,
+}
+
+impl std::fmt::Display for SequenceGap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "order book out of sync: at update {}, next diff starts at {}",
+            self.last_update_id, self.first_update_id
+        )
+    }
+}
+
+impl std::error::Error for SequenceGap {}
+
+// A diff that could not be read, so the book may be missing its changes
+#[derive(Debug, PartialEq)]
+pub struct MalformedDiff(pub String);
+
+impl std::fmt::Display for MalformedDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "malformed depth update: {}", self.0)
+    }
+}
+
+impl std::error::Error for MalformedDiff {}
+
+// Local copy of an exchange order book: a REST snapshot kept current by
+// applying the diff stream in sequence
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderBook {
+    pub bids: Vec<Level>, // Highest price first
+    pub asks: Vec<Level>, // Lowest price first
+    pub last_update_id: u64,
+    market: MarketType, // Spot and futures streams number their diffs differently
+    synced: bool,       // A diff has been applied on top of the snapshot
+}
+
+impl OrderBook {
+    pub fn from_snapshot(market: MarketType, last_update_id: u64, mut bids: Vec<Level>, mut asks: Vec<Level>) -> Self {
+        bids.retain(|level| level.quantity > 0.0);
+        asks.retain(|level| level.quantity > 0.0);
+        bids.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap_or(Ordering::Equal));
+        asks.sort_by(|a, b| a.price.partial_cmp(&b.price).unwrap_or(Ordering::Equal));
+        Self { bids, asks, last_update_id, market, synced: false }
+    }
+
+    // Whether the book has caught up with the diff stream
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    // Diffs the snapshot already includes are skipped: on futures those that
+    // end before its update id, on spot those that end at or before it. The
+    // first one applied must span the snapshot's update id (on spot, the id
+    // after it), and each later one must continue exactly where the previous
+    // one ended.
+    pub fn apply(&mut self, diff: &DepthDiff) -> Result<(), SequenceGap> {
+        let included = match self.market {
+            MarketType::Futures => diff.final_update_id < self.last_update_id,
+            MarketType::Spot => diff.final_update_id <= self.last_update_id,
+        };
+        if included {
+            return Ok(());
+        }
+        let follows = match (self.synced, diff.previous_update_id) {
+            (false, _) => match self.market {
+                MarketType::Futures => diff.first_update_id <= self.last_update_id,
+                MarketType::Spot => diff.first_update_id <= self.last_update_id + 1,
+            },
+            (true, Some(previous)) => previous == self.last_update_id,
+            (true, None) => diff.first_update_id == self.last_update_id + 1,
+        };
+        if !follows {
+            return Err(SequenceGap {
+                last_update_id: self.last_update_id,
+                first_update_id: diff.first_update_id,
+            });
+        }
+
+        for level in &diff.bids {
+            set_level(&mut self.bids, *level, |a, b| b.partial_cmp(&a));
+        }
+        for level in &diff.asks {
+            set_level(&mut self.asks, *level, |a, b| a.partial_cmp(&b));
+        }
+        self.last_update_id = diff.final_update_id;
+        self.synced = true;
+        Ok(())
+    }
+
+    // The best `depth` levels on each side
+    pub fn top(&self, depth: usize) -> OrderBook {
+        OrderBook {
+            bids: self.bids.iter().take(depth).copied().collect(),
+            asks: self.asks.iter().take(depth).copied().collect(),
+            last_update_id: self.last_update_id,
+            market: self.market,
+            synced: self.synced,
+        }
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.asks.first()?.price - self.bids.first()?.price)
+    }
+}
+
+// Running total of quantity from the best level outward
+pub fn cumulative(levels: &[Level]) -> Vec<(f64, f64)> {
+    levels
+        .iter()
+        .scan(0.0, |total, level| {
+            *total += level.quantity;
+            Some((level.price, *total))
+        })
+        .collect()
+}
+
+// Insert, replace or remove one level of a side kept sorted by `order`
+fn set_level(levels: &mut Vec<Level>, level: Level, order: impl Fn(f64, f64) -> Option<Ordering>) {
+    let position = levels.binary_search_by(|existing| order(existing.price, level.price).unwrap_or(Ordering::Equal));
+    match (position, level.quantity > 0.0) {
+        (Ok(index), true) => levels[index] = level,
+        (Ok(index), false) => {
+            levels.remove(index);
+        }
+        (Err(index), true) => levels.insert(index, level),
+        (Err(_), false) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: f64, quantity: f64) -> Level {
+        Level { price, quantity }
+    }
+
+    fn diff(first: u64, last: u64, previous: Option<u64>, bids: Vec<Level>, asks: Vec<Level>) -> DepthDiff {
+        DepthDiff {
+            first_update_id: first,
+            final_update_id: last,
+            previous_update_id: previous,
+            bids,
+            asks,
+        }
+    }
+
+    fn snapshot() -> OrderBook {
+        OrderBook::from_snapshot(
+            MarketType::Futures,
+            100,
+            vec![level(99.0, 2.0), level(100.0, 1.0)],
+            vec![level(102.0, 3.0), level(101.0, 1.5)],
+        )
+    }
+
+    #[test]
+    fn diffs_are_applied_in_sequence() {
+        let mut book = snapshot();
+        assert_eq!(book.spread(), Some(1.0));
+
+        // Entirely before the snapshot
+        book.apply(&diff(90, 95, Some(89), vec![level(100.0, 0.0)], vec![])).unwrap();
+        assert!(!book.is_synced());
+        assert_eq!(book.bids[0], level(100.0, 1.0));
+
+        // Spans the snapshot, so it is the first one to apply
+        book.apply(&diff(98, 103, Some(97), vec![level(100.0, 0.0), level(99.5, 4.0)], vec![level(101.0, 0.5)]))
+            .unwrap();
+        book.apply(&diff(104, 110, Some(103), vec![], vec![level(101.0, 0.0), level(101.5, 2.0)])).unwrap();
+
+        assert!(book.is_synced());
+        assert_eq!(book.last_update_id, 110);
+        assert_eq!(book.bids, vec![level(99.5, 4.0), level(99.0, 2.0)]);
+        assert_eq!(book.asks, vec![level(101.5, 2.0), level(102.0, 3.0)]);
+        assert_eq!(cumulative(&book.asks), vec![(101.5, 2.0), (102.0, 5.0)]);
+    }
+
+    #[test]
+    fn gaps_are_reported() {
+        // The snapshot is older than anything the stream still has
+        let mut book = snapshot();
+        let gap = book.apply(&diff(150, 160, Some(149), vec![], vec![])).unwrap_err();
+        assert_eq!(gap, SequenceGap { last_update_id: 100, first_update_id: 150 });
+
+        // A diff went missing once in sync; streams without a previous id
+        // must continue from the next id
+        let mut book = snapshot();
+        book.apply(&diff(100, 105, Some(99), vec![], vec![])).unwrap();
+        assert!(book.apply(&diff(108, 112, Some(107), vec![], vec![])).is_err());
+        let mut book = OrderBook { market: MarketType::Spot, ..snapshot() };
+        book.apply(&diff(101, 105, None, vec![], vec![])).unwrap();
+        assert!(book.apply(&diff(107, 112, None, vec![], vec![])).is_err());
+        book.apply(&diff(106, 112, None, vec![], vec![])).unwrap();
+    }
+
+    #[test]
+    fn stale_diffs_depend_on_the_market() {
+        // Ending exactly at the snapshot: already in a spot snapshot, but the
+        // first diff to apply on futures
+        let stale = diff(95, 100, None, vec![level(100.0, 0.0)], vec![]);
+
+        let mut spot = OrderBook { market: MarketType::Spot, ..snapshot() };
+        spot.apply(&stale).unwrap();
+        assert!(!spot.is_synced());
+        assert_eq!(spot.bids[0], level(100.0, 1.0));
+        spot.apply(&diff(101, 104, None, vec![], vec![level(101.0, 0.0)])).unwrap();
+        assert!(spot.is_synced());
+        assert_eq!((spot.last_update_id, spot.asks[0]), (104, level(102.0, 3.0)));
+
+        let mut futures = snapshot();
+        futures.apply(&stale).unwrap();
+        assert!(futures.is_synced());
+        assert_eq!(futures.bids[0], level(99.0, 2.0));
+
+        // Starting right after the snapshot is fine on spot, but leaves a
+        // futures book unsure it has every update
+        let next = diff(101, 104, Some(100), vec![], vec![]);
+        let gap = snapshot().apply(&next).unwrap_err();
+        assert_eq!(gap, SequenceGap { last_update_id: 100, first_update_id: 101 });
+        OrderBook { market: MarketType::Spot, ..snapshot() }.apply(&next).unwrap();
+    }
+}
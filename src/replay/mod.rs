@@ -0,0 +1,456 @@
+use crate::exchange::binance::parse_klines_json;
+use crate::exchange::{CandleStream, KlineRange, MarketDataSource, SourceError, SymbolInfo};
+use crate::{CandleData, Timeframe};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplaySpeed {
+    X1,  // Recorded candle spacing in real time
+    X10,
+    Max, // No delay between candles
+}
+
+impl ReplaySpeed {
+    pub fn to_display_string(self) -> &'static str {
+        match self {
+            ReplaySpeed::X1 => "1x",
+            ReplaySpeed::X10 => "10x",
+            ReplaySpeed::Max => "Max",
+        }
+    }
+
+    fn delay(self, gap_secs: f64) -> Option<Duration> {
+        let gap_secs = gap_secs.max(0.0);
+        match self {
+            ReplaySpeed::X1 => Some(Duration::from_secs_f64(gap_secs)),
+            ReplaySpeed::X10 => Some(Duration::from_secs_f64(gap_secs / 10.0)),
+            ReplaySpeed::Max => None,
+        }
+    }
+}
+
+struct ControlState {
+    speed: ReplaySpeed,
+    paused: bool,
+    pending_steps: usize,
+}
+
+// Playback controls shared between the UI and the replay stream
+pub struct ReplayControl {
+    state: Mutex<ControlState>,
+    changed: Notify,
+}
+
+impl ReplayControl {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(ControlState {
+                speed: ReplaySpeed::X1,
+                paused: false,
+                pending_steps: 0,
+            }),
+            changed: Notify::new(),
+        }
+    }
+
+    pub fn speed(&self) -> ReplaySpeed {
+        self.state.lock().map(|state| state.speed).unwrap_or(ReplaySpeed::X1)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().map(|state| state.paused).unwrap_or(false)
+    }
+
+    pub fn set_speed(&self, speed: ReplaySpeed) {
+        self.update(|state| state.speed = speed);
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.update(|state| state.paused = paused);
+    }
+
+    // Emit a single candle; only has an effect while paused
+    pub fn step(&self) {
+        self.update(|state| {
+            if state.paused {
+                state.pending_steps += 1;
+            }
+        });
+    }
+
+    fn reset(&self) {
+        self.update(|state| {
+            state.speed = ReplaySpeed::X1;
+            state.paused = false;
+            state.pending_steps = 0;
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ControlState)) {
+        if let Ok(mut state) = self.state.lock() {
+            f(&mut state);
+        }
+        self.changed.notify_one();
+    }
+
+    // Whether the next candle may be emitted now, consuming a step if paused
+    fn take_permit(&self) -> bool {
+        match self.state.lock() {
+            Ok(mut state) if state.paused && state.pending_steps > 0 => {
+                state.pending_steps -= 1;
+                true
+            }
+            Ok(state) => !state.paused,
+            Err(_) => false,
+        }
+    }
+}
+
+// Wait out `gap_secs` of recorded time at the current speed before the next
+// candle. Pausing holds the wait and a step ends it; a speed change re-arms
+// whatever is left of the gap at the new speed.
+async fn wait_for_turn(control: &ReplayControl, gap_secs: f64) {
+    let mut remaining = gap_secs.max(0.0); // Recorded seconds
+    loop {
+        if control.is_paused() {
+            if control.take_permit() {
+                return;
+            }
+            control.changed.notified().await;
+            continue;
+        }
+
+        let delay = match control.speed().delay(remaining) {
+            Some(delay) if !delay.is_zero() => delay,
+            Some(_) => break,
+            None => {
+                tokio::task::yield_now().await;
+                break;
+            }
+        };
+        let started = tokio::time::Instant::now();
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => break,
+            _ = control.changed.notified() => {
+                let done = started.elapsed().as_secs_f64() / delay.as_secs_f64();
+                remaining *= 1.0 - done.min(1.0);
+            }
+        }
+    }
+
+    // Paused just as the delay ran out
+    while !control.take_permit() {
+        control.changed.notified().await;
+    }
+}
+
+#[derive(Deserialize)]
+struct RecordedCandle {
+    timestamp: f64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+}
+
+// Load recorded candles from a JSON or CSV file.
+//
+// JSON may be either an array of {timestamp, open, high, low, close, volume}
+// objects or a raw Binance klines response. CSV rows are
+// `timestamp,open,high,low,close,volume` with an optional header line.
+// Timestamps may be in seconds or milliseconds.
+pub fn load_candles(path: &Path) -> Result<Vec<CandleData>, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path)?;
+
+    let is_json = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+
+    let mut candles = if is_json {
+        let json: serde_json::Value = serde_json::from_str(&text)?;
+        let is_binance_format = json
+            .as_array()
+            .and_then(|rows| rows.first())
+            .map(|row| row.is_array())
+            .unwrap_or(false);
+
+        if is_binance_format {
+            parse_klines_json(&json).map_err(|e| e.to_string())?
+        } else {
+            serde_json::from_value::<Vec<RecordedCandle>>(json)?
+                .into_iter()
+                .map(|c| CandleData {
+                    timestamp: normalize_timestamp(c.timestamp),
+                    open: c.open,
+                    high: c.high,
+                    low: c.low,
+                    close: c.close,
+                    volume: c.volume,
+                })
+                .collect()
+        }
+    } else {
+        parse_csv(&text)?
+    };
+
+    candles.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+    if candles.is_empty() {
+        return Err(format!("No candles found in {}", path.display()).into());
+    }
+
+    Ok(candles)
+}
+
+fn parse_csv(text: &str) -> Result<Vec<CandleData>, Box<dyn std::error::Error>> {
+    let mut candles = Vec::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() < 6 {
+            return Err(format!("line {}: expected 6 columns, found {}", line_number + 1, fields.len()).into());
+        }
+
+        let values: Result<Vec<f64>, _> = fields[..6].iter().map(|field| field.parse::<f64>()).collect();
+        match values {
+            Ok(values) => candles.push(CandleData {
+                timestamp: normalize_timestamp(values[0]),
+                open: values[1],
+                high: values[2],
+                low: values[3],
+                close: values[4],
+                volume: values[5],
+            }),
+            // Header row
+            Err(_) if line_number == 0 => continue,
+            Err(e) => return Err(format!("line {}: {}", line_number + 1, e).into()),
+        }
+    }
+
+    Ok(candles)
+}
+
+fn normalize_timestamp(timestamp: f64) -> f64 {
+    if timestamp > 1e11 {
+        timestamp / 1000.0
+    } else {
+        timestamp
+    }
+}
+
+// Candles from a recorded file, played back through the live update stream
+pub struct ReplaySource {
+    name: String,
+    candles: Arc<Vec<CandleData>>,
+    control: Arc<ReplayControl>,
+}
+
+impl ReplaySource {
+    pub fn new(path: &Path, candles: Vec<CandleData>) -> Self {
+        Self {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string()),
+            candles: Arc::new(candles),
+            control: Arc::new(ReplayControl::new()),
+        }
+    }
+
+    pub fn control(&self) -> Arc<ReplayControl> {
+        self.control.clone()
+    }
+
+    // Median spacing between recorded candles, in seconds
+    pub fn candle_interval(&self) -> Option<f64> {
+        let mut gaps: Vec<f64> = self
+            .candles
+            .windows(2)
+            .map(|pair| pair[1].timestamp - pair[0].timestamp)
+            .filter(|gap| *gap > 0.0)
+            .collect();
+        if gaps.is_empty() {
+            return None;
+        }
+        gaps.sort_by(|a, b| a.total_cmp(b));
+        Some(gaps[gaps.len() / 2])
+    }
+}
+
+impl MarketDataSource for ReplaySource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Everything is delivered through `live_updates` so playback starts
+    // from the first recorded candle
+    fn historical_klines<'a>(
+        &'a self,
+        _symbol: &'a str,
+        _timeframe: &'a Timeframe,
+        _range: KlineRange,
+    ) -> BoxFuture<'a, Result<Vec<CandleData>, SourceError>> {
+        Box::pin(async move { Ok(Vec::new()) })
+    }
+
+    fn live_updates<'a>(
+        &'a self,
+        _symbol: &'a str,
+        _timeframe: &'a Timeframe,
+    ) -> BoxFuture<'a, Result<CandleStream, SourceError>> {
+        Box::pin(async move {
+            self.control.reset();
+
+            let candles = self.candles.clone();
+            let control = self.control.clone();
+
+            let playback = futures_util::stream::unfold(0usize, move |index| {
+                let candles = candles.clone();
+                let control = control.clone();
+                async move {
+                    let candle = candles.get(index)?.clone();
+                    let gap = match index {
+                        0 => 0.0,
+                        _ => candle.timestamp - candles[index - 1].timestamp,
+                    };
+                    wait_for_turn(&control, gap).await;
+
+                    Some((Ok(candle), index + 1))
+                }
+            });
+
+            // The recording has ended; keep the stream open so the feed
+            // does not treat it as a dropped connection and replay again
+            Ok(playback.chain(futures_util::stream::pending()).boxed())
+        })
+    }
+
+    fn symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, SourceError>> {
+        Box::pin(async move { Ok(Vec::new()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join(name)
+    }
+
+    fn timestamps(candles: &[CandleData]) -> Vec<f64> {
+        candles.iter().map(|candle| candle.timestamp).collect()
+    }
+
+    #[test]
+    fn recordings_load_from_every_format() {
+        let expected = vec![1_700_000_040.0, 1_700_000_100.0, 1_700_000_160.0];
+
+        // Millisecond timestamps, out of order
+        let json = load_candles(&fixture("replay/fixtures/candles.json")).unwrap();
+        assert_eq!(timestamps(&json), expected);
+        assert_eq!(json[1].high, 37125.7);
+
+        let binance = load_candles(&fixture("exchange/fixtures/binance_klines.json")).unwrap();
+        assert_eq!(binance, json);
+
+        // Header row and a blank line
+        let csv = load_candles(&fixture("replay/fixtures/candles.csv")).unwrap();
+        assert_eq!(csv, json);
+    }
+
+    #[test]
+    fn malformed_recordings_are_rejected() {
+        let err = load_candles(&fixture("replay/fixtures/candles_malformed.csv")).unwrap_err();
+        assert!(err.to_string().starts_with("line 3:"), "{}", err);
+
+        let err = load_candles(&fixture("exchange/fixtures/binance_klines_malformed.json")).unwrap_err();
+        assert!(err.to_string().contains("skipped 1 of 3 rows"), "{}", err);
+
+        assert!(parse_csv("1700000040,1,2,0.5\n").is_err());
+    }
+
+    fn source(count: usize) -> ReplaySource {
+        let candles = (0..count)
+            .map(|i| CandleData {
+                timestamp: i as f64 * 10.0,
+                open: 1.0,
+                high: 1.0,
+                low: 1.0,
+                close: 1.0,
+                volume: 1.0,
+            })
+            .collect();
+        ReplaySource::new(Path::new("recording.csv"), candles)
+    }
+
+    async fn next_at(stream: &mut CandleStream, started: Instant) -> (f64, f64) {
+        let candle = stream.next().await.unwrap().unwrap();
+        (candle.timestamp, started.elapsed().as_secs_f64())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn controls_pace_the_emitted_candles() {
+        let source = source(6);
+        let control = source.control();
+        let mut stream = source.live_updates("", &Timeframe::M1).await.unwrap();
+        let started = Instant::now();
+
+        assert_eq!(next_at(&mut stream, started).await, (0.0, 0.0));
+        assert_eq!(next_at(&mut stream, started).await, (10.0, 10.0));
+
+        // Nothing while paused, then one candle per step
+        control.set_paused(true);
+        assert!(tokio::time::timeout(Duration::from_secs(100), stream.next()).await.is_err());
+        control.step();
+        assert_eq!(next_at(&mut stream, started).await, (20.0, 110.0));
+        control.step();
+        assert_eq!(next_at(&mut stream, started).await, (30.0, 110.0));
+
+        control.set_paused(false);
+        control.set_speed(ReplaySpeed::X10);
+        assert_eq!(next_at(&mut stream, started).await, (40.0, 111.0));
+        control.set_speed(ReplaySpeed::Max);
+        assert_eq!(next_at(&mut stream, started).await, (50.0, 111.0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn control_changes_keep_the_rest_of_the_delay() {
+        let source = source(3);
+        let control = source.control();
+        let mut stream = source.live_updates("", &Timeframe::M1).await.unwrap();
+        let started = Instant::now();
+        stream.next().await.unwrap().unwrap();
+
+        // 4 of the 10 seconds pass at 1x; the other 6 take 0.6 s at 10x
+        assert!(tokio::time::timeout(Duration::from_secs(4), stream.next()).await.is_err());
+        control.set_speed(ReplaySpeed::X10);
+        let (timestamp, at) = next_at(&mut stream, started).await;
+        assert_eq!(timestamp, 10.0);
+        assert!((at - 4.6).abs() < 1e-6, "{}", at);
+
+        // A pause holds what is left of the delay until playback resumes
+        control.set_speed(ReplaySpeed::X1);
+        assert!(tokio::time::timeout(Duration::from_secs(3), stream.next()).await.is_err());
+        control.set_paused(true);
+        assert!(tokio::time::timeout(Duration::from_secs(60), stream.next()).await.is_err());
+        control.set_paused(false);
+        let (timestamp, at) = next_at(&mut stream, started).await;
+        assert_eq!(timestamp, 20.0);
+        assert!((at - 74.6).abs() < 1e-6, "{}", at);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::CandleData;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+// timestamp, open, high, low, close, volume as little-endian f64
+const RECORD_SIZE: usize = 6 * 8;
+
+// Append-only candle file for one symbol/interval pair.
+// Records are never rewritten in place; on load the last record for a
+// timestamp wins and the file is compacted if it holds duplicates.
+pub struct CandleStore {
+    path: PathBuf,
+}
+
+impl CandleStore {
+    pub fn open(dir: &Path, symbol: &str, interval: &str) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        // "1M" and "1m" would collide on case-insensitive file systems
+        let file_name = format!("{}_{}.bin", symbol.to_uppercase(), interval.replace('M', "mo"));
+        Ok(Self {
+            path: dir.join(file_name),
+        })
+    }
+
+    pub fn load(&self) -> io::Result<Vec<CandleData>> {
+        let mut bytes = Vec::new();
+        match File::open(&self.path) {
+            Ok(mut file) => {
+                file.read_to_end(&mut bytes)?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        }
+
+        // A partially written trailing record is ignored
+        let record_count = bytes.len() / RECORD_SIZE;
+        let mut candles: Vec<CandleData> = bytes
+            .chunks_exact(RECORD_SIZE)
+            .map(decode_record)
+            .collect();
+
+        // Stable sort keeps append order for equal timestamps, so the
+        // dedup below retains the most recently written record
+        candles.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        let mut deduped: Vec<CandleData> = Vec::with_capacity(candles.len());
+        for candle in candles {
+            match deduped.last_mut() {
+                Some(last) if (last.timestamp - candle.timestamp).abs() < 1.0 => *last = candle,
+                _ => deduped.push(candle),
+            }
+        }
+
+        if deduped.len() != record_count || bytes.len() % RECORD_SIZE != 0 {
+            self.rewrite(&deduped)?;
+        }
+
+        Ok(deduped)
+    }
+
+    // The last `limit` stored candles that opened before `before` (seconds)
+    pub fn load_before(&self, before: f64, limit: usize) -> io::Result<Vec<CandleData>> {
+        let candles = self.load()?;
+        let end = candles.partition_point(|candle| candle.timestamp < before - 0.5);
+        let start = end.saturating_sub(limit);
+        Ok(candles[start..end].to_vec())
+    }
+
+    pub fn append(&self, candles: &[CandleData]) -> io::Result<()> {
+        if candles.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(&encode_records(candles))
+    }
+
+    fn rewrite(&self, candles: &[CandleData]) -> io::Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, encode_records(candles))?;
+        fs::rename(tmp_path, &self.path)
+    }
+}
+
+fn encode_records(candles: &[CandleData]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(candles.len() * RECORD_SIZE);
+    for candle in candles {
+        for value in [
+            candle.timestamp,
+            candle.open,
+            candle.high,
+            candle.low,
+            candle.close,
+            candle.volume,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+fn decode_record(record: &[u8]) -> CandleData {
+    let field = |i: usize| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&record[i * 8..(i + 1) * 8]);
+        f64::from_le_bytes(buf)
+    };
+
+    CandleData {
+        timestamp: field(0),
+        open: field(1),
+        high: field(2),
+        low: field(3),
+        close: field(4),
+        volume: field(5),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(timestamp: f64, close: f64) -> CandleData {
+        CandleData {
+            timestamp,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    fn temp_store(name: &str) -> (PathBuf, CandleStore) {
+        let dir = std::env::temp_dir().join(format!("asterism-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = CandleStore::open(&dir, "btcusdt", "1m").unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn candles_round_trip_in_time_order() {
+        let (dir, store) = temp_store("round-trip");
+        assert!(store.load().unwrap().is_empty());
+
+        store.append(&[candle(120.0, 3.0), candle(180.0, 4.0)]).unwrap();
+        store.append(&[candle(0.0, 1.0), candle(60.0, 2.0)]).unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded, vec![candle(0.0, 1.0), candle(60.0, 2.0), candle(120.0, 3.0), candle(180.0, 4.0)]);
+        assert_eq!(CandleStore::open(&dir, "BTCUSDT", "1m").unwrap().load().unwrap(), loaded);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reload_keeps_the_last_write_and_compacts_the_file() {
+        let (dir, store) = temp_store("dedup");
+        store.append(&[candle(0.0, 1.0), candle(60.0, 2.0)]).unwrap();
+        store.append(&[candle(60.0, 5.0), candle(120.0, 3.0)]).unwrap();
+
+        // A torn trailing record is dropped too
+        let mut file = OpenOptions::new().append(true).open(&store.path).unwrap();
+        file.write_all(&[0u8; 10]).unwrap();
+        drop(file);
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded, vec![candle(0.0, 1.0), candle(60.0, 5.0), candle(120.0, 3.0)]);
+        assert_eq!(fs::metadata(&store.path).unwrap().len(), 3 * RECORD_SIZE as u64);
+        assert_eq!(store.load().unwrap(), loaded);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn load_before_pages_backwards() {
+        let (dir, store) = temp_store("paging");
+        let candles: Vec<CandleData> = (0..10).map(|i| candle(i as f64 * 60.0, i as f64)).collect();
+        store.append(&candles).unwrap();
+
+        assert_eq!(store.load_before(300.0, 3).unwrap(), candles[2..5].to_vec());
+        assert_eq!(store.load_before(120.0, 3).unwrap(), candles[0..2].to_vec());
+        assert!(store.load_before(0.0, 3).unwrap().is_empty());
+        assert_eq!(store.load_before(10000.0, 100).unwrap(), candles);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+use crate::exchange::Trade;
+use std::collections::VecDeque;
+
+// Taker volume on each side over a stretch of trades
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Flow {
+    pub buy: f64,
+    pub sell: f64,
+}
+
+impl Flow {
+    pub fn delta(&self) -> f64 {
+        self.buy - self.sell
+    }
+}
+
+// Recent trades for the time & sales list, plus enough history to total the
+// rolling buy/sell volume
+pub struct TradeTape {
+    trades: VecDeque<Trade>, // Oldest first
+    rows: usize,             // Always kept, however old
+    window: f64,             // Seconds of trades kept for the flow
+}
+
+impl TradeTape {
+    pub fn new(rows: usize, window: f64) -> Self {
+        Self {
+            trades: VecDeque::new(),
+            rows,
+            window,
+        }
+    }
+
+    pub fn push(&mut self, trade: Trade) {
+        let cutoff = trade.timestamp - self.window;
+        self.trades.push_back(trade);
+        while self.trades.len() > self.rows && self.trades.front().is_some_and(|oldest| oldest.timestamp < cutoff) {
+            self.trades.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.trades.clear();
+    }
+
+    // Newest first
+    pub fn latest(&self) -> impl Iterator<Item = &Trade> {
+        self.trades.iter().rev().take(self.rows)
+    }
+
+    // Taker volume within the window ending at the newest trade, so the
+    // exchange's clock is used rather than ours
+    pub fn flow(&self) -> Flow {
+        let Some(newest) = self.trades.back() else {
+            return Flow::default();
+        };
+        let cutoff = newest.timestamp - self.window;
+        self.trades
+            .iter()
+            .rev()
+            .take_while(|trade| trade.timestamp >= cutoff)
+            .fold(Flow::default(), |mut flow, trade| {
+                match trade.is_buyer_maker {
+                    true => flow.sell += trade.quantity,
+                    false => flow.buy += trade.quantity,
+                }
+                flow
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(timestamp: f64, quantity: f64, is_buyer_maker: bool) -> Trade {
+        Trade {
+            timestamp,
+            price: 100.0,
+            quantity,
+            is_buyer_maker,
+        }
+    }
+
+    #[test]
+    fn flow_totals_taker_sides_within_the_window() {
+        let mut tape = TradeTape::new(2, 60.0);
+        tape.push(trade(0.0, 5.0, false));
+        tape.push(trade(30.0, 1.0, false));
+        tape.push(trade(50.0, 2.0, true));
+        tape.push(trade(70.0, 0.5, false));
+
+        // The first trade is outside the window and no longer needed
+        assert_eq!(tape.flow(), Flow { buy: 1.5, sell: 2.0 });
+        assert_eq!(tape.flow().delta(), -0.5);
+        assert_eq!(tape.trades.len(), 3);
+
+        let latest: Vec<f64> = tape.latest().map(|trade| trade.timestamp).collect();
+        assert_eq!(latest, vec![70.0, 50.0]);
+
+        // The newest rows stay listed after the window has passed
+        tape.push(trade(500.0, 1.0, true));
+        assert_eq!(tape.flow(), Flow { buy: 0.0, sell: 1.0 });
+        assert_eq!(tape.latest().count(), 2);
+
+        tape.clear();
+        assert_eq!(tape.flow(), Flow::default());
+    }
+}
@@ -0,0 +1,453 @@
+use crate::indicators::{self, IndicatorConfig};
+use crate::{CandleData, Timeframe};
+use std::collections::VecDeque;
+
+// Candles of a longer `timeframe` built from shorter ones. The last candle is
+// still forming if its period has not ended yet.
+pub fn aggregate(candles: &VecDeque<CandleData>, timeframe: &Timeframe) -> VecDeque<CandleData> {
+    let mut aggregated: VecDeque<CandleData> = VecDeque::new();
+    for candle in candles {
+        fold(&mut aggregated, candle, timeframe);
+    }
+    aggregated
+}
+
+// Add the next shorter candle to the end of `aggregated`
+fn fold(aggregated: &mut VecDeque<CandleData>, candle: &CandleData, timeframe: &Timeframe) {
+    let open_time = timeframe.candle_open(candle.timestamp);
+    match aggregated.back_mut() {
+        Some(current) if current.timestamp == open_time => extend(current, candle),
+        _ => aggregated.push_back(CandleData {
+            timestamp: open_time,
+            ..candle.clone()
+        }),
+    }
+}
+
+// `aggregate` of a series that grows or updates at the end and is trimmed at
+// the front. Only the periods at either end are folded again when it changes.
+pub struct RollingAggregate {
+    timeframe: Timeframe,
+    candles: VecDeque<CandleData>,
+    source: Option<(usize, f64, CandleData)>, // Length, first timestamp and last candle last folded
+}
+
+impl RollingAggregate {
+    pub fn new(timeframe: Timeframe) -> Self {
+        Self { timeframe, candles: VecDeque::new(), source: None }
+    }
+
+    pub fn candles(&self) -> &VecDeque<CandleData> {
+        &self.candles
+    }
+
+    pub fn update(&mut self, source: &VecDeque<CandleData>) {
+        let (Some(first), Some(last)) = (source.front(), source.back()) else {
+            self.candles.clear();
+            self.source = None;
+            return;
+        };
+        let key = (source.len(), first.timestamp, last.clone());
+        if self.source.as_ref() == Some(&key) {
+            return;
+        }
+        let previous_first = self.source.replace(key).map(|(_, first, _)| first);
+        let first_open = self.timeframe.candle_open(first.timestamp);
+
+        // Older candles were added in front, or this is the first update
+        if previous_first.is_none() || self.candles.front().is_none_or(|front| first_open < front.timestamp) {
+            self.candles = aggregate(source, &self.timeframe);
+            return;
+        }
+
+        // Trimmed: drop the periods that have gone and refold the one now first
+        if previous_first != Some(first.timestamp) {
+            while self.candles.front().is_some_and(|candle| candle.timestamp <= first_open) {
+                self.candles.pop_front();
+            }
+            let mut front = VecDeque::new();
+            let period = source.iter().take_while(|candle| self.timeframe.candle_open(candle.timestamp) == first_open);
+            for candle in period {
+                fold(&mut front, candle, &self.timeframe);
+            }
+            if let Some(front) = front.pop_front() {
+                self.candles.push_front(front);
+            }
+        }
+
+        // The last period may have been updated, and new ones may follow it
+        let from = self.candles.pop_back().map_or(f64::MIN, |candle| candle.timestamp);
+        let start = source.partition_point(|candle| candle.timestamp < from);
+        for candle in source.range(start..) {
+            fold(&mut self.candles, candle, &self.timeframe);
+        }
+    }
+}
+
+// Like `aggregate`, but drops the first candle when the shorter candles start
+// partway through its period, as its open and volume would be wrong
+pub fn aggregate_complete(candles: &VecDeque<CandleData>, timeframe: &Timeframe) -> VecDeque<CandleData> {
+    let mut aggregated = aggregate(candles, timeframe);
+    if candles.front().is_some_and(|first| timeframe.candle_open(first.timestamp) != first.timestamp) {
+        aggregated.pop_front();
+    }
+    aggregated
+}
+
+// Add a later candle of the same period to `current`
+fn extend(current: &mut CandleData, candle: &CandleData) {
+    current.high = current.high.max(candle.high);
+    current.low = current.low.min(candle.low);
+    current.close = candle.close;
+    current.volume += candle.volume;
+}
+
+// Folds live updates of a shorter timeframe into the forming candle of a
+// longer one. Updates repeat the same open time while that candle forms.
+pub struct LiveAggregator {
+    timeframe: Timeframe,
+    finished: Option<CandleData>, // Shorter candles of the current period that have closed
+    forming: Option<CandleData>,  // Latest update of the shorter candle still forming
+}
+
+impl LiveAggregator {
+    pub fn new(timeframe: Timeframe) -> Self {
+        Self { timeframe, finished: None, forming: None }
+    }
+
+    // True until the first update, when the earlier part of its period has to
+    // come from `seed`
+    pub fn needs_seed(&self) -> bool {
+        self.finished.is_none() && self.forming.is_none()
+    }
+
+    // Fold in the closed shorter candles that precede `next` in its period
+    pub fn seed(&mut self, history: &[CandleData], next: &CandleData) {
+        let open_time = self.timeframe.candle_open(next.timestamp);
+        for candle in history {
+            if candle.timestamp >= open_time && candle.timestamp < next.timestamp {
+                self.finish(candle, open_time);
+            }
+        }
+    }
+
+    // The longer candle as of this update
+    pub fn update(&mut self, candle: CandleData) -> CandleData {
+        let open_time = self.timeframe.candle_open(candle.timestamp);
+        if let Some(previous) = self.forming.take() {
+            if previous.timestamp != candle.timestamp {
+                let previous_open = self.timeframe.candle_open(previous.timestamp);
+                self.finish(&previous, previous_open);
+            }
+        }
+        if self.finished.as_ref().is_some_and(|finished| finished.timestamp != open_time) {
+            self.finished = None;
+        }
+
+        let mut current = match &self.finished {
+            Some(finished) => finished.clone(),
+            None => CandleData { timestamp: open_time, ..candle.clone() },
+        };
+        if self.finished.is_some() {
+            extend(&mut current, &candle);
+        }
+        self.forming = Some(candle);
+        current
+    }
+
+    fn finish(&mut self, candle: &CandleData, open_time: f64) {
+        match &mut self.finished {
+            Some(finished) if finished.timestamp == open_time => extend(finished, candle),
+            _ => self.finished = Some(CandleData { timestamp: open_time, ..candle.clone() }),
+        }
+    }
+}
+
+// Heikin-Ashi candles: the close is the average of the raw OHLC and the open
+// is the middle of the previous Heikin-Ashi body
+pub fn heikin_ashi(candles: &VecDeque<CandleData>) -> VecDeque<CandleData> {
+    let mut transformed: VecDeque<CandleData> = VecDeque::with_capacity(candles.len());
+
+    for candle in candles {
+        let close = (candle.open + candle.high + candle.low + candle.close) / 4.0;
+        let open = match transformed.back() {
+            Some(previous) => (previous.open + previous.close) / 2.0,
+            None => (candle.open + candle.close) / 2.0,
+        };
+        transformed.push_back(CandleData {
+            timestamp: candle.timestamp,
+            open,
+            high: candle.high.max(open).max(close),
+            low: candle.low.min(open).min(close),
+            close,
+            volume: candle.volume,
+        });
+    }
+    transformed
+}
+
+// Renko brick height
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoxSize {
+    Fixed(f64),
+    Atr(usize), // Latest ATR over this many candles
+}
+
+impl BoxSize {
+    pub fn resolve(&self, candles: &VecDeque<CandleData>) -> Option<f64> {
+        let size = match *self {
+            BoxSize::Fixed(size) => Some(size),
+            BoxSize::Atr(period) => indicators::last_values(&IndicatorConfig::Atr { period }, candles)
+                .first()
+                .copied()
+                .flatten(),
+        };
+        size.filter(|size| *size > 0.0)
+    }
+}
+
+// Renko bricks from closes. A new brick starts at the top or bottom of the
+// last one, so continuing the trend takes one box of movement and a reversal
+// two. Bricks completed by the same candle are spread over its interval so
+// timestamps keep increasing, and share its volume.
+pub fn renko(candles: &VecDeque<CandleData>, box_size: f64) -> VecDeque<CandleData> {
+    let mut bricks = VecDeque::new();
+    let (mut bottom, mut top) = match candles.front() {
+        Some(first) if box_size > 0.0 => (first.close, first.close),
+        _ => return bricks,
+    };
+
+    for (index, candle) in candles.iter().enumerate() {
+        let mut bodies = Vec::new();
+        loop {
+            if candle.close >= top + box_size {
+                bodies.push((top, top + box_size));
+                bottom = top;
+                top += box_size;
+            } else if candle.close <= bottom - box_size {
+                bodies.push((bottom, bottom - box_size));
+                top = bottom;
+                bottom -= box_size;
+            } else {
+                break;
+            }
+        }
+
+        let interval = match (candles.get(index + 1), index.checked_sub(1).map(|i| &candles[i])) {
+            (Some(next), _) => next.timestamp - candle.timestamp,
+            (None, Some(previous)) => candle.timestamp - previous.timestamp,
+            (None, None) => 1.0,
+        };
+        let count = bodies.len() as f64;
+        for (i, (open, close)) in bodies.into_iter().enumerate() {
+            bricks.push_back(CandleData {
+                timestamp: candle.timestamp + interval * i as f64 / count,
+                open,
+                high: open.max(close),
+                low: open.min(close),
+                close,
+                volume: candle.volume / count,
+            });
+        }
+    }
+    bricks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(timestamp: f64, open: f64, high: f64, low: f64, close: f64) -> CandleData {
+        CandleData { timestamp, open, high, low, close, volume: 12.0 }
+    }
+
+    fn closes(closes: &[f64]) -> VecDeque<CandleData> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| candle(i as f64 * 60.0, close, close, close, close))
+            .collect()
+    }
+
+    #[test]
+    fn aggregates_into_longer_candles() {
+        // Seven 1m candles from 00:03: a partial first 5m candle, then a forming one
+        let candles: VecDeque<CandleData> = (3..10)
+            .map(|i| {
+                let price = 100.0 + i as f64;
+                candle(i as f64 * 60.0, price, price + 1.0, price - 1.0, price + 0.5)
+            })
+            .collect();
+        let aggregated = aggregate(&candles, &Timeframe::M5);
+
+        assert_eq!(aggregated.len(), 2);
+        assert_eq!(aggregated[0], CandleData { volume: 24.0, ..candle(0.0, 103.0, 105.0, 102.0, 104.5) });
+        assert_eq!(aggregated[1].timestamp, 300.0);
+        assert_eq!((aggregated[1].open, aggregated[1].close), (105.0, 109.5));
+        assert_eq!((aggregated[1].high, aggregated[1].low, aggregated[1].volume), (110.0, 104.0, 60.0));
+    }
+
+    #[test]
+    fn complete_aggregation_drops_a_partial_first_candle() {
+        // 10m from 5m candles starting at 00:05: the 00:00 candle is missing its
+        // first half, 00:10 is complete and 00:20 is still forming
+        let candles: VecDeque<CandleData> = (1..5)
+            .map(|i| candle(i as f64 * 300.0, i as f64, i as f64 + 0.5, i as f64 - 0.5, i as f64))
+            .collect();
+        let aggregated = aggregate_complete(&candles, &Timeframe::Custom(10));
+
+        assert_eq!(aggregated.len(), 2);
+        assert_eq!(aggregated[0], CandleData { volume: 24.0, ..candle(600.0, 2.0, 3.5, 1.5, 3.0) });
+        assert_eq!(aggregated[1], candle(1200.0, 4.0, 4.5, 3.5, 4.0));
+
+        // Aligned input keeps its first candle
+        assert_eq!(aggregate_complete(&candles.range(1..).cloned().collect(), &Timeframe::Custom(10)).len(), 2);
+    }
+
+    #[test]
+    fn custom_timeframes_build_on_native_ones() {
+        let bases: Vec<Option<Timeframe>> = [2, 10, 45, 120, 360, 3 * 1440]
+            .iter()
+            .map(|&minutes| Timeframe::Custom(minutes).native_base())
+            .collect();
+        assert_eq!(
+            bases,
+            vec![
+                Some(Timeframe::M1),
+                Some(Timeframe::M5),
+                Some(Timeframe::M15),
+                Some(Timeframe::H1),
+                Some(Timeframe::H1),
+                Some(Timeframe::D1),
+            ]
+        );
+        assert_eq!(Timeframe::H4.native_base(), None);
+        assert_eq!(Timeframe::Custom(120).to_display_string(), "2h");
+        assert_eq!(Timeframe::Custom(3 * 1440).to_api_string(), "3d");
+        assert_eq!(Timeframe::Custom(360).candle_open(7.5 * 3600.0), 6.0 * 3600.0);
+    }
+
+    #[test]
+    fn live_updates_fold_into_the_forming_candle() {
+        let mut aggregator = LiveAggregator::new(Timeframe::Custom(2));
+        assert!(aggregator.needs_seed());
+
+        // Joining during 00:01; the closed 00:00 minute comes from history
+        let first = candle(60.0, 11.0, 12.0, 10.0, 11.5);
+        aggregator.seed(&[candle(0.0, 10.0, 13.0, 9.0, 11.0), first.clone()], &first);
+        assert_eq!(aggregator.update(first), CandleData { volume: 24.0, ..candle(0.0, 10.0, 13.0, 9.0, 11.5) });
+        assert!(!aggregator.needs_seed());
+
+        // A repeated update replaces the forming minute rather than adding to it
+        let updated = aggregator.update(candle(60.0, 11.0, 14.0, 10.0, 13.0));
+        assert_eq!(updated, CandleData { volume: 24.0, ..candle(0.0, 10.0, 14.0, 9.0, 13.0) });
+
+        // The next minute starts a new 2m candle
+        let next = aggregator.update(candle(120.0, 13.0, 13.5, 12.5, 13.2));
+        assert_eq!(next, candle(120.0, 13.0, 13.5, 12.5, 13.2));
+        let after = aggregator.update(candle(180.0, 13.2, 15.0, 13.0, 14.0));
+        assert_eq!(after, CandleData { volume: 24.0, ..candle(120.0, 13.0, 15.0, 12.5, 14.0) });
+    }
+
+    #[test]
+    fn weeks_and_months_follow_the_calendar() {
+        // 2024-01-03 (a Wednesday) 12:00 UTC
+        let wednesday = 1_704_283_200.0;
+        assert_eq!(Timeframe::W1.candle_open(wednesday), 1_704_067_200.0); // Monday 2024-01-01
+        assert_eq!(Timeframe::MN1.candle_open(wednesday), 1_704_067_200.0);
+        assert_eq!(Timeframe::MN1.candle_close(1_704_067_200.0), 1_706_745_600.0); // 2024-02-01
+        assert_eq!(Timeframe::MN1.candle_close(1_706_745_600.0), 1_709_251_200.0); // 2024-03-01, leap year
+
+        let days: VecDeque<CandleData> = (0..60)
+            .map(|day| candle(1_704_067_200.0 + day as f64 * 86400.0, 1.0, 1.0, 1.0, 1.0))
+            .collect();
+        let months = aggregate(&days, &Timeframe::MN1);
+        let volumes: Vec<f64> = months.iter().map(|month| month.volume).collect();
+        assert_eq!(volumes, vec![31.0 * 12.0, 29.0 * 12.0]);
+    }
+
+    #[test]
+    fn rolling_aggregate_matches_a_full_rebuild() {
+        let mut rolling = RollingAggregate::new(Timeframe::M5);
+        let mut candles: VecDeque<CandleData> = VecDeque::new();
+        let mut check = |candles: &VecDeque<CandleData>| {
+            rolling.update(candles);
+            assert_eq!(*rolling.candles(), aggregate(candles, &Timeframe::M5));
+        };
+
+        for i in 3..14 {
+            let price = 100.0 + i as f64;
+            candles.push_back(candle(i as f64 * 60.0, price, price + 1.0, price - 1.0, price + 0.5));
+            check(&candles);
+        }
+
+        // The forming candle updates, then the oldest are trimmed partway
+        // through a period and in whole periods
+        candles.back_mut().unwrap().close = 90.0;
+        check(&candles);
+        candles.drain(..4);
+        check(&candles);
+        candles.drain(..5);
+        check(&candles);
+
+        // Older history goes in front
+        candles.push_front(candle(0.0, 1.0, 2.0, 0.5, 1.5));
+        check(&candles);
+        candles.clear();
+        check(&candles);
+    }
+
+    #[test]
+    fn heikin_ashi_averages_prices() {
+        let candles: VecDeque<CandleData> = [
+            candle(0.0, 10.0, 14.0, 8.0, 12.0),
+            candle(60.0, 12.0, 13.0, 9.0, 10.0),
+        ]
+        .into_iter()
+        .collect();
+        let transformed = heikin_ashi(&candles);
+
+        assert_eq!(transformed[0].open, 11.0);
+        assert_eq!(transformed[0].close, 11.0);
+        assert_eq!((transformed[0].high, transformed[0].low), (14.0, 8.0));
+
+        // Opens at the middle of the previous Heikin-Ashi body
+        assert_eq!(transformed[1].open, 11.0);
+        assert_eq!(transformed[1].close, 11.0);
+        assert_eq!((transformed[1].high, transformed[1].low), (13.0, 9.0));
+
+        // The raw candles are left alone
+        assert_eq!(candles[1].open, 12.0);
+    }
+
+    #[test]
+    fn renko_needs_two_boxes_to_reverse() {
+        let bricks = renko(&closes(&[100.0, 101.5, 102.0, 101.0, 100.5, 99.9]), 1.0);
+        let bodies: Vec<(f64, f64)> = bricks.iter().map(|brick| (brick.open, brick.close)).collect();
+
+        // Up to 101 and 102; 101 and 100.5 are within two boxes of the top, 99.9 is not
+        assert_eq!(bodies, vec![(100.0, 101.0), (101.0, 102.0), (101.0, 100.0)]);
+        assert_eq!(bricks[2].timestamp, 5.0 * 60.0);
+    }
+
+    #[test]
+    fn bricks_from_one_candle_share_its_interval() {
+        let bricks = renko(&closes(&[100.0, 103.0, 103.0]), 1.0);
+
+        let times: Vec<f64> = bricks.iter().map(|brick| brick.timestamp).collect();
+        assert_eq!(times, vec![60.0, 80.0, 100.0]);
+        assert!(bricks.iter().all(|brick| brick.volume == 4.0));
+    }
+
+    #[test]
+    fn box_size_from_atr() {
+        // Every candle is 2 points tall with no gaps
+        let candles: VecDeque<CandleData> =
+            (0..20).map(|i| candle(i as f64 * 60.0, 100.0, 101.0, 99.0, 100.0)).collect();
+
+        assert_eq!(BoxSize::Atr(14).resolve(&candles), Some(2.0));
+        assert_eq!(BoxSize::Atr(30).resolve(&candles), None);
+        assert_eq!(BoxSize::Fixed(0.0).resolve(&candles), None);
+        assert!(renko(&candles, 0.0).is_empty());
+    }
+}
@@ -1,5 +1,6 @@
+use crate::indicators::{IndicatorConfig, IndicatorInstance};
 use crate::utils::constant as uc;
-use crate::{CandleType, Candlestick, Chart, ChartState};
+use crate::{CandleData, CandleType, Candlestick, Chart, ChartState};
 use iced::{
     mouse,
     widget::{
@@ -27,6 +28,7 @@ pub fn calculate_knn_signals(
         return (buy_signals, sell_signals);
     }
 
+    let rsi_values = calculate_rsi(candlesticks, 14);
     for i in window_size..data.len() {
         let (timestamp, candle) = data[i];
         let window = &data[i - window_size..i];
@@ -41,35 +43,8 @@ pub fn calculate_knn_signals(
             / 5.0;
         let ma20: f32 = window.iter().map(|(_, c)| c.close).sum::<f32>() / window_size as f32;
 
-        // RSI 계산
-        let price_changes: Vec<f32> = window
-            .windows(2)
-            .map(|w| {
-                let (_, prev) = w[0];
-                let (_, curr) = w[1];
-                curr.close - prev.close
-            })
-            .collect();
-
-        let (gains, losses): (Vec<f32>, Vec<f32>) = price_changes
-            .iter()
-            .map(|&change| {
-                if change > 0.0 {
-                    (change, 0.0)
-                } else {
-                    (0.0, -change)
-                }
-            })
-            .unzip();
-
-        let avg_gain = gains.iter().sum::<f32>() / gains.len() as f32;
-        let avg_loss = losses.iter().sum::<f32>() / losses.len() as f32;
-        let rs = if avg_loss == 0.0 {
-            100.0
-        } else {
-            avg_gain / avg_loss
-        };
-        let rsi = 100.0 - (100.0 / (1.0 + rs));
+        // RSI as of the window's last candle
+        let rsi = rsi_values.get(window[window_size - 1].0).copied().unwrap_or(50.0);
 
         // 볼륨 분석
         let avg_volume = window.iter().map(|(_, c)| c.volume).sum::<f32>() / window_size as f32;
@@ -126,52 +101,30 @@ pub fn calculate_knn_signals(
 
     (buy_signals, sell_signals)
 }
+// Wilder RSI from the shared indicator module, keyed like the candles
 pub fn calculate_rsi(
     candlesticks: &BTreeMap<u64, Candlestick>,
     period: usize,
 ) -> BTreeMap<u64, f32> {
-    let mut rsi_values = BTreeMap::new();
-    if candlesticks.len() < period + 1 {
-        return rsi_values;
-    }
-
-    let mut gains = Vec::new();
-    let mut losses = Vec::new();
-    let mut prev_close = None;
-    let mut timestamps = Vec::new();
-
-    // 가격 변화 계산
-    for (timestamp, candle) in candlesticks.iter() {
-        if let Some(prev) = prev_close {
-            let change = candle.close - prev;
-            timestamps.push(*timestamp);
-            if change >= 0.0 {
-                gains.push(change);
-                losses.push(0.0);
-            } else {
-                gains.push(0.0);
-                losses.push(-change);
-            }
-        }
-        prev_close = Some(candle.close);
-    }
-
-    // RSI 계산
-    for i in period..timestamps.len() {
-        let avg_gain: f32 = gains[i - period..i].iter().sum::<f32>() / period as f32;
-        let avg_loss: f32 = losses[i - period..i].iter().sum::<f32>() / period as f32;
-
-        let rs = if avg_loss == 0.0 {
-            100.0
-        } else {
-            avg_gain / avg_loss
-        };
-
-        let rsi = 100.0 - (100.0 / (1.0 + rs));
-        rsi_values.insert(timestamps[i], rsi);
-    }
-
-    rsi_values
+    let candles: VecDeque<CandleData> = candlesticks
+        .iter()
+        .map(|(timestamp, candle)| CandleData {
+            timestamp: *timestamp as f64,
+            open: candle.open as f64,
+            high: candle.high as f64,
+            low: candle.low as f64,
+            close: candle.close as f64,
+            volume: candle.volume as f64,
+        })
+        .collect();
+
+    let mut rsi = IndicatorInstance::new(0, IndicatorConfig::Rsi { period });
+    rsi.sync(&candles);
+    rsi.visible_series(f64::MIN, f64::MAX)
+        .into_iter()
+        .flat_map(|series| series.points)
+        .map(|(timestamp, value)| (timestamp as u64, value as f32))
+        .collect()
 }
 
 pub fn calculate_moving_average(